@@ -0,0 +1,101 @@
+#!/usr/bin/env python3
+"""Regenerate data/cities.tsv, the city database embedded with the `city-db`
+feature, from GeoNames (https://www.geonames.org, CC BY 4.0).
+
+    python3 scripts/build_citydb.py [--min-population 500000]
+
+Downloads cities15000.zip and admin1CodesASCII.txt and keeps the cities with
+at least the given population, and the smaller ones in KEEP that stand for a
+timezone or are asked for often, most populous first. Rebuild the binary
+afterwards; the table is compiled in.
+"""
+
+import argparse
+import csv
+import io
+import urllib.request
+import zipfile
+from pathlib import Path
+
+BASE = "https://download.geonames.org/export/dump/"
+OUTPUT = Path(__file__).resolve().parent.parent / "data" / "cities.tsv"
+# Below the population cutoff but kept: (asciiname, country, region)
+KEEP = {
+    ("Anchorage", "US", "Alaska"),
+    ("Apia", "WS", "Tuamasaga"),
+    ("Atlanta", "US", "Georgia"),
+    ("Cordoba", "ES", "Andalusia"),
+    ("Darwin", "AU", "Northern Territory"),
+    ("Doha", "QA", "Baladīyat ad Dawḩah"),
+    ("Edinburgh", "GB", "Scotland"),
+    ("Gdansk", "PL", "Pomerania"),
+    ("Geneva", "CH", "Geneva"),
+    ("Halifax", "CA", "Nova Scotia"),
+    ("Hobart", "AU", "Tasmania"),
+    ("Honolulu", "US", "Hawaii"),
+    ("Kiritimati", "KI", "Line Islands"),
+    ("Kuwait City", "KW", "Al Asimah"),
+    ("London", "CA", "Ontario"),
+    ("Manchester", "GB", "England"),
+    ("Miami", "US", "Florida"),
+    ("Minneapolis", "US", "Minnesota"),
+    ("Noumea", "NC", "South Province"),
+    ("Panama City", "PA", "Panamá"),
+    ("Port Moresby", "PG", "National Capital"),
+    ("Portland", "US", "Maine"),
+    ("Porto", "PT", "Porto"),
+    ("Reykjavik", "IS", "Capital Region"),
+    ("Salt Lake City", "US", "Utah"),
+    ("San Jose", "CR", "San José"),
+    ("St. John's", "CA", "Newfoundland and Labrador"),
+    ("Suva", "FJ", "Central"),
+    ("Tallinn", "EE", "Harjumaa"),
+    ("Tel Aviv", "IL", "Tel Aviv"),
+    ("Wellington", "NZ", "Wellington"),
+    ("Zurich", "CH", "Zurich"),
+}
+HEADER = "# name\tascii_name\tregion\tcountry\tlatitude\tlongitude\tpopulation\ttimezone\n"
+
+
+def fetch(name):
+    with urllib.request.urlopen(BASE + name) as response:
+        return response.read()
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    parser.add_argument("--min-population", type=int, default=500_000)
+    args = parser.parse_args()
+
+    regions = {}
+    for line in fetch("admin1CodesASCII.txt").decode("utf-8").splitlines():
+        code, name, *_ = line.split("\t")
+        regions[code] = name
+
+    archive = zipfile.ZipFile(io.BytesIO(fetch("cities15000.zip")))
+    rows = []
+    with archive.open("cities15000.txt") as file:
+        lines = io.TextIOWrapper(file, encoding="utf-8")
+        for record in csv.reader(lines, delimiter="\t", quoting=csv.QUOTE_NONE):
+            name, ascii_name = record[1], record[2]
+            latitude, longitude = record[4], record[5]
+            country, admin1 = record[8], record[10]
+            population, timezone = int(record[14] or 0), record[17]
+            region = regions.get(f"{country}.{admin1}", "")
+            kept = (ascii_name, country, region) in KEEP
+            if (population < args.min_population and not kept) or not timezone:
+                continue
+            rows.append(
+                (name, ascii_name, region, country, latitude, longitude, population, timezone)
+            )
+
+    rows.sort(key=lambda row: -row[6])
+    with OUTPUT.open("w", encoding="utf-8") as out:
+        out.write(HEADER)
+        for row in rows:
+            out.write("\t".join(str(field) for field in row) + "\n")
+    print(f"Wrote {len(rows)} cities to {OUTPUT}")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,44 @@
+#!/usr/bin/env python3
+"""Regenerate data/zone_countries.tsv, the country of each timezone used for
+city flags, from the tz database (public domain).
+
+    python3 scripts/build_zone_countries.py [--zoneinfo /usr/share/zoneinfo]
+
+Reads zone.tab for the country of each zone and tzdata.zi for the links
+(`US/Pacific` is `America/Los_Angeles`), so that old names get a flag too.
+Rebuild the binary afterwards; the table is compiled in.
+"""
+
+import argparse
+from pathlib import Path
+
+OUTPUT = Path(__file__).resolve().parent.parent / "data" / "zone_countries.tsv"
+HEADER = "# timezone\tcountry\n"
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    parser.add_argument("--zoneinfo", type=Path, default=Path("/usr/share/zoneinfo"))
+    args = parser.parse_args()
+
+    countries = {}
+    for line in (args.zoneinfo / "zone.tab").read_text(encoding="utf-8").splitlines():
+        if line.startswith("#") or not line.strip():
+            continue
+        country, _, zone, *_ = line.split("\t")
+        countries[zone] = country
+
+    for line in (args.zoneinfo / "tzdata.zi").read_text(encoding="utf-8").splitlines():
+        fields = line.split()
+        if len(fields) == 3 and fields[0] == "L" and fields[1] in countries:
+            countries.setdefault(fields[2], countries[fields[1]])
+
+    with OUTPUT.open("w", encoding="utf-8") as out:
+        out.write(HEADER)
+        for zone in sorted(countries):
+            out.write(f"{zone}\t{countries[zone]}\n")
+    print(f"Wrote {len(countries)} zones to {OUTPUT}")
+
+
+if __name__ == "__main__":
+    main()
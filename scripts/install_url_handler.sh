@@ -0,0 +1,40 @@
+#!/bin/sh
+# Register swiftbarclocks:// URLs on macOS, for Shortcuts, Keyboard Maestro
+# and anything else that can open a URL:
+#
+#     scripts/install_url_handler.sh [BINARY] [CONFIG]
+#
+# Builds a small AppleScript app in ~/Applications that passes each URL to
+# `swiftbar_clocks callback url`, and registers it with Launch Services.
+# BINARY defaults to swiftbar_clocks on the PATH, CONFIG to the default config.
+set -eu
+
+BINARY=${1:-$(command -v swiftbar_clocks)}
+CONFIG=${2:-}
+APP="$HOME/Applications/SwiftBar Clocks URL Handler.app"
+PLIST="$APP/Contents/Info.plist"
+
+COMMAND="quoted form of \"$BINARY\""
+if [ -n "$CONFIG" ]; then
+    COMMAND="$COMMAND & \" --config \" & quoted form of \"$CONFIG\""
+fi
+
+mkdir -p "$HOME/Applications"
+rm -rf "$APP"
+osacompile -o "$APP" \
+    -e 'on open location this_URL' \
+    -e "do shell script $COMMAND & \" callback url \" & quoted form of this_URL" \
+    -e 'end open location'
+
+/usr/libexec/PlistBuddy \
+    -c 'Set :CFBundleIdentifier io.github.jakub-m.swiftbar-clocks.url-handler' \
+    -c 'Add :LSBackgroundOnly bool true' \
+    -c 'Add :CFBundleURLTypes array' \
+    -c 'Add :CFBundleURLTypes:0 dict' \
+    -c 'Add :CFBundleURLTypes:0:CFBundleURLName string SwiftBar Clocks' \
+    -c 'Add :CFBundleURLTypes:0:CFBundleURLSchemes array' \
+    -c 'Add :CFBundleURLTypes:0:CFBundleURLSchemes:0 string swiftbarclocks' \
+    "$PLIST"
+
+/System/Library/Frameworks/CoreServices.framework/Frameworks/LaunchServices.framework/Support/lsregister -f "$APP"
+echo "Registered swiftbarclocks:// with $APP"
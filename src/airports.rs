@@ -0,0 +1,93 @@
+//! IATA codes of the busiest airports and the timezones they are in, for
+//! `flight`. Not exhaustive: any other airport can be given as a timezone.
+
+use chrono_tz::Tz;
+
+/// Code, city and timezone, sorted by code
+const AIRPORTS: &[(&str, &str, Tz)] = &[
+    ("ADD", "Addis Ababa", Tz::Africa__Addis_Ababa),
+    ("AKL", "Auckland", Tz::Pacific__Auckland),
+    ("AMS", "Amsterdam", Tz::Europe__Amsterdam),
+    ("ARN", "Stockholm", Tz::Europe__Stockholm),
+    ("ATH", "Athens", Tz::Europe__Athens),
+    ("ATL", "Atlanta", Tz::America__New_York),
+    ("AUH", "Abu Dhabi", Tz::Asia__Dubai),
+    ("BCN", "Barcelona", Tz::Europe__Madrid),
+    ("BKK", "Bangkok", Tz::Asia__Bangkok),
+    ("BLR", "Bengaluru", Tz::Asia__Kolkata),
+    ("BOG", "Bogotá", Tz::America__Bogota),
+    ("BOM", "Mumbai", Tz::Asia__Kolkata),
+    ("BOS", "Boston", Tz::America__New_York),
+    ("BRU", "Brussels", Tz::Europe__Brussels),
+    ("CAI", "Cairo", Tz::Africa__Cairo),
+    ("CDG", "Paris", Tz::Europe__Paris),
+    ("CGK", "Jakarta", Tz::Asia__Jakarta),
+    ("CPH", "Copenhagen", Tz::Europe__Copenhagen),
+    ("CPT", "Cape Town", Tz::Africa__Johannesburg),
+    ("DEL", "Delhi", Tz::Asia__Kolkata),
+    ("DEN", "Denver", Tz::America__Denver),
+    ("DFW", "Dallas", Tz::America__Chicago),
+    ("DOH", "Doha", Tz::Asia__Qatar),
+    ("DUB", "Dublin", Tz::Europe__Dublin),
+    ("DXB", "Dubai", Tz::Asia__Dubai),
+    ("EWR", "Newark", Tz::America__New_York),
+    ("EZE", "Buenos Aires", Tz::America__Argentina__Buenos_Aires),
+    ("FCO", "Rome", Tz::Europe__Rome),
+    ("FRA", "Frankfurt", Tz::Europe__Berlin),
+    ("GDN", "Gdańsk", Tz::Europe__Warsaw),
+    ("GRU", "São Paulo", Tz::America__Sao_Paulo),
+    ("HEL", "Helsinki", Tz::Europe__Helsinki),
+    ("HKG", "Hong Kong", Tz::Asia__Hong_Kong),
+    ("HND", "Tokyo Haneda", Tz::Asia__Tokyo),
+    ("HNL", "Honolulu", Tz::Pacific__Honolulu),
+    ("IAD", "Washington Dulles", Tz::America__New_York),
+    ("IAH", "Houston", Tz::America__Chicago),
+    ("ICN", "Seoul", Tz::Asia__Seoul),
+    ("IST", "Istanbul", Tz::Europe__Istanbul),
+    ("JFK", "New York JFK", Tz::America__New_York),
+    ("JNB", "Johannesburg", Tz::Africa__Johannesburg),
+    ("KUL", "Kuala Lumpur", Tz::Asia__Kuala_Lumpur),
+    ("LAS", "Las Vegas", Tz::America__Los_Angeles),
+    ("LAX", "Los Angeles", Tz::America__Los_Angeles),
+    ("LGW", "London Gatwick", Tz::Europe__London),
+    ("LHR", "London Heathrow", Tz::Europe__London),
+    ("LIM", "Lima", Tz::America__Lima),
+    ("LIS", "Lisbon", Tz::Europe__Lisbon),
+    ("MAD", "Madrid", Tz::Europe__Madrid),
+    ("MAN", "Manchester", Tz::Europe__London),
+    ("MEL", "Melbourne", Tz::Australia__Melbourne),
+    ("MEX", "Mexico City", Tz::America__Mexico_City),
+    ("MIA", "Miami", Tz::America__New_York),
+    ("MNL", "Manila", Tz::Asia__Manila),
+    ("MUC", "Munich", Tz::Europe__Berlin),
+    ("NBO", "Nairobi", Tz::Africa__Nairobi),
+    ("NRT", "Tokyo Narita", Tz::Asia__Tokyo),
+    ("ORD", "Chicago O'Hare", Tz::America__Chicago),
+    ("OSL", "Oslo", Tz::Europe__Oslo),
+    ("PEK", "Beijing", Tz::Asia__Shanghai),
+    ("PHX", "Phoenix", Tz::America__Phoenix),
+    ("PVG", "Shanghai", Tz::Asia__Shanghai),
+    ("SCL", "Santiago", Tz::America__Santiago),
+    ("SEA", "Seattle", Tz::America__Los_Angeles),
+    ("SFO", "San Francisco", Tz::America__Los_Angeles),
+    ("SIN", "Singapore", Tz::Asia__Singapore),
+    ("SYD", "Sydney", Tz::Australia__Sydney),
+    ("SZX", "Shenzhen", Tz::Asia__Shanghai),
+    ("TLV", "Tel Aviv", Tz::Asia__Jerusalem),
+    ("TPE", "Taipei", Tz::Asia__Taipei),
+    ("VIE", "Vienna", Tz::Europe__Vienna),
+    ("WAW", "Warsaw", Tz::Europe__Warsaw),
+    ("YUL", "Montréal", Tz::America__Toronto),
+    ("YVR", "Vancouver", Tz::America__Vancouver),
+    ("YYZ", "Toronto", Tz::America__Toronto),
+    ("ZRH", "Zurich", Tz::Europe__Zurich),
+];
+
+/// City and timezone of an airport code, in any case
+pub fn lookup(code: &str) -> Option<(&'static str, Tz)> {
+    let code = code.to_ascii_uppercase();
+    AIRPORTS
+        .binary_search_by(|(known, _, _)| known.cmp(&code.as_str()))
+        .ok()
+        .map(|index| (AIRPORTS[index].1, AIRPORTS[index].2))
+}
@@ -0,0 +1,62 @@
+//! What the menu wants acted on, gathered once per render: upcoming DST
+//! changes and notifications held back by quiet hours. `alert_badge` counts
+//! them on the menu bar title.
+
+use crate::{Config, dst, notify};
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+
+pub struct Alerts {
+    /// DST changes in the configured zones within `dst_warning_days`
+    pub dst: Vec<dst::Change>,
+    /// Notifications waiting for quiet hours to end
+    pub deferred: usize,
+}
+
+impl Alerts {
+    pub fn collect(config: &Config, now: DateTime<Local>) -> Alerts {
+        let dst = match config.dst_warning_days {
+            Some(days) => {
+                let mut zones: Vec<Tz> = config
+                    .cities
+                    .iter()
+                    .filter_map(|city| config.timezone(&city.timezone).ok())
+                    .collect();
+                zones.sort_by_key(|tz| tz.name());
+                zones.dedup();
+                dst::upcoming(&zones, now.with_timezone(&Utc), days)
+            }
+            None => Vec::new(),
+        };
+        let deferred = match notify::quiet_hours(config, now) {
+            Some(_) => config.state.deferred.len(),
+            None => 0,
+        };
+        Alerts { dst, deferred }
+    }
+
+    /// Pending alerts; like in the menu, zones changing at the same moment to
+    /// the same abbreviation count once
+    pub fn count(&self) -> usize {
+        let mut lines: Vec<(DateTime<Utc>, String)> = Vec::new();
+        for change in &self.dst {
+            let line = (change.at, change.abbreviation());
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+        lines.len() + self.deferred
+    }
+}
+
+/// ` •` for one alert, ` •3` for more, after the menu bar title
+pub fn write_badge(output: &mut String, count: usize) {
+    match count {
+        0 => {}
+        1 => output.push_str(" •"),
+        count => {
+            output.push_str(" •");
+            output.push_str(&count.to_string());
+        }
+    }
+}
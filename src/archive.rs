@@ -0,0 +1,143 @@
+//! `--archive daily`: a JSON line per day with every city's time and UTC
+//! offset, appended on the first menu refresh of the day, so that the offsets
+//! of a past day can be looked up with `archive <date>` instead of working
+//! out DST history by hand.
+//!
+//! The log is `swiftbar_clock_config.archive.jsonl` next to the config (in
+//! the cache directory for remote configs) unless `--archive-file` says
+//! otherwise.
+
+use crate::render::{base_offset_secs, format_diff, format_utc_offset};
+use crate::{Config, cache, remote, resolve_config_path};
+use chrono::{DateTime, Local, NaiveDate, Offset, SecondsFormat, SubsecRound, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Period {
+    /// Once per local day
+    Daily,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    /// Local date the entry is for
+    date: NaiveDate,
+    taken: DateTime<Utc>,
+    cities: Vec<City>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct City {
+    name: String,
+    timezone: String,
+    /// Local time in the city, RFC 3339
+    time: String,
+    utc_offset_secs: i32,
+    abbreviation: String,
+}
+
+/// Where the archive of the config at `config` goes by default
+pub fn default_path(config: &str) -> Option<PathBuf> {
+    if remote::is_url(config) {
+        return Some(cache::dir()?.join("archive.jsonl"));
+    }
+    Some(
+        resolve_config_path(config)
+            .ok()?
+            .with_extension("archive.jsonl"),
+    )
+}
+
+/// Append today's entry to `path` unless it has one; problems are warnings,
+/// the menu renders regardless
+pub fn record(config: &Config, path: &Path, now: DateTime<Local>) {
+    // The last write tells whether today is done without reading the log
+    let written = fs::metadata(path).and_then(|metadata| metadata.modified());
+    if let Ok(written) = written
+        && DateTime::<Local>::from(written).date_naive() == now.date_naive()
+    {
+        return;
+    }
+    if let Err(err) = append(config, path, now) {
+        eprintln!("Warning: Cannot archive to {}: {}", path.display(), err);
+    }
+}
+
+fn append(config: &Config, path: &Path, now: DateTime<Local>) -> Result<(), String> {
+    let cities = config
+        .cities
+        .iter()
+        .filter_map(|city| {
+            let time = now.with_timezone(&config.timezone(&city.timezone).ok()?);
+            Some(City {
+                name: city.name.clone(),
+                timezone: time.timezone().name().to_string(),
+                time: time.to_rfc3339_opts(SecondsFormat::Secs, false),
+                utc_offset_secs: time.offset().fix().local_minus_utc(),
+                abbreviation: time.format("%Z").to_string(),
+            })
+        })
+        .collect();
+    let entry = Entry {
+        date: now.date_naive(),
+        taken: now.with_timezone(&Utc).trunc_subsecs(0),
+        cities,
+    };
+    let line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+/// The archived offsets of `date`, each relative to the home city (or the
+/// local offset, as today)
+pub fn show(config: &Config, path: &Path, date: NaiveDate) -> Result<String, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let entry = content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Entry>(line).ok())
+        .find(|entry| entry.date == date)
+        .ok_or_else(|| format!("nothing archived for {} in {}", date, path.display()))?;
+
+    // Relative to the home city as archived, if it was
+    let base = config
+        .cities
+        .iter()
+        .find(|city| city.role == Some(crate::Role::Home))
+        .and_then(|home| entry.cities.iter().find(|city| city.name == home.name))
+        .map_or_else(
+            || base_offset_secs(config, entry.taken.with_timezone(&Local)),
+            |home| home.utc_offset_secs,
+        );
+    let width = entry
+        .cities
+        .iter()
+        .map(|city| city.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut output = format!(
+        "{} (recorded {})\n",
+        date.format("%a %Y-%m-%d"),
+        entry.taken.format("%H:%M UTC")
+    );
+    for city in &entry.cities {
+        output.push_str(&format!(
+            "{:<width$}  {} {}{}\n",
+            city.name,
+            format_utc_offset(city.utc_offset_secs),
+            city.abbreviation,
+            format_diff(city.utc_offset_secs - base)
+        ));
+    }
+    Ok(output)
+}
@@ -0,0 +1,91 @@
+//! Timestamped snapshots of the config (and state) files.
+//!
+//! Every programmatic edit should call [`backup`] first, so a bad automated
+//! edit can always be undone with `config restore`.
+
+use chrono::Local;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// A file on disk and the machine-independent name it is saved under
+pub struct ManagedFile {
+    pub name: &'static str,
+    pub path: PathBuf,
+}
+
+/// Directory holding one subdirectory per backup, next to the config file
+fn backups_dir(config_path: &Path) -> PathBuf {
+    let mut name = config_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(".backups");
+    config_path.with_file_name(name)
+}
+
+/// Copy the existing `files` into a new timestamped backup, returning its directory
+pub fn backup(config_path: &Path, files: &[ManagedFile]) -> io::Result<PathBuf> {
+    let timestamp = Local::now().format(TIMESTAMP_FORMAT).to_string();
+    let mut dir = backups_dir(config_path).join(&timestamp);
+    // Never overwrite a backup taken within the same second
+    let mut suffix = 1;
+    while dir.exists() {
+        dir = backups_dir(config_path).join(format!("{}-{}", timestamp, suffix));
+        suffix += 1;
+    }
+    fs::create_dir_all(&dir)?;
+
+    for file in files.iter().filter(|f| f.path.exists()) {
+        fs::copy(&file.path, dir.join(file.name))?;
+    }
+    Ok(dir)
+}
+
+/// Names of the available backups, oldest first
+pub fn list(config_path: &Path) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(backups_dir(config_path))? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Restore `files` from the named backup, or the latest one if `name` is `None`
+///
+/// The current files are backed up first, so a restore can be undone as well.
+pub fn restore(
+    config_path: &Path,
+    files: &[ManagedFile],
+    name: Option<&str>,
+) -> io::Result<String> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => list(config_path)?
+            .pop()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no backups found"))?,
+    };
+
+    let dir = backups_dir(config_path).join(&name);
+    if !dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no backup named {}", name),
+        ));
+    }
+
+    backup(config_path, files)?;
+    for file in files {
+        let saved = dir.join(file.name);
+        if saved.exists() {
+            fs::copy(saved, &file.path)?;
+        }
+    }
+    Ok(name)
+}
@@ -0,0 +1,45 @@
+//! A five-row block font for digits, used for the large clocks of the TUI and
+//! the `bigtime` format.
+
+const HEIGHT: usize = 5;
+
+fn glyph(c: char) -> [&'static str; HEIGHT] {
+    match c {
+        '0' => ["###", "# #", "# #", "# #", "###"],
+        '1' => ["  #", "  #", "  #", "  #", "  #"],
+        '2' => ["###", "  #", "###", "#  ", "###"],
+        '3' => ["###", "  #", "###", "  #", "###"],
+        '4' => ["# #", "# #", "###", "  #", "  #"],
+        '5' => ["###", "#  ", "###", "  #", "###"],
+        '6' => ["###", "#  ", "###", "# #", "###"],
+        '7' => ["###", "  #", "  #", "  #", "  #"],
+        '8' => ["###", "# #", "###", "# #", "###"],
+        '9' => ["###", "# #", "###", "  #", "###"],
+        ':' => [" ", "#", " ", "#", " "],
+        _ => ["   ", "   ", "   ", "   ", "   "],
+    }
+}
+
+/// Render `text` as rows of big characters drawn with `fill`
+///
+/// Every font pixel is `width` characters wide, since terminal cells are
+/// roughly twice as tall as they are wide.
+pub fn render(text: &str, fill: char, width: usize) -> Vec<String> {
+    (0..HEIGHT)
+        .map(|row| {
+            let glyphs: Vec<String> = text
+                .chars()
+                .map(|c| {
+                    glyph(c)[row]
+                        .chars()
+                        .map(|pixel| {
+                            let cell = if pixel == '#' { fill } else { ' ' };
+                            cell.to_string().repeat(width)
+                        })
+                        .collect()
+                })
+                .collect();
+            glyphs.join(&" ".repeat(width))
+        })
+        .collect()
+}
@@ -0,0 +1,66 @@
+//! Single-file `.tar.gz` bundles of the config (and state) files, for moving a
+//! setup between machines or handing a starter setup to a teammate.
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::backup::ManagedFile;
+use crate::config_format::Format;
+
+/// Write the existing `files` into a gzipped tarball at `bundle`
+pub fn export(bundle: &Path, files: &[ManagedFile]) -> io::Result<()> {
+    let encoder = GzEncoder::new(File::create(bundle)?, Compression::default());
+    let mut archive = tar::Builder::new(encoder);
+
+    for file in files.iter().filter(|f| f.path.exists()) {
+        archive.append_path_with_name(&file.path, file.name)?;
+    }
+    archive.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Unpack a bundle over `files`, returning the names of the files restored
+///
+/// Entries are matched to `files` by name; anything else in the archive is
+/// ignored, so a bundle can't write outside the known locations. A config
+/// bundled in another format than the one here is converted, without its
+/// comments.
+pub fn import(bundle: &Path, files: &[ManagedFile]) -> io::Result<Vec<String>> {
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(bundle)?));
+    let mut imported = Vec::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+
+        if let Some(target) = files.iter().find(|f| f.name == name) {
+            entry.unpack(&target.path)?;
+            imported.push(target.path.display().to_string());
+        } else if let Some(from) = config_format(&name)
+            && let Some((target, to)) = files.iter().find_map(|f| Some((f, config_format(f.name)?)))
+        {
+            let mut text = String::new();
+            entry.read_to_string(&mut text)?;
+            let converted = from
+                .parse_as_written(&text)
+                .and_then(|config| to.serialize(&config))
+                .map_err(|e| io::Error::other(format!("{}: {}", name, e)))?;
+            fs::write(&target.path, converted)?;
+            imported.push(format!(
+                "{} (converted from {})",
+                target.path.display(),
+                name
+            ));
+        }
+    }
+    Ok(imported)
+}
+
+/// The format of a config entry, by its name
+fn config_format(name: &str) -> Option<Format> {
+    Format::from_extension(name).filter(|format| format.entry_name() == name)
+}
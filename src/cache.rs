@@ -0,0 +1,193 @@
+//! Text payloads cached on disk with a time to live.
+//!
+//! Every feature that does expensive work (fetching remote configs, running
+//! hooks) keeps its results in its own namespace under the cache directory.
+//! Entries past their TTL are still kept, so a feature can fall back to the
+//! last good payload when the network or a command fails. Each entry file
+//! starts with the key on its own line, JSON-encoded so that multi-line keys
+//! (`exec: |` commands) fit on it, to make `cache list` readable.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+static BYPASS: AtomicBool = AtomicBool::new(false);
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+pub struct Entry {
+    pub namespace: String,
+    pub key: String,
+    pub age: Duration,
+    pub size: u64,
+}
+
+/// Ignore fresh entries for the rest of the run (`--no-cache`). Payloads are
+/// still stored, and stale ones are still used as a fallback.
+pub fn bypass() {
+    BYPASS.store(true, Ordering::Relaxed);
+}
+
+pub fn dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".cache"),
+    };
+    Some(base.join("swiftbar_clocks"))
+}
+
+/// Payload stored for `key` if it is younger than `ttl`
+pub fn get(namespace: &str, key: &str, ttl: Duration) -> Option<String> {
+    let fresh = || {
+        if BYPASS.load(Ordering::Relaxed) {
+            return None;
+        }
+        let path = entry_path(namespace, key)?;
+        file_age(&path).filter(|age| *age < ttl)?;
+        read(&path, key)
+    };
+    count(fresh())
+}
+
+/// Payload stored for `key`, however old
+pub fn get_stale(namespace: &str, key: &str) -> Option<String> {
+    count(entry_path(namespace, key).and_then(|path| read(&path, key)))
+}
+
+/// Hits and misses of `get` and `get_stale` so far in this run
+pub fn stats() -> (u64, u64) {
+    (HITS.load(Ordering::Relaxed), MISSES.load(Ordering::Relaxed))
+}
+
+fn count(payload: Option<String>) -> Option<String> {
+    let counter = if payload.is_some() { &HITS } else { &MISSES };
+    counter.fetch_add(1, Ordering::Relaxed);
+    payload
+}
+
+/// Time since the payload for `key` was stored
+pub fn age(namespace: &str, key: &str) -> Option<Duration> {
+    file_age(&entry_path(namespace, key)?)
+}
+
+/// Store `payload` for `key`. Failures only cost a cache miss, so they are ignored.
+pub fn put(namespace: &str, key: &str, payload: &str) {
+    let Some(path) = entry_path(namespace, key) else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(path, encode(key, payload));
+}
+
+pub fn list() -> Vec<Entry> {
+    let Some(dir) = dir() else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    for namespace in read_dir_sorted(&dir) {
+        for path in read_dir_sorted(&namespace) {
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            entries.push(Entry {
+                namespace: file_name(&namespace),
+                key: stored_key(&content).unwrap_or_default(),
+                age: file_age(&path).unwrap_or_default(),
+                size: content.len() as u64,
+            });
+        }
+    }
+    entries
+}
+
+/// Remove all entries, or only those of `namespace`. Returns the number removed.
+pub fn clear(namespace: Option<&str>) -> Result<usize, String> {
+    let Some(dir) = dir() else {
+        return Ok(0);
+    };
+    let mut removed = 0;
+    for path in read_dir_sorted(&dir) {
+        if namespace.is_some_and(|namespace| file_name(&path) != namespace) {
+            continue;
+        }
+        removed += read_dir_sorted(&path).len();
+        fs::remove_dir_all(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(removed)
+}
+
+/// FNV-1a, stable across Rust releases unlike `DefaultHasher`
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn entry_path(namespace: &str, key: &str) -> Option<PathBuf> {
+    Some(dir()?.join(namespace).join(format!("{:016x}", fnv1a(key))))
+}
+
+fn read(path: &Path, key: &str) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    decode(&content, key).map(str::to_string)
+}
+
+fn encode(key: &str, payload: &str) -> String {
+    format!("{}\n{}", serde_json::Value::from(key), payload)
+}
+
+/// The payload of an entry stored for `key`
+fn decode<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    let (_, payload) = content.split_once('\n')?;
+    // A different key means a hash collision, treat it as a miss
+    (stored_key(content)? == key).then_some(payload)
+}
+
+fn stored_key(content: &str) -> Option<String> {
+    let line = content.lines().next()?;
+    serde_json::from_str(line).ok()
+}
+
+fn file_age(path: &Path) -> Option<Duration> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    SystemTime::now().duration_since(modified).ok()
+}
+
+fn read_dir_sorted(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_line_keys_round_trip() {
+        let key = "curl -s https://example.com \\\n  | jq -r .status\n";
+        let content = encode(key, "ok\nsecond line");
+        assert_eq!(decode(&content, key), Some("ok\nsecond line"));
+        assert_eq!(stored_key(&content).as_deref(), Some(key));
+        assert_eq!(decode(&content, "curl -s https://example.com \\"), None);
+    }
+
+    #[test]
+    fn empty_payloads_are_hits() {
+        assert_eq!(decode(&encode("true", ""), "true"), Some(""));
+    }
+}
@@ -0,0 +1,156 @@
+//! Month view in a submenu, marking today in the home timezone, days on which
+//! a city's clocks change and the configured `events`.
+
+use crate::{Config, Role};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::fmt::Write;
+
+const FONT: &str = " | font=Menlo size=12";
+
+/// A day on which a timezone's offset changes, and the change in seconds
+struct Transition {
+    day: NaiveDate,
+    tz: Tz,
+    change_secs: i32,
+}
+
+pub fn write_submenu(
+    output: &mut String,
+    config: &Config,
+    now: DateTime<Local>,
+) -> Result<(), String> {
+    let home = config
+        .cities
+        .iter()
+        .find(|city| city.role == Some(Role::Home))
+        .and_then(|city| config.timezone(&city.timezone).ok());
+    let today = match home {
+        Some(tz) => now.with_timezone(&tz).date_naive(),
+        None => now.date_naive(),
+    };
+    let first = today
+        .with_day(1)
+        .ok_or_else(|| format!("calendar: no first day for {}", today))?;
+    let days_in_month = first
+        .checked_add_months(chrono::Months::new(1))
+        .map_or(30, |next| (next - first).num_days() as u32);
+
+    let transitions = transitions(config, home, first, days_in_month);
+    let events: Vec<_> = config
+        .events
+        .iter()
+        .filter(|event| event.date.year() == first.year() && event.date.month() == first.month())
+        .collect();
+
+    let _ = writeln!(output, "{}", first.format("%B %Y"));
+    let _ = writeln!(output, "-- Mo  Tu  We  Th  Fr  Sa  Su{}", FONT);
+    let mut line = String::from("--");
+    for _ in 0..first.weekday().num_days_from_monday() {
+        line.push_str("    ");
+    }
+    for day in 1..=days_in_month {
+        let date = first.with_day(day).unwrap_or(first);
+        let marker = if events.iter().any(|event| event.date == date) {
+            '*'
+        } else if transitions.iter().any(|t| t.day == date) {
+            '~'
+        } else {
+            ' '
+        };
+        if date == today {
+            let _ = write!(line, "[{:>2}]", day);
+        } else {
+            let _ = write!(line, " {:>2}{}", day, marker);
+        }
+        if date.weekday().num_days_from_monday() == 6 || day == days_in_month {
+            output.push_str(line.trim_end_matches(' '));
+            output.push_str(FONT);
+            output.push('\n');
+            line.replace_range(2.., "");
+        }
+    }
+
+    if transitions.is_empty() && events.is_empty() {
+        return Ok(());
+    }
+    output.push_str("-----\n");
+    let mut notes: Vec<(NaiveDate, String)> = events
+        .iter()
+        .map(|event| (event.date, format!("* {}", event.title)))
+        .collect();
+    // One note per day and direction, naming every city affected
+    let mut changes: Vec<(NaiveDate, bool, Vec<&str>)> = Vec::new();
+    for transition in &transitions {
+        let forward = transition.change_secs > 0;
+        let names = config
+            .cities
+            .iter()
+            .filter(|city| config.timezone(&city.timezone).ok() == Some(transition.tz))
+            .map(|city| city.name.as_str());
+        match changes
+            .iter_mut()
+            .find(|(day, f, _)| *day == transition.day && *f == forward)
+        {
+            Some((.., all)) => all.extend(names),
+            None => changes.push((transition.day, forward, names.collect())),
+        }
+    }
+    for (day, forward, names) in changes {
+        let direction = if forward { "forward" } else { "back" };
+        notes.push((
+            day,
+            format!("~ Clocks go {} in {}", direction, names.join(", ")),
+        ));
+    }
+    notes.sort_by_key(|(day, _)| *day);
+    for (day, note) in notes {
+        let _ = writeln!(output, "--{} {}", day.format("%b %-d"), note);
+    }
+    Ok(())
+}
+
+/// Offset changes of the configured timezones during the month, by home date
+fn transitions(config: &Config, home: Option<Tz>, first: NaiveDate, days: u32) -> Vec<Transition> {
+    // Rosters repeat timezones a lot, check each one once
+    let mut zones: Vec<Tz> = config
+        .cities
+        .iter()
+        .filter_map(|city| config.timezone(&city.timezone).ok())
+        .collect();
+    zones.sort_by_key(|tz| tz.name());
+    zones.dedup();
+    let midnight = |day: NaiveDate| -> DateTime<Utc> {
+        let naive = day.and_hms_opt(0, 0, 0).unwrap_or_default();
+        match home {
+            Some(tz) => tz
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|t| t.to_utc()),
+            None => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|t| t.to_utc()),
+        }
+        .unwrap_or_else(|| naive.and_utc())
+    };
+    let offset = |tz: &Tz, instant: DateTime<Utc>| {
+        instant.with_timezone(tz).offset().fix().local_minus_utc()
+    };
+
+    let mut transitions = Vec::new();
+    for day in (0..days).filter_map(|i| first.checked_add_days(chrono::Days::new(i.into()))) {
+        let (start, end) = (midnight(day), midnight(day) + Duration::days(1));
+        for tz in &zones {
+            let change_secs = offset(tz, end) - offset(tz, start);
+            if change_secs != 0 {
+                transitions.push(Transition {
+                    day,
+                    tz: *tz,
+                    change_secs,
+                });
+            }
+        }
+    }
+    transitions
+}
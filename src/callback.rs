@@ -0,0 +1,261 @@
+//! Actions behind clickable menu items.
+//!
+//! An item runs this binary again as `swiftbar_clocks --config <config>
+//! callback <action>`, which updates the state file; SwiftBar then refreshes
+//! the plugin to show the result.
+
+use crate::{
+    Config, config_edit, convert, hooks, include, natural, notify, remote, resolve_config_path,
+    server, state,
+};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use clap::{Subcommand, ValueEnum};
+use std::env;
+use std::fmt::Write;
+use std::io::Write as _;
+use std::process::{self, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+#[derive(Subcommand, Debug)]
+pub enum CallbackAction {
+    /// Show all times relative to a city, or to the home city again if omitted
+    RelativeTo { city: Option<String> },
+    /// Record that you talked to someone in the city just now
+    Contacted { city: String },
+    /// Fetch one cached section again, ignoring its TTL
+    Refresh {
+        /// Rerun the exec command of this city
+        #[arg(long)]
+        city: Option<String>,
+        /// Rerun the command of this extra_lines entry, counting from 0
+        #[arg(long)]
+        line: Option<usize>,
+        /// Fetch this remote config
+        #[arg(long)]
+        remote: Option<String>,
+    },
+    /// Move a city one place up in the config file
+    MoveUp { city: String },
+    /// Move a city one place down in the config file
+    MoveDown { city: String },
+    /// Copy the current time in a city to the clipboard
+    Copy {
+        city: String,
+        #[arg(long, value_enum, default_value_t = Stamp::Iso)]
+        format: Stamp,
+    },
+    /// Handle a `swiftbarclocks://` URL, e.g.
+    /// `swiftbarclocks://convert?time=15:00&tz=Europe/Berlin`
+    Url { url: String },
+}
+
+const URL_SCHEME: &str = "swiftbarclocks://";
+
+/// Machine-readable timestamps offered by the copy actions
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Stamp {
+    /// `2024-03-10T15:04:05+01:00`
+    Iso,
+    /// `2024-03-10T15:04:05.123+01:00`, with milliseconds
+    Rfc3339,
+    /// Unix timestamp in seconds
+    Epoch,
+}
+
+/// SwiftBar parameters making a menu item run `callback <args>`, starting with
+/// ` | `. Empty if the path of this binary is unknown.
+pub fn params(config: &Config, args: &[&str]) -> String {
+    let mut callback = Vec::with_capacity(args.len() + 1);
+    callback.push("callback");
+    callback.extend(args);
+    command_params(config, &callback)
+}
+
+/// Like [`params`] for any subcommand, e.g. `["say"]`
+pub fn command_params(config: &Config, args: &[&str]) -> String {
+    run_params(config, args, false)
+}
+
+/// Like [`command_params`], in a Terminal window for interactive commands
+pub fn terminal_params(config: &Config, args: &[&str]) -> String {
+    run_params(config, args, true)
+}
+
+fn run_params(config: &Config, args: &[&str], terminal: bool) -> String {
+    static EXE: OnceLock<Option<String>> = OnceLock::new();
+    let exe = EXE.get_or_init(|| {
+        let exe = env::current_exe().ok()?;
+        Some(quote(&exe.to_string_lossy()))
+    });
+    let Some(exe) = exe else {
+        return String::new();
+    };
+    let mut all_args = Vec::with_capacity(args.len() + 4);
+    if let Some(source) = &config.source {
+        all_args.extend(["--config", source]);
+    }
+    if let Some(profile) = include::selected_profile() {
+        all_args.extend(["--profile", profile]);
+    }
+    all_args.extend(args);
+
+    let mut params = format!(" | bash={}", exe);
+    for (i, arg) in all_args.iter().enumerate() {
+        let _ = write!(params, " param{}={}", i + 1, quote(arg));
+    }
+    let _ = write!(params, " terminal={} refresh=true", terminal);
+    params
+}
+
+pub fn run(config: &str, action: CallbackAction) -> Result<(), String> {
+    match action {
+        CallbackAction::RelativeTo { city } => {
+            state::update(config, |state| state.relative_to = city)
+        }
+        CallbackAction::Contacted { city } => state::update(config, |state| {
+            state.contacted.insert(city, Utc::now());
+        }),
+        CallbackAction::Refresh { city, line, remote } => {
+            if let Some(url) = remote {
+                remote::refresh(&url).ok_or_else(|| format!("could not fetch {}", url))?;
+            }
+            let loaded = crate::load_config(config.to_string());
+            if let Some(name) = city {
+                let exec = loaded
+                    .cities
+                    .iter()
+                    .find(|city| city.name == name)
+                    .and_then(|city| city.exec.as_deref())
+                    .ok_or_else(|| format!("no city '{}' with an exec command", name))?;
+                force_refresh(exec);
+            }
+            if let Some(index) = line {
+                let extra = loaded
+                    .extra_lines
+                    .get(index)
+                    .ok_or_else(|| format!("no extra_lines entry {}", index))?;
+                force_refresh(&extra.exec);
+            }
+            Ok(())
+        }
+        CallbackAction::MoveUp { city } => {
+            config_edit::move_city(&resolve_config_path(config)?, &city, true)
+        }
+        CallbackAction::MoveDown { city } => {
+            config_edit::move_city(&resolve_config_path(config)?, &city, false)
+        }
+        CallbackAction::Copy { city, format } => {
+            let loaded = crate::load_config(config.to_string());
+            let city = loaded
+                .cities
+                .iter()
+                .find(|c| c.name == city)
+                .ok_or_else(|| format!("no configured city named '{}'", city))?;
+            let now = Utc::now().with_timezone(&loaded.timezone(&city.timezone)?);
+            copy(&stamp(&now, format))
+        }
+        CallbackAction::Url { url } => open_url(config, &url),
+    }
+}
+
+/// `convert?time=…&tz=…` shows the conversion as a notification, and prints
+/// it for callers that capture the output
+fn open_url(config: &str, url: &str) -> Result<(), String> {
+    let rest = url
+        .strip_prefix(URL_SCHEME)
+        .ok_or_else(|| format!("not a {} URL: {}", URL_SCHEME, url))?;
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+    match action.trim_matches('/') {
+        "convert" => {
+            let time = server::query_param(query, "time")
+                .ok_or_else(|| format!("{} needs a time parameter", url))?;
+            let loaded = crate::load_config(config.to_string());
+            let instant = convert::resolve(
+                &loaded,
+                Local::now(),
+                &time,
+                server::query_param(query, "tz").as_deref(),
+            )?;
+            let text = convert::convert_text(&loaded, instant);
+            print!("{}", text);
+            let (title, cities) = text.split_once('\n').unwrap_or((&text, ""));
+            // Asked for just now, so quiet hours don't hold it back
+            notify::deliver(title, cities.trim_end())
+        }
+        other => Err(format!("unknown URL action '{}'", other)),
+    }
+}
+
+/// `paste-meeting`: the time proposed in `text`, by default the clipboard's,
+/// in every city, printed, shown as a notification and copied back
+pub fn paste_meeting(
+    config: &Config,
+    now: DateTime<Local>,
+    text: Option<&str>,
+) -> Result<(), String> {
+    let text = match text {
+        Some(text) => text.to_string(),
+        None => paste()?,
+    };
+    let instant = natural::parse(config, now, &text)?;
+    let converted = convert::convert_text(config, instant);
+    print!("{}", converted);
+    copy(&converted)?;
+    let (title, cities) = converted.split_once('\n').unwrap_or((&converted, ""));
+    notify::deliver(title, cities.trim_end())
+}
+
+/// The clipboard's text, its first line if it has several
+fn paste() -> Result<String, String> {
+    let output = process::Command::new("pbpaste")
+        .output()
+        .map_err(|e| format!("cannot run pbpaste: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("pbpaste exited with {}", output.status));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    match text.lines().map(str::trim).find(|line| !line.is_empty()) {
+        Some(line) => Ok(line.to_string()),
+        None => Err("the clipboard is empty".into()),
+    }
+}
+
+/// The time as `format`, in the time's own zone
+pub fn stamp(time: &DateTime<Tz>, format: Stamp) -> String {
+    match format {
+        Stamp::Iso => time.to_rfc3339_opts(SecondsFormat::Secs, false),
+        Stamp::Rfc3339 => time.to_rfc3339_opts(SecondsFormat::Millis, false),
+        Stamp::Epoch => time.timestamp().to_string(),
+    }
+}
+
+fn copy(text: &str) -> Result<(), String> {
+    let mut child = process::Command::new("pbcopy")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run pbcopy: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| e.to_string())?;
+    }
+    match child.wait().map_err(|e| e.to_string())? {
+        status if status.success() => Ok(()),
+        status => Err(format!("pbcopy exited with {}", status)),
+    }
+}
+
+fn force_refresh(command: &str) {
+    hooks::refresh(command, Duration::ZERO, hooks::DEFAULT_TIMEOUT);
+}
+
+/// Quote a parameter value for SwiftBar if it contains spaces or quotes
+fn quote(value: &str) -> String {
+    if value.is_empty() || value.contains([' ', '"', '|', '=']) {
+        format!("\"{}\"", value.replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
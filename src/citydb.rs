@@ -0,0 +1,158 @@
+//! Major cities with their region, country, coordinates, population and
+//! timezone, embedded from `data/cities.tsv` with the `city-db` feature (on
+//! by default). `scripts/build_citydb.py` regenerates the table from GeoNames.
+//!
+//! Built without the feature, the table is empty and every lookup misses.
+
+use std::sync::OnceLock;
+
+#[cfg(feature = "city-db")]
+const DATA: &str = include_str!("../data/cities.tsv");
+#[cfg(not(feature = "city-db"))]
+const DATA: &str = "";
+
+pub struct City {
+    pub name: &'static str,
+    pub ascii_name: &'static str,
+    pub region: &'static str,
+    /// ISO 3166 country code, e.g. `JP`
+    pub country: &'static str,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub population: u64,
+    pub timezone: &'static str,
+}
+
+/// Whether the binary was built with the database
+pub fn is_available() -> bool {
+    cfg!(feature = "city-db")
+}
+
+/// Every city, most populous first
+pub fn all() -> &'static [City] {
+    static CITIES: OnceLock<Vec<City>> = OnceLock::new();
+    CITIES.get_or_init(|| {
+        let mut cities: Vec<City> = DATA
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(parse_line)
+            .collect();
+        // The generated table is sorted already, a hand-edited one may not be
+        cities.sort_by_key(|city| std::cmp::Reverse(city.population));
+        cities
+    })
+}
+
+fn parse_line(line: &'static str) -> Option<City> {
+    let mut fields = line.split('\t');
+    let mut next = || fields.next();
+    Some(City {
+        name: next()?,
+        ascii_name: next()?,
+        region: next()?,
+        country: next()?,
+        latitude: next()?.parse().ok()?,
+        longitude: next()?.parse().ok()?,
+        population: next()?.parse().ok()?,
+        timezone: next()?,
+    })
+}
+
+/// Cities called `name`, or `name` followed by more words ("New York" is
+/// "New York City"), ignoring case and accents; most populous first
+pub fn lookup(name: &str) -> Vec<&'static City> {
+    let name = fold(name);
+    let prefix = format!("{} ", name);
+    all()
+        .iter()
+        .filter(|city| folds(city).any(|folded| folded == name || folded.starts_with(&prefix)))
+        .collect()
+}
+
+/// Cities a config city called `name` can be in, for a city without a
+/// `timezone`: those [`lookup`] finds, or for names like `Ana (Seattle)` the
+/// ones it finds for the part in parentheses
+pub fn resolve(name: &str) -> Vec<&'static City> {
+    let cities = lookup(name.trim());
+    if !cities.is_empty() {
+        return cities;
+    }
+    match name
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+    {
+        Some((place, _)) => lookup(place.trim()),
+        None => Vec::new(),
+    }
+}
+
+/// Cities whose name contains `query`, ignoring case and accents; most
+/// populous first
+pub fn search(query: &str) -> Vec<&'static City> {
+    let query = fold(query);
+    all()
+        .iter()
+        .filter(|city| folds(city).any(|folded| folded.contains(&query)))
+        .collect()
+}
+
+/// Both names of `city` folded: `fold` does not know every letter the
+/// GeoNames ascii name spells out
+fn folds(city: &City) -> impl Iterator<Item = String> {
+    [city.name, city.ascii_name].into_iter().map(fold)
+}
+
+/// Lower case without diacritics, so `São Paulo` matches `sao paulo`
+pub fn fold(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ą' | 'ă' => 'a',
+            'ç' | 'ć' | 'č' => 'c',
+            'ď' | 'đ' => 'd',
+            'è' | 'é' | 'ê' | 'ë' | 'ę' | 'ě' => 'e',
+            'ğ' => 'g',
+            'ì' | 'í' | 'î' | 'ï' | 'ı' => 'i',
+            'ł' => 'l',
+            'ñ' | 'ń' | 'ň' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ő' => 'o',
+            'ř' => 'r',
+            'ś' | 'š' | 'ş' | 'ș' => 's',
+            'ť' | 'ţ' | 'ț' => 't',
+            'ù' | 'ú' | 'û' | 'ü' | 'ů' | 'ű' => 'u',
+            'ý' | 'ÿ' => 'y',
+            'ź' | 'ż' | 'ž' => 'z',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "city-db"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_ignores_diacritics_either_way() {
+        for name in ["Wrocław", "wroclaw", "WROCLAW"] {
+            let found = lookup(name);
+            assert_eq!(found.len(), 1, "{}", name);
+            assert_eq!(found[0].timezone, "Europe/Warsaw");
+        }
+        assert_eq!(resolve("Ola (Kraków)")[0].name, "Kraków");
+    }
+
+    #[test]
+    fn search_ignores_diacritics_either_way() {
+        assert!(search("wroc").iter().any(|city| city.name == "Wrocław"));
+        assert!(search("gdańsk").iter().any(|city| city.name == "Gdańsk"));
+        assert!(search("sao p").iter().any(|city| city.name == "São Paulo"));
+    }
+
+    #[test]
+    fn ascii_names_are_ascii() {
+        for city in all() {
+            assert!(city.ascii_name.is_ascii(), "{}", city.ascii_name);
+            assert_eq!(fold(city.name), fold(city.ascii_name), "{}", city.name);
+        }
+    }
+}
@@ -0,0 +1,914 @@
+//! The command line: arguments, subcommands and what each of them runs.
+
+use crate::callback::CallbackAction;
+use crate::{
+    Config, TimeFormat, archive, backup, bundle, cache, callback, citydb, color, comfort,
+    config_diff, config_edit, convert, describe, digest, duration, fetchd, fuzzy, gist, grid,
+    include, lint, load_config, managed_files, menu, metrics, mqtt, notify, overlap, pick,
+    read_config_file, render, resolve_config_path, selftest, server, snapshot, speech, template,
+    tui, validate, watch,
+};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_SWIFTBAR_CLOCK_CONFIG: &str = "~/.config/swiftbar_clock_config.yaml";
+
+#[derive(Parser, Debug)]
+#[command(name = "swiftbar_clocks")]
+#[command(about = "Display world clocks with unicode clock icons", long_about = None)]
+pub struct Args {
+    /// Path or http(s) URL of the configuration file
+    #[arg(short, long, env = "SWIFTBAR_CLOCK_CONFIG", default_value=DEFAULT_SWIFTBAR_CLOCK_CONFIG)]
+    config: String,
+
+    /// List all available timezones, or those matching PATTERN best first,
+    /// e.g. `--list-timezones berl`
+    ///
+    /// See also: https://en.wikipedia.org/wiki/List_of_tz_database_time_zones
+    #[arg(short = 'l', long = "list-timezones", value_name = "PATTERN", num_args = 0..=1)]
+    list_timezones: Option<Option<String>>,
+
+    /// Output format
+    #[arg(long, visible_alias = "output", value_enum, default_value_t = OutputFormat::Swiftbar)]
+    format: OutputFormat,
+
+    /// City to show, for formats that output a single city
+    #[arg(long)]
+    city: Option<String>,
+
+    /// Print the cities as an aligned table for terminals instead of the
+    /// menu; `--watch` redraws the same table in place
+    #[arg(long)]
+    plain: bool,
+
+    /// Show a terminal world clock redrawn in place instead of the menu
+    #[arg(long)]
+    watch: bool,
+
+    /// Time between redraws in watch mode, e.g. 1s or 500ms
+    #[arg(long, default_value = "1s", value_parser = duration::parse_interval)]
+    interval: std::time::Duration,
+
+    /// Render the menu once and print how long it took, with cache and
+    /// fetch statistics, instead of the menu
+    #[arg(long, value_enum, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "prometheus")]
+    metrics: Option<metrics::Format>,
+
+    /// Render the menu ITERATIONS times and report the cost per render
+    #[arg(long, value_name = "ITERATIONS", num_args = 0..=1, default_missing_value = "10000")]
+    bench_render: Option<u32>,
+
+    /// Recompute cached data (remote configs, hook output) instead of reusing it
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Layer this section of the config's `profiles` on top of its cities
+    #[arg(long, global = true, env = "SWIFTBAR_CLOCK_PROFILE")]
+    profile: Option<String>,
+
+    /// Fail on config problems (unreadable file, YAML errors, unknown keys,
+    /// invalid timezones) instead of falling back to defaults or skipping
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Colors in terminal output; `auto` follows NO_COLOR, CLICOLOR_FORCE
+    /// and whether stdout is a terminal
+    #[arg(long, global = true, value_enum, default_value_t = color::Choice::Auto)]
+    color: color::Choice,
+
+    /// Show times as `2:05 PM`, like `time_format: 12h`
+    #[arg(long)]
+    twelve_hour: bool,
+
+    /// Render as of this instant instead of now, e.g. 2024-07-01T16:00:00+02:00
+    #[arg(long, global = true, value_name = "RFC3339", value_parser = parse_at)]
+    at: Option<DateTime<FixedOffset>>,
+
+    /// Print the windows of the next 7 days in which every city is within its
+    /// working hours
+    #[arg(long)]
+    find_overlap: bool,
+
+    /// Show a minimal menu with diagnostics (binary, config, parse status)
+    /// instead of the clocks, for when the menu comes up blank
+    #[arg(long)]
+    self_test: bool,
+
+    /// Append every city's time and UTC offset to a log once per PERIOD,
+    /// on the first menu refresh
+    #[arg(long, value_enum, value_name = "PERIOD")]
+    archive: Option<archive::Period>,
+
+    /// Log of `--archive` and `archive`, by default `*.archive.jsonl` next
+    /// to the config
+    #[arg(long, global = true, value_name = "PATH")]
+    archive_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum OutputFormat {
+    /// SwiftBar plugin menu
+    Swiftbar,
+    /// Local time and every city as one JSON document, for jq and dashboards
+    Json,
+    /// The SwiftBar menu in the format of Argos (GNOME) and Kargos (KDE)
+    Argos,
+    /// Home Assistant sensor payloads (JSON)
+    Hass,
+    /// Waybar custom module JSON, all cities in the tooltip
+    Waybar,
+    /// Plain "21:46 in Tokyo" lines, for Apple Shortcuts
+    Shortcuts,
+    /// City details as a JSON dictionary, for Apple Shortcuts
+    ShortcutsDict,
+    /// One city's time in large digits, redrawn every --interval
+    Bigtime,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Manage the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Package the config into a single file
+    Export {
+        /// Path of the .tar.gz bundle to write
+        #[arg(long)]
+        bundle: PathBuf,
+    },
+    /// Replace the config with the contents of a bundle
+    Import {
+        /// Path of a .tar.gz bundle made with `export`
+        bundle: PathBuf,
+    },
+    /// Show what a given time is in all configured cities
+    Convert {
+        /// Time as HH:MM (today) or YYYY-MM-DD HH:MM
+        time: String,
+        /// Timezone (or alias) the time is given in, local if omitted
+        #[arg(long)]
+        tz: Option<String>,
+    },
+    /// Serve the clocks over HTTP as JSON (/json) and a dashboard page (/html)
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8787)]
+        port: u16,
+        /// Address to bind, use 0.0.0.0 to serve other devices
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+    /// Periodically publish the clocks to an MQTT broker
+    ///
+    /// Each city gets `<prefix>/<city>/time`, `.../offset` and `.../daynight` topics.
+    Publish {
+        /// Broker URL, tcp://[user:password@]host[:port]
+        #[arg(long)]
+        mqtt: String,
+        /// Topic prefix
+        #[arg(long, default_value = "clocks")]
+        topic_prefix: String,
+        /// Seconds between updates
+        #[arg(long, default_value_t = 60)]
+        interval: u64,
+    },
+    /// Fuzzy-find a city or timezone and add it to the config
+    Pick,
+    /// Full-screen world clock, with keys to add, remove and reorder cities
+    Tui {
+        /// Start in the table view (`t` switches views)
+        #[arg(long)]
+        table: bool,
+    },
+    /// Synchronize the config with a shared copy
+    Sync {
+        #[command(subcommand)]
+        target: SyncTarget,
+    },
+    /// Markdown summary of the coming days, for pasting into a team note
+    Digest {
+        /// Cover the next 7 days
+        #[arg(long, required = true)]
+        week: bool,
+    },
+    /// Rank the coming half-hour slots by how comfortable they are for everyone
+    Slots {
+        /// How far ahead to look, in hours
+        #[arg(long, default_value_t = 24)]
+        hours: u32,
+        /// Number of slots to show
+        #[arg(long, default_value_t = 5)]
+        top: usize,
+    },
+    /// Keep remote configs and hook output fresh in the background, so
+    /// renders only read the cache
+    Fetchd {
+        /// Seconds between refreshes
+        #[arg(long, default_value_t = 300)]
+        interval: u64,
+    },
+    /// Print a city's hours mapped onto local hours, working hours shaded
+    Grid {
+        /// City to map
+        #[arg(long)]
+        city: String,
+        /// Cover Monday to Sunday of this week instead of just today
+        #[arg(long)]
+        week: bool,
+        /// Print a Markdown table
+        #[arg(long)]
+        markdown: bool,
+    },
+    /// Summarize the config in plain English
+    Describe,
+    /// Add a duration to a time, correctly across DST changes, and show the
+    /// result in its zone, in UTC and in all configured cities
+    #[command(allow_negative_numbers = true)]
+    AddDuration {
+        /// Start as `YYYY-MM-DD HH:MM [timezone]`, `HH:MM [timezone]` or `now`
+        start: String,
+        /// Duration such as `+36h`, `-90m` or `2d4h`; days are calendar days
+        #[arg(allow_hyphen_values = true)]
+        duration: String,
+    },
+    /// The timezone a city without `timezone` gets from its name, and the
+    /// other cities of that name
+    Resolve { city: String },
+    /// Look a city up in the built-in city database
+    Search {
+        query: String,
+        /// Only cities in this country, as an ISO code like `JP`
+        #[arg(long)]
+        country: Option<String>,
+    },
+    /// Arrival time of a flight, at the destination and in all cities
+    Flight {
+        /// Departure airport and local time, like `LHR 21:35` or
+        /// `LHR 2025-05-01 21:35`; a timezone works in place of the airport
+        departure: String,
+        /// Flight time, like `11h20m`
+        #[arg(long)]
+        duration: String,
+        /// Destination airport code or timezone
+        #[arg(long)]
+        to: String,
+    },
+    /// Check the config for mistakes, exiting with an error if there are any
+    Lint {
+        /// Also flag cities whose `active_until` has passed
+        #[arg(long)]
+        expired: bool,
+    },
+    /// Read the cities' times aloud (macOS)
+    Say {
+        /// Cities to speak, all if omitted
+        cities: Vec<String>,
+    },
+    /// Read a proposed time like `3pm PT` from the clipboard and show and
+    /// copy it in all cities
+    PasteMeeting {
+        /// Text to read instead of the clipboard
+        text: Option<String>,
+    },
+    /// Show a macOS notification, or hold it back until quiet hours end; for
+    /// alarms and reminders run from cron or Shortcuts
+    Notify {
+        title: String,
+        #[arg(default_value = "")]
+        message: String,
+    },
+    /// The UTC offsets archived by `--archive` on a past day
+    Archive { date: NaiveDate },
+    /// Record every city's UTC offset, or compare it with the recorded one
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotCommand,
+    },
+    /// Run the action of a clicked menu item
+    Callback {
+        #[command(subcommand)]
+        action: CallbackAction,
+    },
+    /// Inspect or clear cached data
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SnapshotCommand {
+    /// Save the current offsets
+    Save {
+        /// Snapshot file, next to the config by default
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+    /// Show the offsets that changed since the snapshot, failing if any did
+    Diff {
+        /// Snapshot file, next to the config by default
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// List cache entries with their age and size
+    List,
+    /// Remove cache entries
+    Clear {
+        /// Only clear this namespace, e.g. remote or exec
+        namespace: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SyncTarget {
+    /// Merge the cities with a GitHub gist and push the result back
+    ///
+    /// The token is read from GITHUB_TOKEN.
+    Gist {
+        /// Gist ID
+        id: String,
+        /// Only merge the gist into the local config, don't update the gist
+        #[arg(long)]
+        no_push: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Write a commented starter config
+    Init {
+        /// Replace an existing config, after taking a backup
+        #[arg(long)]
+        force: bool,
+    },
+    /// Save a timestamped copy of the config
+    Backup,
+    /// Restore the config from a backup
+    Restore {
+        /// Backup to restore, the latest one if omitted
+        name: Option<String>,
+    },
+    /// List available backups
+    Backups,
+    /// Add a city, e.g. `config add Berlin Europe/Berlin`
+    Add {
+        name: String,
+        /// Timezone name, or one of the config's aliases
+        timezone: String,
+    },
+    /// Remove a city by name
+    Remove { name: String },
+    /// List the configured cities and their timezones
+    List,
+    /// Check the config for YAML errors, unknown keys and invalid timezones
+    Validate,
+    /// Show which cities and settings differ between two configs
+    Diff {
+        from: String,
+        to: String,
+        #[arg(long, value_enum, default_value_t)]
+        format: config_diff::Format,
+    },
+}
+
+fn parse_at(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map_err(|err| format!("{} (expected RFC 3339 like 2024-07-01T16:00:00+02:00)", err))
+}
+
+fn list_timezones(pattern: Option<&str>) -> Result<(), String> {
+    // chrono-tz provides TZ_VARIANTS constant with all timezones
+    let Some(pattern) = pattern else {
+        for tz in chrono_tz::TZ_VARIANTS {
+            println!("{}", tz.name());
+        }
+        return Ok(());
+    };
+    let mut matches: Vec<(i32, &str)> = chrono_tz::TZ_VARIANTS
+        .iter()
+        .filter_map(|tz| Some((fuzzy::score(pattern, tz.name())?, tz.name())))
+        .collect();
+    if matches.is_empty() {
+        return Err(format!("no timezone matches {}", pattern));
+    }
+    // Best first, alphabetical among equals
+    matches.sort_by_key(|&(score, name)| (std::cmp::Reverse(score), name));
+    for (_, name) in matches {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+/// Matching cities with where they are, how big and what time it is there,
+/// so that one San Jose is easy to tell from another
+fn search_cities(now: DateTime<Local>, query: &str, country: Option<&str>) -> Result<(), String> {
+    if !citydb::is_available() {
+        return Err("built without the city database (the city-db feature)".into());
+    }
+    let cities: Vec<&citydb::City> = citydb::search(query)
+        .into_iter()
+        .filter(|city| country.is_none_or(|country| city.country.eq_ignore_ascii_case(country)))
+        .collect();
+    if cities.is_empty() {
+        return Err(match country {
+            Some(country) => format!(
+                "no city matching '{}' in {}",
+                query,
+                country.to_ascii_uppercase()
+            ),
+            None => format!("no city matching '{}'", query),
+        });
+    }
+    print_cities(now, &cities);
+    Ok(())
+}
+
+fn resolve_city(now: DateTime<Local>, name: &str) -> Result<(), String> {
+    if !citydb::is_available() {
+        return Err("built without the city database (the city-db feature)".into());
+    }
+    let cities = citydb::resolve(name);
+    let Some(first) = cities.first() else {
+        return Err(format!(
+            "no city called '{}', set its timezone in the config",
+            name
+        ));
+    };
+    println!(
+        "{} resolves to {} ({}, {}, {})",
+        name, first.timezone, first.name, first.region, first.country
+    );
+    if cities.len() > 1 {
+        println!("\nOther cities of that name, set `timezone` to use one:\n");
+        print_cities(now, &cities[1..]);
+    }
+    Ok(())
+}
+
+/// An aligned row per city: where, the time there, population and timezone
+fn print_cities(now: DateTime<Local>, cities: &[&citydb::City]) {
+    let places: Vec<String> = cities
+        .iter()
+        .map(|city| format!("{}, {}, {}", city.name, city.region, city.country))
+        .collect();
+    let width = places
+        .iter()
+        .map(|place| place.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (city, place) in cities.iter().zip(&places) {
+        let time = city
+            .timezone
+            .parse::<chrono_tz::Tz>()
+            .map(|tz| now.with_timezone(&tz).format("%H:%M %a").to_string())
+            .unwrap_or_default();
+        println!(
+            "{:<width$}  {}  {:>11}  {}",
+            place,
+            time,
+            format_population(city.population),
+            city.timezone,
+        );
+    }
+}
+
+/// `8,804,190`
+fn format_population(population: u64) -> String {
+    let digits = population.to_string();
+    let mut output = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            output.push(',');
+        }
+        output.push(digit);
+    }
+    output
+}
+
+fn run_config_command(config: &str, action: ConfigCommand) -> Result<(), String> {
+    let config_path = resolve_config_path(config)?;
+    let files = managed_files(&config_path);
+
+    match action {
+        ConfigCommand::Init { force } => {
+            config_edit::init(&config_path, force)?;
+            println!("Wrote {}", config_path.display());
+        }
+        ConfigCommand::Backup => {
+            let dir = backup::backup(&config_path, &files).map_err(|e| e.to_string())?;
+            println!("Backed up to {}", dir.display());
+        }
+        ConfigCommand::Restore { name } => {
+            let name = backup::restore(&config_path, &files, name.as_deref())
+                .map_err(|e| e.to_string())?;
+            println!("Restored backup {}", name);
+        }
+        ConfigCommand::Backups => {
+            for name in backup::list(&config_path).map_err(|e| e.to_string())? {
+                println!("{}", name);
+            }
+        }
+        ConfigCommand::Add { name, timezone } => {
+            // Aliases are only known to the config being added to
+            let config = if config_path.exists() {
+                read_config_file(&config_path)?
+            } else {
+                Config::default()
+            };
+            // `${VAR}` is only known once the config is loaded
+            if !timezone.contains("${") {
+                config.timezone(&timezone)?;
+            }
+            config_edit::add_city(&config_path, &name, &timezone)?;
+            println!("Added {} ({})", name, timezone);
+        }
+        ConfigCommand::Remove { name } => {
+            config_edit::remove_city(&config_path, &name)?;
+            println!("Removed {}", name);
+        }
+        ConfigCommand::Validate => {
+            report_invalid(validate::validate(config))?;
+            println!("{} is valid", config_path.display());
+        }
+        ConfigCommand::Diff { from, to, format } => {
+            print!("{}", config_diff::diff(&from, &to, format)?);
+        }
+        ConfigCommand::List => {
+            let config = read_config_file(&config_path)?;
+            let width = config
+                .cities
+                .iter()
+                .map(|city| city.name.chars().count())
+                .max()
+                .unwrap_or(0);
+            for city in &config.cities {
+                match citydb::resolve(&city.name).first() {
+                    Some(found) if city.timezone.is_empty() => {
+                        println!("{:<width$}  {} (from the name)", city.name, found.timezone);
+                    }
+                    _ => println!("{:<width$}  {}", city.name, city.timezone),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print each problem, failing if there are any
+fn report_invalid(result: Result<(), Vec<String>>) -> Result<(), String> {
+    let Err(problems) = result else {
+        return Ok(());
+    };
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+    match problems.len() {
+        1 => Err("1 problem in the config".to_string()),
+        n => Err(format!("{} problems in the config", n)),
+    }
+}
+
+fn print_slots(config: &Config, now: DateTime<Local>, hours: u32, top: usize) {
+    for slot in comfort::rank_slots(config, now, hours).iter().take(top) {
+        let scores: Vec<String> = slot
+            .scores
+            .iter()
+            .map(|(name, score)| format!("{} {}", name, score))
+            .collect();
+        println!(
+            "{}  min {:>3}  avg {:>3}  {}",
+            slot.start.format("%a %H:%M"),
+            slot.min,
+            slot.average,
+            scores.join(", ")
+        );
+    }
+}
+
+fn print_overlap(config: &Config, now: DateTime<Local>) {
+    let clocks = render::city_clocks(config, now);
+    let twelve_hour = config.time_format() == TimeFormat::TwelveHour;
+    for day in now.date_naive().iter_days().take(7) {
+        let windows = overlap::windows(&clocks, day);
+        let windows: Vec<String> = windows
+            .iter()
+            .map(|window| {
+                format!(
+                    "{} ({})",
+                    window.format(twelve_hour),
+                    duration::format_hm(window.duration())
+                )
+            })
+            .collect();
+        let windows = match windows.is_empty() {
+            true => "none".to_string(),
+            false => windows.join(", "),
+        };
+        println!("{}  {}", day.format("%a %b %e"), windows);
+    }
+}
+
+fn run_snapshot_command(config: &str, action: SnapshotCommand) -> Result<(), String> {
+    let (SnapshotCommand::Save { file } | SnapshotCommand::Diff { file }) = &action;
+    let path = match file {
+        Some(path) => path.clone(),
+        None => resolve_config_path(config)?.with_extension("snapshot.yaml"),
+    };
+    let config = load_config(config.to_string());
+    match action {
+        SnapshotCommand::Save { .. } => snapshot::save(&config, &path),
+        SnapshotCommand::Diff { .. } => snapshot::diff(&config, &path),
+    }
+}
+
+fn run_cache_command(action: CacheCommand) -> Result<(), String> {
+    match action {
+        CacheCommand::List => {
+            for entry in cache::list() {
+                println!(
+                    "{:<8} {:>7}s {:>8}B  {}",
+                    entry.namespace,
+                    entry.age.as_secs(),
+                    entry.size,
+                    // One line per entry, also for multi-line commands
+                    entry.key.trim_end().replace('\n', " ⏎ ")
+                );
+            }
+        }
+        CacheCommand::Clear { namespace } => {
+            let removed = cache::clear(namespace.as_deref())?;
+            println!("Removed {} cache entries", removed);
+        }
+    }
+    Ok(())
+}
+
+fn export_bundle(config: &str, bundle: &Path) -> Result<(), String> {
+    let config_path = resolve_config_path(config)?;
+    bundle::export(bundle, &managed_files(&config_path)).map_err(|e| e.to_string())?;
+    println!("Exported to {}", bundle.display());
+    Ok(())
+}
+
+fn import_bundle(config: &str, bundle: &Path) -> Result<(), String> {
+    let config_path = resolve_config_path(config)?;
+    let files = managed_files(&config_path);
+
+    backup::backup(&config_path, &files).map_err(|e| e.to_string())?;
+    for imported in bundle::import(bundle, &files).map_err(|e| e.to_string())? {
+        println!("Imported {}", imported);
+    }
+    Ok(())
+}
+
+/// Run the command line `args`, `Err` with a message for the user on failure
+pub fn run(args: Args) -> Result<(), String> {
+    if let Some(pattern) = &args.list_timezones {
+        return list_timezones(pattern.as_deref());
+    }
+    if args.self_test {
+        print!("{}", selftest::render(&args.config));
+        return Ok(());
+    }
+    if args.no_cache {
+        cache::bypass();
+    }
+    if let Some(profile) = args.profile.clone() {
+        include::select_profile(profile);
+    }
+    // Config subcommands read and write the file themselves
+    if args.strict && !matches!(args.command, Some(Command::Config { .. })) {
+        report_invalid(validate::validate(&args.config))?;
+    }
+    let now = args
+        .at
+        .map_or_else(Local::now, |at| at.with_timezone(&Local));
+    let archive_file = args
+        .archive_file
+        .clone()
+        .or_else(|| archive::default_path(&args.config))
+        .ok_or_else(|| "no place for the archive, set --archive-file".to_string());
+    if let Some(command) = args.command {
+        return match command {
+            Command::Config { action } => run_config_command(&args.config, action),
+            Command::Export { bundle } => export_bundle(&args.config, &bundle),
+            Command::Import { bundle } => import_bundle(&args.config, &bundle),
+            Command::Convert { time, tz } => {
+                let config = load_config(args.config.clone());
+                convert::resolve(&config, now, &time, tz.as_deref())
+                    .map(|instant| print!("{}", convert::convert_text(&config, instant)))
+            }
+            Command::Serve { port, host } => server::serve(&args.config, &host, port),
+            Command::Publish {
+                mqtt,
+                topic_prefix,
+                interval,
+            } => mqtt::run(
+                &args.config,
+                &mqtt,
+                &topic_prefix,
+                std::time::Duration::from_secs(interval),
+            ),
+            Command::Tui { table } => tui::run(&args.config, table, color::enabled(args.color)),
+            Command::Pick => pick::run(&args.config),
+            Command::Sync {
+                target: SyncTarget::Gist { id, no_push },
+            } => gist::sync(&args.config, &id, !no_push),
+            Command::Digest { week: _ } => {
+                print!("{}", digest::week(&load_config(args.config.clone()), now));
+                Ok(())
+            }
+            Command::Slots { hours, top } => {
+                let config = load_config(args.config.clone());
+                print_slots(&config, now, hours, top);
+                Ok(())
+            }
+            Command::Fetchd { interval } => {
+                fetchd::run(&args.config, std::time::Duration::from_secs(interval))
+            }
+            Command::Grid {
+                city,
+                week,
+                markdown,
+            } => {
+                let config = load_config(args.config.clone());
+                grid::render(&config, now, &city, week, markdown).map(|grid| print!("{}", grid))
+            }
+            Command::AddDuration { start, duration } => {
+                let config = load_config(args.config.clone());
+                convert::add_duration_text(&config, now, &start, &duration)
+                    .map(|text| print!("{}", text))
+            }
+            Command::Resolve { city } => resolve_city(now, &city),
+            Command::Search { query, country } => search_cities(now, &query, country.as_deref()),
+            Command::Flight {
+                departure,
+                duration,
+                to,
+            } => {
+                let config = load_config(args.config.clone());
+                convert::flight_text(&config, now, &departure, &duration, &to)
+                    .map(|text| print!("{}", text))
+            }
+            Command::Describe => {
+                let config = load_config(args.config.clone());
+                print!("{}", describe::describe(&config, now));
+                Ok(())
+            }
+            Command::Lint { expired } => {
+                let config = load_config(args.config.clone());
+                let problems = lint::check(&config, now.to_utc(), expired);
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+                match problems.len() {
+                    0 => Ok(()),
+                    1 => Err("1 problem found".to_string()),
+                    n => Err(format!("{} problems found", n)),
+                }
+            }
+            Command::Say { cities } => speech::say(&load_config(args.config.clone()), &cities),
+            Command::PasteMeeting { text } => {
+                callback::paste_meeting(&load_config(args.config.clone()), now, text.as_deref())
+            }
+            Command::Notify { title, message } => notify::send(
+                &load_config(args.config.clone()),
+                &args.config,
+                now,
+                &title,
+                &message,
+            ),
+            Command::Archive { date } => {
+                archive::show(&load_config(args.config.clone()), &archive_file?, date)
+                    .map(|text| print!("{}", text))
+            }
+            Command::Snapshot { action } => run_snapshot_command(&args.config, action),
+            Command::Callback { action } => callback::run(&args.config, action),
+            Command::Cache { action } => run_cache_command(action),
+        };
+    }
+
+    let city = args.city.as_deref();
+    let load = || {
+        let mut config = load_config(args.config.clone());
+        if args.twelve_hour {
+            config.time_format = Some(TimeFormat::TwelveHour);
+        }
+        config
+    };
+    let started = std::time::Instant::now();
+    let config = load();
+    let load_time = started.elapsed();
+    let reload = load;
+    if args.watch {
+        if args.at.is_some() {
+            return Err("--at renders a single instant, it can't be combined with --watch".into());
+        }
+        return watch::run(config, reload, args.interval, color::enabled(args.color));
+    }
+    if args.plain {
+        let resolved = render::ResolvedCities::new(&config);
+        let mut table = String::new();
+        render::render_table_into(
+            &mut table,
+            &resolved,
+            now,
+            &mut Vec::new(),
+            color::enabled(args.color),
+        );
+        print!("{}", table);
+        return Ok(());
+    }
+    if args.find_overlap {
+        print_overlap(&config, now);
+        return Ok(());
+    }
+    if let Some(format) = args.metrics {
+        let started = std::time::Instant::now();
+        render_menu(&config, now);
+        let metrics =
+            metrics::Metrics::collect(&args.config, &config, load_time, started.elapsed());
+        print!("{}", metrics.format(format));
+        return Ok(());
+    }
+    if let Some(iterations) = args.bench_render {
+        bench_render(&config, iterations.max(1));
+        return Ok(());
+    }
+
+    let output = match args.format {
+        OutputFormat::Swiftbar if args.at.is_none() => {
+            // Each refresh is a chance to deliver what quiet hours held back
+            notify::flush(&config, &args.config, now);
+            if let Some(archive::Period::Daily) = args.archive {
+                archive::record(&config, &archive_file?, now);
+            }
+            render_menu(&config, now)
+        }
+        OutputFormat::Swiftbar => render_menu(&config, now),
+        OutputFormat::Argos => menu::argos(&menu::parse(&render_menu(&config, now))),
+        OutputFormat::Json => format!("{}\n", render::render_json(&config, now)),
+        OutputFormat::Hass => format!("{}\n", render::render_hass(&config, now)),
+        OutputFormat::Waybar => format!("{}\n", render::render_waybar(&config, now, city)?),
+        OutputFormat::Shortcuts => render::render_shortcuts(&config, now, city)?,
+        OutputFormat::ShortcutsDict => {
+            format!("{}\n", render::render_shortcuts_dict(&config, now, city)?)
+        }
+        OutputFormat::Bigtime => {
+            return watch::run_bigtime(config, reload, city, args.interval);
+        }
+    };
+
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    let _ = stdout.write_all(output.as_bytes());
+    let _ = stdout.flush();
+    Ok(())
+}
+
+/// The SwiftBar menu, from the user's template if there is one
+fn render_menu(config: &Config, now: DateTime<Local>) -> String {
+    match &config.template {
+        Some(path) => template::render(config, path, now).unwrap_or_else(|err| {
+            // Keep the plugin usable and show what went wrong in the menu
+            format!(
+                "{}---\nTemplate error: {} | color=red\n",
+                render::render_swiftbar(config, now),
+                err.lines().next().unwrap_or_default()
+            )
+        }),
+        None => render::render_swiftbar(config, now),
+    }
+}
+
+/// Time the render path as used by streaming modes: timezones resolved once,
+/// buffers reused between iterations
+fn bench_render(config: &Config, iterations: u32) {
+    let resolved = render::ResolvedCities::new(config);
+    let mut output = String::new();
+    let mut clocks = Vec::new();
+
+    let start = std::time::Instant::now();
+    for _ in 0..iterations {
+        render::render_swiftbar_into(&mut output, &resolved, Local::now(), &mut clocks);
+        std::hint::black_box(&output);
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{} cities, {} iterations, {:.2?} per render",
+        config.cities.len(),
+        iterations,
+        elapsed / iterations
+    );
+}
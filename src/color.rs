@@ -0,0 +1,43 @@
+//! Whether terminal output gets colors. `--color always|never` decides;
+//! otherwise `NO_COLOR` turns them off, `CLICOLOR_FORCE` on and
+//! `CLICOLOR=0` off (see no-color.org and bixense.com/clicolors), and
+//! without any of these colors go to terminals but not to pipes and files.
+
+use clap::ValueEnum;
+use std::env;
+use std::io::{self, IsTerminal};
+
+pub const RESET: &str = "\x1b[0m";
+pub const BOLD: &str = "\x1b[1m";
+pub const DIM: &str = "\x1b[2m";
+pub const RED: &str = "\x1b[31m";
+pub const GREEN: &str = "\x1b[32m";
+pub const YELLOW: &str = "\x1b[33m";
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum Choice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Whether to color what is printed to stdout
+pub fn enabled(choice: Choice) -> bool {
+    match choice {
+        Choice::Always => true,
+        Choice::Never => false,
+        Choice::Auto => {
+            let set = |name: &str| env::var_os(name).filter(|value| !value.is_empty());
+            if set("NO_COLOR").is_some() {
+                false
+            } else if set("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+                true
+            } else if set("CLICOLOR").is_some_and(|value| value == "0") {
+                false
+            } else {
+                io::stdout().is_terminal()
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! How comfortable a moment is for someone, from 0 (deep night) to 100
+//! (mid-morning).
+//!
+//! The same score orders the dropdown with `sort_by: comfort` and ranks
+//! meeting times in `slots`, where a slot is only as good as it is for the
+//! worst-off city.
+
+use crate::Config;
+use crate::render::ResolvedCities;
+use chrono::{DateTime, Local, TimeDelta, Timelike};
+
+/// Score at each hour of the day, interpolated linearly in between
+const CURVE: [(u32, u8); 12] = [
+    (0, 0),
+    (5, 0),
+    (7, 40),
+    (9, 85),
+    (10, 100),
+    (12, 100),
+    (14, 90),
+    (17, 70),
+    (19, 40),
+    (21, 20),
+    (23, 5),
+    (24, 0),
+];
+
+/// Comfort of the local time `hour:minute`
+pub fn score(hour: u32, minute: u32) -> u8 {
+    let minutes = hour * 60 + minute;
+    CURVE
+        .windows(2)
+        .find(|pair| minutes < pair[1].0 * 60)
+        .map(|pair| {
+            let ((from_hour, from), (to_hour, to)) = (pair[0], pair[1]);
+            let span = (to_hour - from_hour) * 60;
+            let into = minutes - from_hour * 60;
+            let (from, to) = (i64::from(from), i64::from(to));
+            (from + (to - from) * i64::from(into) / i64::from(span)) as u8
+        })
+        .unwrap_or(0)
+}
+
+/// A candidate meeting time with its comfort across cities
+pub struct Slot {
+    pub start: DateTime<Local>,
+    /// Score of the worst-off city
+    pub min: u8,
+    pub average: u8,
+    pub scores: Vec<(String, u8)>,
+}
+
+/// Half-hour slots over the next `hours`, best first. Cities out of office
+/// at a slot don't count towards it.
+pub fn rank_slots(config: &Config, now: DateTime<Local>, hours: u32) -> Vec<Slot> {
+    let resolved = ResolvedCities::new(config);
+    let mut clocks = Vec::new();
+    // Start at the next full half hour
+    let first = now
+        .with_second(0)
+        .and_then(|t| t.with_nanosecond(0))
+        .unwrap_or(now)
+        + TimeDelta::minutes(i64::from(30 - now.minute() % 30));
+
+    let mut slots: Vec<Slot> = (0..hours * 2)
+        .filter_map(|i| {
+            let start = first + TimeDelta::minutes(i64::from(i) * 30);
+            resolved.clocks_into(start, &mut clocks);
+            let scores: Vec<(String, u8)> = clocks
+                .iter()
+                .filter(|clock| !clock.is_ooo())
+                .map(|clock| (clock.city.name.clone(), clock.comfort()))
+                .collect();
+            let min = scores.iter().map(|(_, score)| *score).min()?;
+            let total: u32 = scores.iter().map(|(_, score)| u32::from(*score)).sum();
+            Some(Slot {
+                start,
+                min,
+                average: (total / scores.len() as u32) as u8,
+                scores,
+            })
+        })
+        .collect();
+
+    // Stable, so equally good slots stay in chronological order
+    slots.sort_by_key(|slot| std::cmp::Reverse((slot.min, slot.average)));
+    slots
+}
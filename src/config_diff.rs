@@ -0,0 +1,210 @@
+//! `config diff`: what adopting one config instead of another would change,
+//! city by city and setting by setting. Both are compared as parsed, so
+//! formatting, comments and settings spelled out at their default don't
+//! show up.
+
+use crate::{Config, read_config_file, resolve_config_path};
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+use std::fmt::Write;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+
+/// A value before and after
+type Change = (Value, Value);
+
+#[derive(Default)]
+struct Diff {
+    added_cities: Vec<Value>,
+    removed_cities: Vec<Value>,
+    /// City name and its fields that differ
+    changed_cities: Vec<(String, Vec<(String, Change)>)>,
+    /// Whether the cities both have are in a different order
+    reordered: bool,
+    added_settings: Vec<(String, Value)>,
+    removed_settings: Vec<(String, Value)>,
+    changed_settings: Vec<(String, Change)>,
+}
+
+/// The differences from config `from` to config `to`
+pub fn diff(from: &str, to: &str, format: Format) -> Result<String, String> {
+    let load = |path: &str| -> Result<Value, String> {
+        let config: Config = read_config_file(&resolve_config_path(path)?)?;
+        serde_json::to_value(config).map_err(|e| e.to_string())
+    };
+    let diff = Diff::new(load(from)?, load(to)?);
+    Ok(match format {
+        Format::Text => diff.text(),
+        Format::Json => format!("{}\n", diff.json()),
+    })
+}
+
+impl Diff {
+    fn new(mut from: Value, mut to: Value) -> Self {
+        let mut diff = Diff::default();
+        let cities =
+            |config: &mut Value| match config.as_object_mut().and_then(|c| c.remove("cities")) {
+                Some(Value::Array(cities)) => cities,
+                _ => Vec::new(),
+            };
+        let (old, new) = (cities(&mut from), cities(&mut to));
+        let name = |city: &Value| city["name"].as_str().unwrap_or_default().to_string();
+        let find =
+            |cities: &[Value], wanted: &str| cities.iter().find(|c| name(c) == wanted).cloned();
+
+        for city in &old {
+            match find(&new, &name(city)) {
+                None => diff.removed_cities.push(city.clone()),
+                Some(other) => {
+                    let fields = changes(city, &other);
+                    if !fields.is_empty() {
+                        diff.changed_cities.push((name(city), fields));
+                    }
+                }
+            }
+        }
+        for city in &new {
+            if find(&old, &name(city)).is_none() {
+                diff.added_cities.push(city.clone());
+            }
+        }
+        let common = |one: &[Value], other: &[Value]| -> Vec<String> {
+            one.iter()
+                .map(name)
+                .filter(|n| other.iter().any(|city| name(city) == *n))
+                .collect()
+        };
+        diff.reordered = common(&old, &new) != common(&new, &old);
+
+        for (key, (before, after)) in changes(&from, &to) {
+            match (before, after) {
+                (Value::Null, after) => diff.added_settings.push((key, after)),
+                (before, Value::Null) => diff.removed_settings.push((key, before)),
+                change => diff.changed_settings.push((key, change)),
+            }
+        }
+        diff
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added_cities.is_empty()
+            && self.removed_cities.is_empty()
+            && self.changed_cities.is_empty()
+            && !self.reordered
+            && self.added_settings.is_empty()
+            && self.removed_settings.is_empty()
+            && self.changed_settings.is_empty()
+    }
+
+    fn text(&self) -> String {
+        if self.is_empty() {
+            return "No differences\n".to_string();
+        }
+        let mut output = String::new();
+        let describe = |city: &Value| {
+            format!(
+                "{} ({})",
+                city["name"].as_str().unwrap_or_default(),
+                city["timezone"].as_str().unwrap_or_default()
+            )
+        };
+        for city in &self.added_cities {
+            let _ = writeln!(output, "+ city {}", describe(city));
+        }
+        for city in &self.removed_cities {
+            let _ = writeln!(output, "- city {}", describe(city));
+        }
+        for (name, fields) in &self.changed_cities {
+            for (field, (before, after)) in fields {
+                let _ = writeln!(
+                    output,
+                    "~ city {}: {} {} → {}",
+                    name,
+                    field,
+                    show(before),
+                    show(after)
+                );
+            }
+        }
+        if self.reordered {
+            output.push_str("~ cities in a different order\n");
+        }
+        for (key, value) in &self.added_settings {
+            let _ = writeln!(output, "+ {}: {}", key, show(value));
+        }
+        for (key, value) in &self.removed_settings {
+            let _ = writeln!(output, "- {}: {}", key, show(value));
+        }
+        for (key, (before, after)) in &self.changed_settings {
+            let _ = writeln!(output, "~ {}: {} → {}", key, show(before), show(after));
+        }
+        output
+    }
+
+    fn json(&self) -> Value {
+        let change = |(before, after): &Change| json!({ "from": before, "to": after });
+        let changed_cities: Vec<Value> = self
+            .changed_cities
+            .iter()
+            .map(|(name, fields)| {
+                let fields: Map<String, Value> = fields
+                    .iter()
+                    .map(|(field, field_change)| (field.clone(), change(field_change)))
+                    .collect();
+                json!({ "name": name, "fields": fields })
+            })
+            .collect();
+        let settings = |settings: &[(String, Value)]| -> Map<String, Value> {
+            settings.iter().cloned().collect()
+        };
+        let changed_settings: Map<String, Value> = self
+            .changed_settings
+            .iter()
+            .map(|(key, setting_change)| (key.clone(), change(setting_change)))
+            .collect();
+        json!({
+            "cities": {
+                "added": self.added_cities,
+                "removed": self.removed_cities,
+                "changed": changed_cities,
+                "reordered": self.reordered,
+            },
+            "settings": {
+                "added": settings(&self.added_settings),
+                "removed": settings(&self.removed_settings),
+                "changed": changed_settings,
+            },
+        })
+    }
+}
+
+/// Keys of two JSON objects whose values differ, a missing key as null
+fn changes(before: &Value, after: &Value) -> Vec<(String, Change)> {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+    let mut keys: Vec<&String> = before.keys().chain(after.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .filter_map(|key| {
+            let old = before.get(key).cloned().unwrap_or(Value::Null);
+            let new = after.get(key).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| (key.clone(), (old, new)))
+        })
+        .collect()
+}
+
+/// Strings as they are, anything else as compact JSON
+fn show(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => "(none)".to_string(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,339 @@
+//! Edits to the config file that keep its comments and layout, for changes
+//! made from the menu and `config add`/`remove`, and the starter config of
+//! `config init`. `write_config_file` serializes the whole config again,
+//! which is fine for the TUI and gist sync but would strip a hand-written
+//! file of its comments.
+//!
+//! TOML and JSON configs are parsed, edited and written out whole instead.
+//!
+//! Only the block list under `cities:` is understood. Each entry, with the
+//! comments right above it, is moved around as lines of text, and the result
+//! is parsed again before anything is written.
+
+use crate::config_format::Format;
+use crate::{CityConfig, Config, backup, managed_files, read_config_file, write_config_file};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// Commented starter config with the default cities
+const STARTER: &str = include_str!("../data/starter.yaml");
+
+/// Write the starter config to `path`, replacing an existing file (after a
+/// backup) only with `force`
+pub fn init(path: &Path, force: bool) -> Result<(), String> {
+    if path.exists() {
+        if !force {
+            return Err(format!(
+                "{} already exists, pass --force to replace it",
+                path.display()
+            ));
+        }
+        backup::backup(path, &managed_files(path)).map_err(|e| e.to_string())?;
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    // Only the YAML starter has comments to walk a new user through it
+    let content = match Format::of_path(path) {
+        Format::Yaml => STARTER.to_string(),
+        format => format.serialize(&Config::default())?,
+    };
+    fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Move the city `name` one place up or down in the file's `cities` list
+pub fn move_city(path: &Path, name: &str, up: bool) -> Result<(), String> {
+    if Format::of_path(path) != Format::Yaml {
+        return edit_parsed(path, |cities| {
+            let index = position(cities, path, name)?;
+            let other = match (up, index) {
+                (true, 0) => return Err(format!("{} is already first", name)),
+                (true, _) => index - 1,
+                (false, _) if index + 1 == cities.len() => {
+                    return Err(format!("{} is already last", name));
+                }
+                (false, _) => index + 1,
+            };
+            cities.swap(index, other);
+            Ok(())
+        });
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let names = city_names(&text)?;
+    let index = names
+        .iter()
+        .position(|n| n == name)
+        .ok_or_else(|| format!("{} is not in {}", name, path.display()))?;
+    let other = match (up, index) {
+        (true, 0) => return Err(format!("{} is already first", name)),
+        (true, _) => index - 1,
+        (false, _) if index + 1 == names.len() => {
+            return Err(format!("{} is already last", name));
+        }
+        (false, _) => index + 1,
+    };
+
+    let lines: Vec<&str> = text.lines().collect();
+    let entries = entries(&lines)?;
+    if entries.len() != names.len() {
+        return Err(format!(
+            "{}: found {} of {} cities as list entries, edit it by hand",
+            path.display(),
+            entries.len(),
+            names.len()
+        ));
+    }
+    let (first, second) = (&entries[index.min(other)], &entries[index.max(other)]);
+    let mut edited: Vec<&str> = Vec::with_capacity(lines.len());
+    edited.extend(&lines[..first.start]);
+    edited.extend(&lines[second.clone()]);
+    edited.extend(&lines[first.end..second.start]);
+    edited.extend(&lines[first.clone()]);
+    edited.extend(&lines[second.end..]);
+    let mut edited = edited.join("\n");
+    if text.ends_with('\n') {
+        edited.push('\n');
+    }
+
+    // Never write a file that doesn't say what was meant
+    let mut expected = names;
+    expected.swap(index, other);
+    if city_names(&edited)? != expected {
+        return Err(format!(
+            "{}: moving {} would change more than the order, edit it by hand",
+            path.display(),
+            name
+        ));
+    }
+    backup::backup(path, &managed_files(path)).map_err(|e| e.to_string())?;
+    fs::write(path, edited).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Append a city with just a name and timezone to the file's `cities` list,
+/// creating the file if there is none yet
+pub fn add_city(path: &Path, name: &str, timezone: &str) -> Result<(), String> {
+    if Format::of_path(path) != Format::Yaml {
+        return edit_parsed(path, |cities| {
+            if cities.iter().any(|city| city.name == name) {
+                return Err(format!("{} is already in {}", name, path.display()));
+            }
+            cities.push(CityConfig {
+                name: name.to_string(),
+                timezone: timezone.to_string(),
+                ..Default::default()
+            });
+            Ok(())
+        });
+    }
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => "cities:\n".to_string(),
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    };
+    let lines: Vec<&str> = text.lines().collect();
+    let entries = entries(&lines)?;
+    // An empty `cities:` is null to serde
+    let names = if entries.is_empty() {
+        city_names(&text).unwrap_or_default()
+    } else {
+        city_names(&text)?
+    };
+    if names.iter().any(|n| n == name) {
+        return Err(format!("{} is already in {}", name, path.display()));
+    }
+    if entries.len() != names.len() {
+        return Err(format!(
+            "{}: found {} of {} cities as list entries, edit it by hand",
+            path.display(),
+            entries.len(),
+            names.len()
+        ));
+    }
+    // Same indentation as the entries already there
+    let (position, indent) = match entries.last() {
+        Some(last) => {
+            let item = lines[last.clone()]
+                .iter()
+                .find(|line| line.trim_start().starts_with('-'))
+                .copied()
+                .unwrap_or_default();
+            (last.end, item.len() - item.trim_start().len())
+        }
+        None => (
+            lines
+                .iter()
+                .position(|line| line.starts_with("cities:"))
+                .map_or(lines.len(), |i| i + 1),
+            2,
+        ),
+    };
+    let scalar = |value: &str| -> Result<String, String> {
+        let yaml = serde_yaml::to_string(value).map_err(|e| e.to_string())?;
+        Ok(yaml.trim_end().to_string())
+    };
+    let entry = [
+        format!("{}- name: {}", " ".repeat(indent), scalar(name)?),
+        format!("{}  timezone: {}", " ".repeat(indent), scalar(timezone)?),
+    ];
+    let mut edited: Vec<&str> = Vec::with_capacity(lines.len() + 2);
+    edited.extend(&lines[..position]);
+    edited.extend(entry.iter().map(String::as_str));
+    edited.extend(&lines[position..]);
+    let mut edited = edited.join("\n");
+    edited.push('\n');
+
+    let mut expected = names;
+    expected.push(name.to_string());
+    if city_names(&edited)? != expected {
+        return Err(format!(
+            "{}: adding {} would change more than the list, edit it by hand",
+            path.display(),
+            name
+        ));
+    }
+    if path.exists() {
+        backup::backup(path, &managed_files(path)).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, edited).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Remove the city `name`, with the comments right above it, from the file's
+/// `cities` list
+pub fn remove_city(path: &Path, name: &str) -> Result<(), String> {
+    if Format::of_path(path) != Format::Yaml {
+        return edit_parsed(path, |cities| {
+            cities.remove(position(cities, path, name)?);
+            Ok(())
+        });
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let names = city_names(&text)?;
+    let index = names
+        .iter()
+        .position(|n| n == name)
+        .ok_or_else(|| format!("{} is not in {}", name, path.display()))?;
+
+    let lines: Vec<&str> = text.lines().collect();
+    let entries = entries(&lines)?;
+    if entries.len() != names.len() {
+        return Err(format!(
+            "{}: found {} of {} cities as list entries, edit it by hand",
+            path.display(),
+            entries.len(),
+            names.len()
+        ));
+    }
+    let entry = &entries[index];
+    let mut edited: Vec<&str> = Vec::with_capacity(lines.len());
+    edited.extend(&lines[..entry.start]);
+    edited.extend(&lines[entry.end..]);
+    let mut edited = edited.join("\n");
+    if text.ends_with('\n') {
+        edited.push('\n');
+    }
+
+    let mut expected = names;
+    expected.remove(index);
+    // The last city leaves `cities:` empty, which is null to serde
+    let remaining = if expected.is_empty() {
+        city_names(&edited).unwrap_or_default()
+    } else {
+        city_names(&edited)?
+    };
+    if remaining != expected {
+        return Err(format!(
+            "{}: removing {} would change more than the list, edit it by hand",
+            path.display(),
+            name
+        ));
+    }
+    backup::backup(path, &managed_files(path)).map_err(|e| e.to_string())?;
+    fs::write(path, edited).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// TOML and JSON configs are edited as data and written out whole, so
+/// comments in a TOML file are lost
+fn edit_parsed(
+    path: &Path,
+    edit: impl FnOnce(&mut Vec<CityConfig>) -> Result<(), String>,
+) -> Result<(), String> {
+    if !path.exists() {
+        let mut config = Config {
+            cities: Vec::new(),
+            ..Config::default()
+        };
+        edit(&mut config.cities)?;
+        let content = Format::of_path(path).serialize(&config)?;
+        return fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+    let mut config = read_config_file(path)?;
+    edit(&mut config.cities)?;
+    write_config_file(path, &config)
+}
+
+fn position(cities: &[CityConfig], path: &Path, name: &str) -> Result<usize, String> {
+    cities
+        .iter()
+        .position(|city| city.name == name)
+        .ok_or_else(|| format!("{} is not in {}", name, path.display()))
+}
+
+fn city_names(text: &str) -> Result<Vec<String>, String> {
+    let config: Config = serde_yaml::from_str(text).map_err(|e| e.to_string())?;
+    Ok(config.cities.into_iter().map(|city| city.name).collect())
+}
+
+/// Lines of each entry under `cities:`, from the comments directly above its
+/// `-` to its last line of content or of comments indented like its fields.
+/// Blank lines and comments further apart stay where they are.
+fn entries(lines: &[&str]) -> Result<Vec<Range<usize>>, String> {
+    let Some(list) = lines.iter().position(|line| {
+        line.strip_prefix("cities:")
+            .is_some_and(|rest| rest.trim().is_empty() || rest.trim_start().starts_with('#'))
+    }) else {
+        return Err("cities: not a block list, edit the file by hand".to_string());
+    };
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let is_item = |line: &str| {
+        let rest = line.trim_start();
+        rest == "-" || rest.starts_with("- ")
+    };
+
+    let mut entries: Vec<Range<usize>> = Vec::new();
+    let mut item_indent = None;
+    // Start of the comments right above the next line of content
+    let mut comments = None;
+    for (i, line) in lines.iter().enumerate().skip(list + 1) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            comments = None;
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            // Indented like the fields, it belongs to the entry it follows
+            if let Some(item_indent) = item_indent
+                && indent(line) > item_indent
+                && let Some(entry) = entries.last_mut()
+                && entry.end == i
+            {
+                entry.end = i + 1;
+            } else {
+                comments.get_or_insert(i);
+            }
+            continue;
+        }
+        let item_indent = *item_indent.get_or_insert(indent(line));
+        if indent(line) == item_indent && is_item(line) {
+            entries.push(comments.unwrap_or(i)..i + 1);
+        } else if indent(line) > item_indent
+            && let Some(entry) = entries.last_mut()
+        {
+            entry.end = i + 1;
+        } else {
+            break;
+        }
+        comments = None;
+    }
+    Ok(entries)
+}
@@ -0,0 +1,106 @@
+//! Configs in YAML, TOML or JSON. The format follows the extension
+//! (`.yaml`/`.yml`, `.toml`, `.json`); without one of these, as for most
+//! URLs, it is guessed from the content.
+
+use crate::{Config, interpolate};
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl Format {
+    /// The format of the config at `source`, a path or URL, with `text`
+    pub fn detect(source: &str, text: &str) -> Self {
+        Format::from_extension(source).unwrap_or_else(|| Format::sniff(text))
+    }
+
+    /// The format a config at `path` is written in; YAML for new files
+    /// without a known extension
+    pub fn of_path(path: &Path) -> Self {
+        Format::from_extension(&path.to_string_lossy()).unwrap_or(Format::Yaml)
+    }
+
+    pub fn from_extension(source: &str) -> Option<Self> {
+        // URLs may carry a query after the file name
+        let name = source.split(['?', '#']).next().unwrap_or(source);
+        let extension = Path::new(name).extension()?.to_str()?;
+        match extension.to_ascii_lowercase().as_str() {
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    /// JSON starts with `{`, TOML with `key = value` or a `[table]`
+    fn sniff(text: &str) -> Self {
+        let first = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .unwrap_or_default();
+        let is_key = |key: &str| {
+            let key = key.trim().trim_matches('"');
+            !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        };
+        if first.starts_with('{') {
+            Format::Json
+        } else if first.starts_with('[')
+            || first.split_once('=').is_some_and(|(key, _)| is_key(key))
+        {
+            Format::Toml
+        } else {
+            Format::Yaml
+        }
+    }
+
+    /// Parse and expand `${VAR}` and `~/`, see the `interpolate` module
+    pub fn parse(self, text: &str) -> Result<Config, String> {
+        let config = self.parse_as_written(text)?;
+        if interpolate::is_needed(text) {
+            interpolate::config(config)
+        } else {
+            Ok(config)
+        }
+    }
+
+    /// Parse without expanding anything, for edits that write the config back
+    pub fn parse_as_written(self, text: &str) -> Result<Config, String> {
+        match self {
+            Format::Yaml => serde_yaml::from_str(text).map_err(|e| e.to_string()),
+            Format::Toml => toml::from_str(text).map_err(|e| e.to_string()),
+            Format::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// What the config is called in backups and bundles
+    pub fn entry_name(self) -> &'static str {
+        match self {
+            Format::Yaml => "config.yaml",
+            Format::Toml => "config.toml",
+            Format::Json => "config.json",
+        }
+    }
+
+    pub fn serialize(self, config: &Config) -> Result<String, String> {
+        match self {
+            Format::Yaml => serde_yaml::to_string(config).map_err(|e| e.to_string()),
+            Format::Toml => toml::to_string(config).map_err(|e| e.to_string()),
+            Format::Json => serde_json::to_string_pretty(config)
+                .map(|json| json + "\n")
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Parse the config at `source`, a path or URL, in its format
+pub fn parse(source: &str, text: &str) -> Result<Config, String> {
+    Format::detect(source, text).parse(text)
+}
@@ -0,0 +1,318 @@
+//! Converting a wall-clock time in one timezone to all configured cities.
+//!
+//! Shared by the `convert` subcommand and the server's `/convert` endpoint.
+
+use crate::render::{self, city_clocks, format_diff};
+use crate::{Config, airports, headers};
+use chrono::{
+    DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeDelta, TimeZone,
+    Timelike,
+};
+use chrono_tz::Tz;
+use serde_json::Value;
+
+const DATE_TIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S"];
+
+/// Parse `HH:MM` (the day of `now` in `tz`) or `YYYY-MM-DD HH:MM` as a time in `tz`
+pub fn parse_time<T: TimeZone>(
+    time: &str,
+    tz: &T,
+    now: DateTime<Local>,
+) -> Result<DateTime<T>, String> {
+    let time = time.trim();
+    let naive = DATE_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(time, format).ok())
+        .or_else(|| {
+            let today: NaiveDate = now.with_timezone(tz).date_naive();
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .ok()
+                .map(|t| today.and_time(t))
+        })
+        .ok_or_else(|| format!("can't parse '{}', expected HH:MM or YYYY-MM-DD HH:MM", time))?;
+
+    // Ambiguous times (DST fall-back) resolve to the earlier instant
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| format!("{} does not exist in that timezone (DST gap)", naive))
+}
+
+/// Resolve `time` in the named timezone or alias, or the local timezone if `tz` is `None`
+pub fn resolve(
+    config: &Config,
+    now: DateTime<Local>,
+    time: &str,
+    tz: Option<&str>,
+) -> Result<DateTime<Local>, String> {
+    match tz {
+        Some(name) => {
+            let tz = config.timezone(name)?;
+            Ok(parse_time(time, &tz, now)?.with_timezone(&Local))
+        }
+        None => parse_time(time, &Local, now),
+    }
+}
+
+/// The next `hours` full hours of the base timezone (home city or local),
+/// each next to the same instant in `tz`
+pub fn hour_by_hour(
+    config: &Config,
+    now: DateTime<Local>,
+    tz: Tz,
+    hours: u32,
+) -> Vec<(DateTime<FixedOffset>, DateTime<Tz>)> {
+    let base = render::base_timezone(config);
+    let in_base = |instant: DateTime<Local>| match base {
+        Some(base) => instant.with_timezone(&base).fixed_offset(),
+        None => instant.fixed_offset(),
+    };
+    // Whole hours are in the base timezone, which may be a half hour off UTC
+    let mine = in_base(now);
+    let first = now
+        - TimeDelta::seconds(i64::from(mine.minute() * 60 + mine.second()))
+        - TimeDelta::nanoseconds(i64::from(mine.nanosecond()))
+        + TimeDelta::hours(1);
+    (0..i64::from(hours))
+        .map(|i| {
+            let instant = first + TimeDelta::hours(i);
+            (in_base(instant), instant.with_timezone(&tz))
+        })
+        .collect()
+}
+
+pub fn convert_json(config: &Config, instant: DateTime<Local>) -> Value {
+    render::render_json(config, instant)
+}
+
+pub fn convert_text(config: &Config, instant: DateTime<Local>) -> String {
+    let mut output = format!("{}\n", instant.format("%a %Y-%m-%d %H:%M %Z"));
+    write_cities(&mut output, config, instant, instant.date_naive());
+    output
+}
+
+/// One line per configured city at `instant`. When the cities are not all on
+/// `day`, across midnight or the date line, every line has the full date and
+/// those on another day say how many days off it they are.
+fn write_cities(output: &mut String, config: &Config, instant: DateTime<Local>, day: NaiveDate) {
+    let clocks = city_clocks(config, instant);
+    let spread = clocks.iter().any(|clock| clock.time.date_naive() != day);
+    let date = if spread { "%a %b %-d" } else { "%a" };
+    for clock in clocks {
+        output.push_str(&format!(
+            "{} {} {}{}",
+            clock.formatted_time(),
+            clock.time.format(date),
+            clock.city.name,
+            format_diff(clock.diff_secs)
+        ));
+        match (clock.time.date_naive() - day).num_days() {
+            0 => {}
+            1 => output.push_str(", +1 day"),
+            -1 => output.push_str(", -1 day"),
+            days => output.push_str(&format!(", {:+} days", days)),
+        }
+        output.push('\n');
+    }
+}
+
+/// `start` plus `duration`, e.g. `"2025-05-01 23:00 America/Chicago"` and
+/// `+36h`, in the start's zone, in UTC and in all configured cities. Days are
+/// calendar days in the start's zone, hours and minutes are elapsed time.
+pub fn add_duration_text(
+    config: &Config,
+    now: DateTime<Local>,
+    start: &str,
+    duration: &str,
+) -> Result<String, String> {
+    // The zone is optional and comes last: "2025-05-01 23:00 America/Chicago"
+    let (time, tz) = match start.trim().rsplit_once(' ') {
+        Some((time, name)) if config.timezone(name).is_ok() => (time, config.timezone(name)?),
+        _ => (
+            start.trim(),
+            headers::local_timezone()
+                .ok_or("the local timezone is unknown, add one to the time")?,
+        ),
+    };
+    let start = match time {
+        "now" => now.with_timezone(&tz),
+        time => parse_time(time, &tz, now)?,
+    };
+    let (days, elapsed) = crate::duration::parse_calendar_duration(duration)?;
+    let end = add_days(start, days)?
+        .checked_add_signed(elapsed)
+        .ok_or_else(|| format!("{} is out of range", duration))?;
+
+    let format = "%a %Y-%m-%d %H:%M %Z";
+    let mut output = format!(
+        "{} ({})\n{} →\n{} ({})\n{}\n",
+        start.format(format),
+        tz.name(),
+        duration.trim(),
+        end.format(format),
+        tz.name(),
+        end.with_timezone(&chrono::Utc).format(format)
+    );
+    let (before, after) = (start.offset().fix(), end.offset().fix());
+    if before != after {
+        let change = after.local_minus_utc() - before.local_minus_utc();
+        output.push_str(&format!(
+            "Clocks in {} change by {} on the way\n",
+            tz.name(),
+            format_diff(change).trim().trim_matches(['(', ')'])
+        ));
+    }
+    output.push('\n');
+    let end = end.with_timezone(&Local);
+    write_cities(&mut output, config, end, end.date_naive());
+    Ok(output)
+}
+
+/// Arrival of a flight leaving at `departure` (`LHR 21:35` or
+/// `LHR 2025-05-01 21:35`, local time at the airport) and landing at `to`
+/// after `duration`, with the day shift and the time in all configured cities
+pub fn flight_text(
+    config: &Config,
+    now: DateTime<Local>,
+    departure: &str,
+    duration: &str,
+    to: &str,
+) -> Result<String, String> {
+    let (from, time) = departure
+        .trim()
+        .split_once(' ')
+        .ok_or("expected the departure as `<airport> <time>`, e.g. `LHR 21:35`")?;
+    let (from_name, from_tz) = place(config, from)?;
+    let (to_name, to_tz) = place(config, to)?;
+    let departs = parse_time(time, &from_tz, now)?;
+    let flight = crate::duration::parse_duration(duration)?;
+    if flight <= TimeDelta::zero() {
+        return Err(format!("flight duration '{}' must be positive", duration));
+    }
+    let arrives = (departs + flight).with_timezone(&to_tz);
+
+    let format = "%a %Y-%m-%d %H:%M %Z";
+    let shift = match (arrives.date_naive() - departs.date_naive()).num_days() {
+        0 => "same day".to_string(),
+        1 => "+1 day".to_string(),
+        -1 => "-1 day".to_string(),
+        days => format!("{:+} days", days),
+    };
+    let mut output = format!(
+        "Departs {} {}\nArrives {} {} ({})\nFlight time {}\n\nAt arrival:\n",
+        from_name,
+        departs.format(format),
+        to_name,
+        arrives.format(format),
+        shift,
+        crate::duration::format_hm(flight)
+    );
+    let arrives = arrives.with_timezone(&Local);
+    write_cities(&mut output, config, arrives, arrives.date_naive());
+    Ok(output)
+}
+
+/// An airport code, or else a timezone or alias, with how to show it
+fn place(config: &Config, code: &str) -> Result<(String, Tz), String> {
+    if let Some((city, tz)) = airports::lookup(code) {
+        return Ok((format!("{} ({})", city, code.to_ascii_uppercase()), tz));
+    }
+    let tz = config
+        .timezone(code)
+        .map_err(|_| format!("unknown airport '{}', give its timezone instead", code))?;
+    Ok((code.to_string(), tz))
+}
+
+/// The same wall clock time `days` later, moved past the gap if a DST change
+/// skips it
+fn add_days(time: DateTime<Tz>, days: i64) -> Result<DateTime<Tz>, String> {
+    let naive = time.naive_local() + TimeDelta::try_days(days).ok_or("too many days")?;
+    let tz = time.timezone();
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(naive + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .ok_or_else(|| format!("{} does not exist in {}", naive, tz.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CityConfig, Role};
+    use chrono::Utc;
+
+    fn city(name: &str, timezone: &str) -> CityConfig {
+        CityConfig {
+            name: name.to_string(),
+            timezone: timezone.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The zones either side of the date line, relative to a UTC home
+    fn date_line() -> Config {
+        let mut home = city("Home", "UTC");
+        home.role = Some(Role::Home);
+        Config {
+            cities: vec![
+                home,
+                city("Kiritimati", "Pacific/Kiritimati"),
+                city("Apia", "Pacific/Apia"),
+                city("Pago Pago", "Pacific/Pago_Pago"),
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// The city lines at `utc`, with days counted from its UTC date
+    fn lines(config: &Config, utc: DateTime<Utc>) -> String {
+        let mut output = String::new();
+        write_cities(
+            &mut output,
+            config,
+            utc.with_timezone(&Local),
+            utc.date_naive(),
+        );
+        output
+    }
+
+    #[test]
+    fn same_day_keeps_the_weekday_only() {
+        let config = Config {
+            cities: date_line().cities.into_iter().take(1).collect(),
+            ..Default::default()
+        };
+        let at = Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap();
+        assert_eq!(lines(&config, at), "12:00 Sat Home\n");
+    }
+
+    #[test]
+    fn kiribati_and_samoa_are_a_day_ahead_of_american_samoa() {
+        let at = Utc.with_ymd_and_hms(2024, 6, 15, 11, 0, 0).unwrap();
+        assert_eq!(
+            lines(&date_line(), at),
+            "11:00 Sat Jun 15 Home\n\
+             01:00 Sun Jun 16 Kiritimati (+14h), +1 day\n\
+             00:00 Sun Jun 16 Apia (+13h), +1 day\n\
+             00:00 Sat Jun 15 Pago Pago (-11h)\n"
+        );
+    }
+
+    #[test]
+    fn date_line_puts_neighbours_on_both_sides_of_utc() {
+        let at = Utc.with_ymd_and_hms(2024, 6, 15, 0, 30, 0).unwrap();
+        let output = lines(&date_line(), at);
+        assert!(output.contains("14:30 Sat Jun 15 Kiritimati (+14h)\n"));
+        assert!(output.contains("13:30 Fri Jun 14 Pago Pago (-11h), -1 day\n"));
+    }
+
+    #[test]
+    fn samoa_skipped_december_30_2011() {
+        // Apia moved from UTC-10 to UTC+14 at the end of December 29
+        let before = Utc.with_ymd_and_hms(2011, 12, 30, 9, 0, 0).unwrap();
+        let after = Utc.with_ymd_and_hms(2011, 12, 30, 10, 0, 0).unwrap();
+        assert!(lines(&date_line(), before).contains("23:00 Thu Dec 29 Apia (-10h), -1 day\n"));
+        assert!(lines(&date_line(), after).contains("00:00 Sat Dec 31 Apia (+14h), +1 day\n"));
+    }
+}
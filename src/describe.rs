@@ -0,0 +1,189 @@
+//! `describe`: the config summed up in a few plain sentences, for reviewing a
+//! shared team config without reading the YAML.
+
+use crate::render::{format_utc_offset, select_clocks};
+use crate::{Config, Role, TimeFormat};
+use chrono::{DateTime, Local};
+use std::collections::BTreeSet;
+
+pub fn describe(config: &Config, now: DateTime<Local>) -> String {
+    let mut sentences = Vec::new();
+    let clocks = select_clocks(config, now, None).unwrap_or_default();
+
+    let zones: BTreeSet<&str> = config.cities.iter().map(|c| c.timezone.as_str()).collect();
+    let mut tracking = format!(
+        "Tracking {} across {}",
+        count(config.cities.len(), "city", "cities"),
+        count(zones.len(), "zone", "zones")
+    );
+    let offsets = clocks.iter().map(|clock| clock.utc_offset_secs);
+    if let (Some(min), Some(max)) = (offsets.clone().min(), offsets.max())
+        && min != max
+    {
+        tracking.push_str(&format!(
+            " ({} to {})",
+            format_utc_offset(min),
+            format_utc_offset(max)
+        ));
+    }
+    sentences.push(tracking);
+
+    let mut roles = Vec::new();
+    for role in [Role::Home, Role::Hq, Role::Office, Role::Customer] {
+        let names: Vec<&str> = config
+            .cities
+            .iter()
+            .filter(|city| city.role == Some(role))
+            .map(|city| city.name.as_str())
+            .collect();
+        if !names.is_empty() {
+            let verb = if names.len() == 1 { "is" } else { "are" };
+            roles.push(format!("{} {} {}", role.label(), verb, join(&names)));
+        }
+    }
+    if !roles.is_empty() {
+        sentences.push(capitalize(&roles.join("; ")));
+    }
+    let mut hours = "Working hours are 09:00–17:00 on weekdays".to_string();
+    let custom: Vec<String> = config
+        .cities
+        .iter()
+        .filter(|city| city.work_start.is_some() || city.work_end.is_some())
+        .map(|city| {
+            let (start, end) = city.working_hours();
+            format!(
+                "{} {}–{}",
+                city.name,
+                start.format("%H:%M"),
+                end.format("%H:%M")
+            )
+        })
+        .collect();
+    if !custom.is_empty() {
+        let custom: Vec<&str> = custom.iter().map(String::as_str).collect();
+        hours.push_str(&format!(", except {}", join(&custom)));
+    }
+    sentences.push(hours);
+
+    let ooo: Vec<String> = clocks
+        .iter()
+        .filter(|clock| clock.is_ooo())
+        .filter_map(|clock| {
+            let until = clock.city.ooo_until?;
+            Some(format!(
+                "{} until {}",
+                clock.city.name,
+                until.format("%b %-d")
+            ))
+        })
+        .collect();
+    if !ooo.is_empty() {
+        sentences.push(format!("Out of office: {}", ooo.join(", ")));
+    }
+
+    let conditional = config.cities.iter().filter(|c| c.show_if.is_some()).count();
+    if conditional > 0 {
+        sentences.push(format!(
+            "{} only shown when a rule holds",
+            capitalize(&count(conditional, "city is", "cities are"))
+        ));
+    }
+    let project = config
+        .cities
+        .iter()
+        .filter(|c| c.active_from.is_some() || c.active_until.is_some())
+        .count();
+    if project > 0 {
+        sentences.push(format!(
+            "{} shown for a limited time",
+            capitalize(&count(project, "city is", "cities are"))
+        ));
+    }
+    let contacts = config.cities.iter().filter(|c| c.track_contact).count();
+    if contacts > 0 {
+        sentences.push(format!(
+            "Last contact is tracked for {}",
+            count(contacts, "city", "cities")
+        ));
+    }
+    if !config.remote.is_empty() {
+        sentences.push(format!(
+            "Cities are merged in from {}",
+            count(config.remote.len(), "remote config", "remote configs")
+        ));
+    }
+
+    let mut shown = vec![match config.time_format() {
+        TimeFormat::TwentyFourHour => "24-hour times",
+        TimeFormat::TwelveHour => "12-hour times",
+    }];
+    if config.calendar {
+        shown.push("a calendar");
+    }
+    if !config.projections.is_empty() {
+        shown.push("projections");
+    }
+    if config.merge_same_offset {
+        shown.push("cities at the same offset merged");
+    }
+    sentences.push(format!("The menu shows {}", join(&shown)));
+
+    let mut extras = Vec::new();
+    if !config.events.is_empty() {
+        extras.push(count(config.events.len(), "event", "events"));
+    }
+    if !config.extra_lines.is_empty() {
+        extras.push(count(
+            config.extra_lines.len(),
+            "extra section",
+            "extra sections",
+        ));
+    }
+    if !config.aliases.is_empty() {
+        extras.push(count(
+            config.aliases.len(),
+            "timezone alias",
+            "timezone aliases",
+        ));
+    }
+    if !extras.is_empty() {
+        let extras: Vec<&str> = extras.iter().map(String::as_str).collect();
+        sentences.push(format!("Also configured: {}", join(&extras)));
+    }
+    if config.honor_focus {
+        sentences.push("The menu is dimmed during Focus".to_string());
+    }
+    if config.speak {
+        sentences.push("Times can be read aloud".to_string());
+    }
+    if config.paste_meeting {
+        sentences.push("Times copied from chat can be converted from the menu".to_string());
+    }
+
+    let mut output = sentences.join(".\n");
+    output.push_str(".\n");
+    output
+}
+
+/// `1 city`, `3 cities`
+fn count(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+/// `a`, `a and b`, `a, b and c`
+fn join(items: &[&str]) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.to_string(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+/// First letter in upper case
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,120 @@
+//! `digest --week`: the coming week as Markdown, for a Monday team note.
+//! Clock changes in the configured zones, the configured `events` and the
+//! longest window each day in which everyone is at work, all in local time.
+
+use crate::render::{self, city_clocks};
+use crate::{Config, TimeFormat, dst, duration, overlap};
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+use std::fmt::Write;
+
+const DAYS: usize = 7;
+
+pub fn week(config: &Config, now: DateTime<Local>) -> String {
+    let today = now.date_naive();
+    let last = today + chrono::Days::new(DAYS as u64 - 1);
+    let twelve_hour = config.time_format() == TimeFormat::TwelveHour;
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "## Week of {} – {}\n",
+        today.format("%b %-d"),
+        last.format("%b %-d")
+    );
+
+    output.push_str("### Clock changes\n\n");
+    let mut zones: Vec<Tz> = config
+        .cities
+        .iter()
+        .filter_map(|city| config.timezone(&city.timezone).ok())
+        .collect();
+    zones.sort_by_key(|tz| tz.name());
+    zones.dedup();
+    let changes = dst::upcoming(&zones, now.with_timezone(&Utc), DAYS as u32);
+    // Zones changing together to the same abbreviation share a line, as in the menu
+    let mut lines: Vec<(&dst::Change, String, Vec<&str>)> = Vec::new();
+    for change in &changes {
+        let abbreviation = change.abbreviation();
+        let names = config
+            .cities
+            .iter()
+            .filter(|city| config.timezone(&city.timezone).ok() == Some(change.tz))
+            .map(|city| city.name.as_str());
+        match lines
+            .iter_mut()
+            .find(|(seen, a, _)| seen.at == change.at && *a == abbreviation)
+        {
+            Some((.., all)) => all.extend(names),
+            None => lines.push((change, abbreviation, names.collect())),
+        }
+    }
+    let format = if twelve_hour {
+        "%a %b %-d, %-l:%M %p"
+    } else {
+        "%a %b %-d, %H:%M"
+    };
+    for (change, abbreviation, names) in &lines {
+        let direction = if change.change_secs > 0 {
+            "forward"
+        } else {
+            "back"
+        };
+        let _ = writeln!(
+            output,
+            "- **{}**: {} — clocks go {} {} to {}",
+            change.at.with_timezone(&Local).format(format),
+            names.join(", "),
+            direction,
+            render::format_hours(change.change_secs.abs()).trim_start_matches('+'),
+            abbreviation
+        );
+    }
+    if lines.is_empty() {
+        output.push_str("- None\n");
+    }
+
+    output.push_str("\n### Events\n\n");
+    let mut events: Vec<_> = config
+        .events
+        .iter()
+        .filter(|event| (today..=last).contains(&event.date))
+        .collect();
+    events.sort_by_key(|event| event.date);
+    for event in &events {
+        let when = match (event.date - today).num_days() {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            days => format!("in {} days", days),
+        };
+        let _ = writeln!(
+            output,
+            "- **{}**: {} ({})",
+            event.date.format("%a %b %-d"),
+            event.title,
+            when
+        );
+    }
+    if events.is_empty() {
+        output.push_str("- None\n");
+    }
+
+    output.push_str("\n### Best overlap\n\n");
+    output.push_str("| Day | Everyone at work | Length |\n|---|---|---|\n");
+    let clocks = city_clocks(config, now);
+    for day in today.iter_days().take(DAYS) {
+        let best = overlap::windows(&clocks, day)
+            .into_iter()
+            .max_by_key(|window| window.duration());
+        let _ = match best {
+            Some(window) => writeln!(
+                output,
+                "| {} | {} | {} |",
+                day.format("%a %b %-d"),
+                window.format(twelve_hour),
+                duration::format_hm(window.duration())
+            ),
+            None => writeln!(output, "| {} | none | |", day.format("%a %b %-d")),
+        };
+    }
+    output
+}
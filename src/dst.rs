@@ -0,0 +1,69 @@
+//! Upcoming DST changes of the configured timezones, so a meeting that moves
+//! by an hour for half the team doesn't come as a surprise.
+//!
+//! chrono-tz doesn't expose its transition table, so each zone is walked a
+//! day at a time and a change in offset is narrowed down to the second.
+
+use chrono::{DateTime, Offset, TimeDelta, Utc};
+use chrono_tz::Tz;
+
+/// The moment a timezone's offset changes, and by how much
+pub struct Change {
+    pub tz: Tz,
+    pub at: DateTime<Utc>,
+    pub change_secs: i32,
+}
+
+impl Change {
+    /// Abbreviation in effect after the change, e.g. `BST`, or `UTC+3` for
+    /// zones the tz database has no letters for
+    pub fn abbreviation(&self) -> String {
+        let after = self.at.with_timezone(&self.tz);
+        let abbreviation = after.format("%Z").to_string();
+        if abbreviation.starts_with(['+', '-']) {
+            crate::render::format_utc_offset(after.offset().fix().local_minus_utc())
+        } else {
+            abbreviation
+        }
+    }
+}
+
+/// Changes in `zones` after `now` and within `days`, earliest first
+pub fn upcoming(zones: &[Tz], now: DateTime<Utc>, days: u32) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for &tz in zones {
+        let mut start = now;
+        for _ in 0..days {
+            let end = start + TimeDelta::days(1);
+            if offset(tz, start) != offset(tz, end) {
+                let at = narrow(tz, start, end);
+                changes.push(Change {
+                    tz,
+                    at,
+                    change_secs: offset(tz, at) - offset(tz, start),
+                });
+            }
+            start = end;
+        }
+    }
+    changes.sort_by_key(|change| change.at);
+    changes
+}
+
+fn offset(tz: Tz, instant: DateTime<Utc>) -> i32 {
+    instant.with_timezone(&tz).offset().fix().local_minus_utc()
+}
+
+/// First second in `start..=end` with the offset `end` has
+fn narrow(tz: Tz, mut start: DateTime<Utc>, mut end: DateTime<Utc>) -> DateTime<Utc> {
+    let after = offset(tz, end);
+    while end - start > TimeDelta::seconds(1) {
+        let middle = start + (end - start) / 2;
+        if offset(tz, middle) == after {
+            end = middle;
+        } else {
+            start = middle;
+        }
+    }
+    end
+}
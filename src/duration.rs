@@ -0,0 +1,116 @@
+//! Parsing of human-friendly durations such as `1s`, `90m`, `1h30m` or `-2d`.
+
+use chrono::TimeDelta;
+
+/// Parse a duration made of `<number><unit>` parts, optionally signed
+///
+/// Units are `d`, `h`, `m`, `s` and `ms`.
+pub fn parse_duration(text: &str) -> Result<TimeDelta, String> {
+    let (days, rest) = parse_calendar_duration(text)?;
+    TimeDelta::try_days(days)
+        .and_then(|days| days.checked_add(&rest))
+        .ok_or_else(|| format!("duration '{}' is out of range", text.trim()))
+}
+
+/// Like [`parse_duration`], with the days apart: a day is a calendar day, which
+/// is 23 or 25 hours long across a DST change, and the rest is elapsed time
+pub fn parse_calendar_duration(text: &str) -> Result<(i64, TimeDelta), String> {
+    let text = text.trim();
+    let (negative, mut rest) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if rest.is_empty() {
+        return Err(format!("invalid duration '{}'", text));
+    }
+
+    let mut days = 0i64;
+    let mut total = TimeDelta::zero();
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number: i64 = rest[..digits]
+            .parse()
+            .map_err(|_| format!("invalid duration '{}'", text))?;
+        rest = &rest[digits..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let part = match &rest[..unit_len] {
+            "d" => {
+                days = days
+                    .checked_add(number)
+                    .ok_or_else(|| format!("duration '{}' is out of range", text))?;
+                rest = &rest[unit_len..];
+                continue;
+            }
+            "h" => TimeDelta::try_hours(number),
+            "m" => TimeDelta::try_minutes(number),
+            "s" => TimeDelta::try_seconds(number),
+            "ms" => TimeDelta::try_milliseconds(number),
+            unit => return Err(format!("unknown unit '{}' in duration '{}'", unit, text)),
+        }
+        .ok_or_else(|| format!("duration '{}' is out of range", text))?;
+        total += part;
+        rest = &rest[unit_len..];
+    }
+
+    Ok(if negative {
+        (-days, -total)
+    } else {
+        (days, total)
+    })
+}
+
+/// Clap value parser for positive intervals
+pub fn parse_interval(text: &str) -> Result<std::time::Duration, String> {
+    parse_duration(text)?
+        .to_std()
+        .ok()
+        .filter(|d| !d.is_zero())
+        .ok_or_else(|| format!("interval '{}' must be positive", text))
+}
+
+/// Compact age such as `now`, `12m ago`, `5h ago` or `3d ago`
+pub fn format_ago(age: TimeDelta) -> String {
+    match age.num_minutes() {
+        ..1 => "now".to_string(),
+        minutes @ ..60 => format!("{}m ago", minutes),
+        minutes @ ..1440 => format!("{}h ago", minutes / 60),
+        minutes => format!("{}d ago", minutes / 1440),
+    }
+}
+
+/// Age in words, like `just now`, `1 hour ago` or `3 days ago`
+pub fn format_ago_words(age: TimeDelta) -> String {
+    let plural =
+        |n: i64, unit: &str| format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" });
+    match age.num_minutes() {
+        ..1 => "just now".to_string(),
+        minutes @ ..60 => plural(minutes, "minute"),
+        minutes @ ..1440 => plural(minutes / 60, "hour"),
+        minutes => plural(minutes / 1440, "day"),
+    }
+}
+
+/// Hours and minutes such as `9h 05m` or `45m`
+pub fn format_hm(delta: TimeDelta) -> String {
+    let minutes = delta.num_minutes();
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, minutes) => format!("{}h {:02}m", hours, minutes),
+    }
+}
+
+/// Hours and minutes in words, like `9 hours 5 minutes` or `1 minute`
+pub fn format_hm_words(delta: TimeDelta) -> String {
+    let minutes = delta.num_minutes();
+    let plural = |n: i64, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => plural(minutes, "minute"),
+        (hours, 0) => plural(hours, "hour"),
+        (hours, minutes) => format!("{} {}", plural(hours, "hour"), plural(minutes, "minute")),
+    }
+}
@@ -0,0 +1,68 @@
+//! Clock faces from a user-supplied icon pack instead of the emoji, set with
+//! `clock_faces:`. A pack is either a directory of 24 template PNGs or a list
+//! of 24 SF Symbol names, one per half hour from 12:00 to 11:30.
+//!
+//! A face that can't be loaded falls back to the emoji with a warning.
+
+use crate::{
+    CLOCK_0100, CLOCK_0130, CLOCK_0200, CLOCK_0230, CLOCK_0300, CLOCK_0330, CLOCK_0400, CLOCK_0430,
+    CLOCK_0500, CLOCK_0530, CLOCK_0600, CLOCK_0630, CLOCK_0700, CLOCK_0730, CLOCK_0800, CLOCK_0830,
+    CLOCK_0900, CLOCK_0930, CLOCK_1000, CLOCK_1030, CLOCK_1100, CLOCK_1130, CLOCK_1200, CLOCK_1230,
+    ClockFacesConfig, expand_home, get_accurate_clock_icon,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::fs;
+use std::path::PathBuf;
+
+/// The emoji faces in pack order
+const FACES: [&str; 24] = [
+    CLOCK_1200, CLOCK_1230, CLOCK_0100, CLOCK_0130, CLOCK_0200, CLOCK_0230, CLOCK_0300, CLOCK_0330,
+    CLOCK_0400, CLOCK_0430, CLOCK_0500, CLOCK_0530, CLOCK_0600, CLOCK_0630, CLOCK_0700, CLOCK_0730,
+    CLOCK_0800, CLOCK_0830, CLOCK_0900, CLOCK_0930, CLOCK_1000, CLOCK_1030, CLOCK_1100, CLOCK_1130,
+];
+
+/// SwiftBar parameter showing the pack's face for `hour:minute`, such as
+/// `sfimage=clock` or `templateImage=<base64>`. The directory wins if both
+/// are configured.
+pub fn image_param(
+    faces: &ClockFacesConfig,
+    hour: u32,
+    minute: u32,
+) -> Option<(&'static str, String)> {
+    let emoji = get_accurate_clock_icon(hour, minute);
+    let index = FACES.iter().position(|face| *face == emoji)?;
+
+    if let Some(dir) = &faces.dir {
+        let dir = expand_home(dir).unwrap_or_else(|| PathBuf::from(dir));
+        let path = dir.join(format!("{}.png", file_stem(index)));
+        match fs::read(&path) {
+            Ok(png) => return Some(("templateImage", STANDARD.encode(png))),
+            Err(err) => eprintln!(
+                "Warning: Cannot read clock face {}: {}",
+                path.display(),
+                err
+            ),
+        }
+    }
+    if !faces.symbols.is_empty() {
+        if faces.symbols.len() == FACES.len() {
+            return Some(("sfimage", faces.symbols[index].clone()));
+        }
+        eprintln!(
+            "Warning: clock_faces.symbols needs {} names, found {}",
+            FACES.len(),
+            faces.symbols.len()
+        );
+    }
+    None
+}
+
+/// `1200`, `1230`, `0100` … `1130`
+fn file_stem(index: usize) -> String {
+    let hour = match index / 2 {
+        0 => 12,
+        hour => hour,
+    };
+    format!("{:02}{:02}", hour, index % 2 * 30)
+}
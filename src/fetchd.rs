@@ -0,0 +1,59 @@
+//! Companion process keeping the caches fresh in the background.
+//!
+//! `fetchd` refreshes remote configs and includes and reruns command hooks on its own
+//! schedule. While its heartbeat is recent, renders only read the cache and
+//! never wait on the network or a slow command.
+
+use crate::{cache, hooks, include, load_config_file, remote};
+use std::thread;
+use std::time::Duration;
+
+const CACHE_NAMESPACE: &str = "fetchd";
+const HEARTBEAT: &str = "heartbeat";
+/// Commands run in the background can take longer than during a render
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn run(config: &str, interval: Duration) -> Result<(), String> {
+    loop {
+        refresh(config);
+        cache::put(CACHE_NAMESPACE, HEARTBEAT, &interval.as_secs().to_string());
+        thread::sleep(interval);
+    }
+}
+
+/// Whether a `fetchd` has refreshed the caches within the last two intervals.
+/// Always false with `--no-cache`, which makes renders refresh themselves.
+pub fn is_running() -> bool {
+    let Some(interval) = cache::get(CACHE_NAMESPACE, HEARTBEAT, Duration::MAX)
+        .and_then(|payload| payload.trim().parse::<u64>().ok())
+    else {
+        return false;
+    };
+    cache::age(CACHE_NAMESPACE, HEARTBEAT)
+        .is_some_and(|age| age < Duration::from_secs(interval * 2 + 5))
+}
+
+fn refresh(path: &str) {
+    if remote::is_url(path) {
+        remote::refresh(path);
+    }
+    let config = load_config_file(path.to_string());
+    for url in &config.remote {
+        remote::refresh(url);
+    }
+    include::refresh_remote(&config, path);
+
+    let commands = config
+        .cities
+        .iter()
+        .filter_map(|city| Some((city.exec.as_deref()?, city.exec_ttl)))
+        .chain(
+            config
+                .extra_lines
+                .iter()
+                .map(|extra| (extra.exec.as_str(), extra.ttl)),
+        );
+    for (command, ttl) in commands {
+        hooks::refresh(command, hooks::ttl(ttl), COMMAND_TIMEOUT);
+    }
+}
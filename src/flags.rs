@@ -0,0 +1,48 @@
+//! Country flags in front of city rows, with `flags: true`. The country is
+//! the city database's for a city of that name in the zone, otherwise the
+//! zone's own from the tz database, embedded from `data/zone_countries.tsv`
+//! (`scripts/build_zone_countries.py` regenerates it).
+
+use crate::{CityConfig, citydb};
+use chrono_tz::Tz;
+
+const DATA: &str = include_str!("../data/zone_countries.tsv");
+
+/// ISO 3166 code of the country `tz` is in, `None` for zones such as `UTC`
+pub fn zone_country(tz: Tz) -> Option<&'static str> {
+    DATA.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('\t'))
+        .find(|(zone, _)| *zone == tz.name())
+        .map(|(_, country)| country)
+}
+
+/// The flag of `city` in `tz`: its `flag`, or the flag of its country
+pub fn city_flag(city: &CityConfig, tz: Tz) -> Option<String> {
+    if let Some(flag) = &city.flag {
+        // An ISO code, or any emoji or text as it is; empty for none
+        return match emoji(flag) {
+            Some(emoji) => Some(emoji),
+            None => Some(flag.clone()).filter(|flag| !flag.is_empty()),
+        };
+    }
+    let country = citydb::lookup(&city.name)
+        .into_iter()
+        .find(|found| found.timezone == tz.name())
+        .map(|found| found.country)
+        .or_else(|| zone_country(tz))?;
+    emoji(country)
+}
+
+/// The flag emoji of an ISO 3166 code such as `JP`: the code's letters as
+/// regional indicator symbols
+pub fn emoji(country: &str) -> Option<String> {
+    if country.len() != 2 || !country.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    country
+        .to_ascii_uppercase()
+        .bytes()
+        .map(|b| char::from_u32(0x1F1E6 + u32::from(b - b'A')))
+        .collect()
+}
@@ -0,0 +1,51 @@
+//! Whether a macOS Focus (Do Not Disturb or any other mode) is on.
+//!
+//! macOS has no public API for it. By default the Focus assertions database is
+//! read, which needs Full Disk Access for SwiftBar; `focus_command` can bridge
+//! to anything else, e.g. a Shortcut printing the current Focus.
+
+use crate::{Config, expand_home, hooks};
+use serde_json::Value;
+use std::fs;
+use std::time::Duration;
+
+const ASSERTIONS: &str = "~/Library/DoNotDisturb/DB/Assertions.json";
+const COMMAND_TTL: Duration = Duration::from_secs(30);
+
+/// Whether Focus is on and the config asks to honor it
+pub fn is_active(config: &Config) -> bool {
+    if !config.honor_focus {
+        return false;
+    }
+    match &config.focus_command {
+        Some(command) => hooks::run_cached(command, COMMAND_TTL, hooks::DEFAULT_TIMEOUT)
+            .ok()
+            .as_deref()
+            .and_then(hooks::first_line)
+            .is_some_and(is_on),
+        None => has_assertions(),
+    }
+}
+
+/// Any output other than an explicit "off" means a Focus is on
+fn is_on(output: &str) -> bool {
+    !matches!(
+        output.to_lowercase().as_str(),
+        "" | "off" | "false" | "0" | "no" | "none"
+    )
+}
+
+fn has_assertions() -> bool {
+    let Some(content) = expand_home(ASSERTIONS).and_then(|path| fs::read_to_string(path).ok())
+    else {
+        return false;
+    };
+    let Ok(db) = serde_json::from_str::<Value>(&content) else {
+        return false;
+    };
+    db["data"].as_array().into_iter().flatten().any(|entry| {
+        entry["storeAssertionRecords"]
+            .as_array()
+            .is_some_and(|records| !records.is_empty())
+    })
+}
@@ -0,0 +1,28 @@
+//! Fuzzy matching for pickers: the query's characters have to appear in
+//! order, and matches at word starts and in unbroken runs rank higher.
+
+use crate::citydb::fold;
+
+/// Score of `candidate` for `query`, `None` if it doesn't match. Case and
+/// accents are ignored; an empty query matches everything equally.
+pub fn score(query: &str, candidate: &str) -> Option<i32> {
+    let query: Vec<char> = fold(query).chars().filter(|c| !c.is_whitespace()).collect();
+    let candidate: Vec<char> = fold(candidate).chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query {
+        let found = (next..candidate.len()).find(|&i| candidate[i] == wanted)?;
+        let word_start = found == 0 || !candidate[found - 1].is_alphanumeric();
+        score += match previous {
+            Some(previous) if previous + 1 == found => 8,
+            _ if word_start => 6,
+            _ => 1,
+        };
+        // Gaps cost a little, so tighter matches win among equals
+        score -= (found - next).min(5) as i32;
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
@@ -0,0 +1,222 @@
+//! Two-way sync of the city list with a GitHub gist.
+//!
+//! The version of the gist seen at the last sync is kept in the cache
+//! directory and used as the base of a three-way merge, so cities added on
+//! either side survive, while cities removed on one side stay removed.
+
+use crate::{
+    CityConfig, Config, cache, read_config_file, remote, resolve_config_path, write_config_file,
+};
+use serde_json::{Value, json};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const GIST_API: &str = "https://api.github.com/gists";
+const GIST_FILE_NAME: &str = "swiftbar_clock_config.yaml";
+
+pub fn sync(config: &str, id: &str, push: bool) -> Result<(), String> {
+    let token = env::var("GITHUB_TOKEN").map_err(|_| "GITHUB_TOKEN is not set".to_string())?;
+    let config_path = resolve_config_path(config)?;
+    let local = read_config_file(&config_path)?;
+
+    let remote = fetch_gist(id, &token)?;
+    let base_cities = match base_path(id).and_then(|p| fs::read_to_string(p).ok()) {
+        Some(content) => parse_cities(&content)?,
+        None => Vec::new(),
+    };
+
+    let outcome = plan(&base_cities, local, remote.as_deref(), push)?;
+    if let Some(config) = &outcome.local {
+        write_config_file(&config_path, config)?;
+        println!("Updated {}", config_path.display());
+    }
+    if let Some(content) = &outcome.push {
+        push_gist(id, &token, content)?;
+        println!("Updated gist {}", id);
+    }
+
+    if let Some(path) = base_path(id) {
+        let written = match &outcome.base {
+            Some(content) => {
+                if let Some(parent) = path.parent() {
+                    let _ = fs::create_dir_all(parent);
+                }
+                fs::write(&path, content)
+            }
+            None => fs::remove_file(&path).or_else(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            }),
+        };
+        written.map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// What a sync does, worked out before anything is written
+#[derive(Debug)]
+struct Outcome {
+    /// The merged config, if the local file changes
+    local: Option<Config>,
+    /// Content to push to the gist
+    push: Option<String>,
+    /// The new base: what the gist holds after the sync, so a city only
+    /// known locally is not mistaken for one dropped from the gist
+    base: Option<String>,
+}
+
+fn plan(
+    base: &[CityConfig],
+    mut local: Config,
+    remote: Option<&str>,
+    push: bool,
+) -> Result<Outcome, String> {
+    let remote_cities = match remote {
+        Some(content) => parse_cities(content)?,
+        None => Vec::new(),
+    };
+    let merged = merge(base, &local.cities, &remote_cities);
+    let changed = merged != local.cities;
+    local.cities = merged;
+
+    let (push, base) = if push && local.cities != remote_cities {
+        let content = serde_yaml::to_string(&local).map_err(|e| e.to_string())?;
+        (Some(content.clone()), Some(content))
+    } else {
+        (None, remote.map(str::to_string))
+    };
+    Ok(Outcome {
+        local: changed.then_some(local),
+        push,
+        base,
+    })
+}
+
+fn parse_cities(content: &str) -> Result<Vec<CityConfig>, String> {
+    serde_yaml::from_str::<Config>(content)
+        .map(|config| config.cities)
+        .map_err(|e| format!("invalid gist config: {}", e))
+}
+
+/// Three-way merge of city lists, keyed by city name
+///
+/// Remote order wins, cities only present locally are appended. When a city
+/// changed on both sides the remote version is kept.
+fn merge(base: &[CityConfig], local: &[CityConfig], remote: &[CityConfig]) -> Vec<CityConfig> {
+    let find = |cities: &[CityConfig], name: &str| cities.iter().find(|c| c.name == name).cloned();
+    let mut merged = Vec::new();
+
+    for theirs in remote {
+        let ours = find(local, &theirs.name);
+        let original = find(base, &theirs.name);
+        match (ours, original) {
+            // Changed locally only
+            (Some(ours), Some(original)) if ours != original && *theirs == original => {
+                merged.push(ours)
+            }
+            (Some(_), _) => merged.push(theirs.clone()),
+            // Deleted locally
+            (None, Some(_)) => {}
+            // Added remotely
+            (None, None) => merged.push(theirs.clone()),
+        }
+    }
+
+    for ours in local {
+        let in_remote = remote.iter().any(|c| c.name == ours.name);
+        let in_base = base.iter().any(|c| c.name == ours.name);
+        // Cities dropped from the gist since the last sync stay dropped
+        if !in_remote && !in_base {
+            merged.push(ours.clone());
+        }
+    }
+    merged
+}
+
+fn base_path(id: &str) -> Option<PathBuf> {
+    Some(cache::dir()?.join("gist").join(format!("{}.yaml", id)))
+}
+
+fn fetch_gist(id: &str, token: &str) -> Result<Option<String>, String> {
+    let gist: Value = remote::agent()
+        .get(format!("{}/{}", GIST_API, id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", "swiftbar_clocks")
+        .call()
+        .and_then(|mut response| response.body_mut().read_json())
+        .map_err(|e| format!("failed to fetch gist {}: {}", id, e))?;
+
+    let files = gist["files"].as_object();
+    let file = files.and_then(|files| files.get(GIST_FILE_NAME).or_else(|| files.values().next()));
+    Ok(file.and_then(|f| f["content"].as_str()).map(str::to_string))
+}
+
+fn push_gist(id: &str, token: &str, content: &str) -> Result<(), String> {
+    remote::agent()
+        .patch(format!("{}/{}", GIST_API, id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", "swiftbar_clocks")
+        .send_json(json!({ "files": { GIST_FILE_NAME: { "content": content } } }))
+        .map_err(|e| format!("failed to update gist {}: {}", id, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn city(name: &str) -> CityConfig {
+        CityConfig {
+            name: name.to_string(),
+            timezone: "UTC".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn config(names: &[&str]) -> Config {
+        Config {
+            cities: names.iter().map(|name| city(name)).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn names(cities: &[CityConfig]) -> Vec<&str> {
+        cities.iter().map(|city| city.name.as_str()).collect()
+    }
+
+    #[test]
+    fn no_push_then_push_keeps_local_only_cities() {
+        let gist = serde_yaml::to_string(&config(&["Tokyo"])).unwrap();
+
+        // `sync gist --no-push`: the gist's Tokyo joins, Berlin stays local
+        let first = plan(&[], config(&["Berlin"]), Some(&gist), false).unwrap();
+        let local = first.local.unwrap();
+        assert_eq!(names(&local.cities), ["Tokyo", "Berlin"]);
+        assert_eq!(first.push, None);
+        assert_eq!(first.base.as_deref(), Some(gist.as_str()));
+
+        // `sync gist`: Berlin was never on the gist, so it is pushed
+        let base = parse_cities(&first.base.unwrap()).unwrap();
+        let second = plan(&base, local, Some(&gist), true).unwrap();
+        assert!(second.local.is_none());
+        let pushed = parse_cities(&second.push.unwrap()).unwrap();
+        assert_eq!(names(&pushed), ["Tokyo", "Berlin"]);
+        assert_eq!(
+            names(&parse_cities(&second.base.unwrap()).unwrap()),
+            ["Tokyo", "Berlin"]
+        );
+    }
+
+    #[test]
+    fn cities_dropped_from_the_gist_stay_dropped() {
+        let pushed = serde_yaml::to_string(&config(&["Tokyo", "Berlin"])).unwrap();
+        let gist = serde_yaml::to_string(&config(&["Tokyo"])).unwrap();
+        let base = parse_cities(&pushed).unwrap();
+        let outcome = plan(&base, config(&["Tokyo", "Berlin"]), Some(&gist), true).unwrap();
+        assert_eq!(names(&outcome.local.unwrap().cities), ["Tokyo"]);
+        assert_eq!(outcome.push, None);
+    }
+}
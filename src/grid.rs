@@ -0,0 +1,112 @@
+//! Someone's hours laid over mine: one row per day of my local time, one
+//! column per hour, each cell holding the hour it is for them. Their working
+//! hours are shaded, so the grid doubles as an availability chart to hand to
+//! a new teammate.
+
+use crate::Config;
+use crate::render::{CityClock, select_clocks};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Offset, Timelike};
+use std::fmt::Write;
+
+/// Grid of `city`'s hours over today, or over the current week (Monday to
+/// Sunday) with `week`
+pub fn render(
+    config: &Config,
+    now: DateTime<Local>,
+    city: &str,
+    week: bool,
+    markdown: bool,
+) -> Result<String, String> {
+    let clock = select_clocks(config, now, Some(city))?.remove(0);
+    let city = clock.city;
+    let tz = clock.time.timezone();
+
+    let today = now.date_naive();
+    let days: Vec<NaiveDate> = if week {
+        let monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+        (0..7).map(|i| monday + Duration::days(i)).collect()
+    } else {
+        vec![today]
+    };
+
+    let mut output = String::new();
+    let mut off_the_hour = None;
+    if markdown {
+        output.push_str("| Day |");
+        for hour in 0..24 {
+            let _ = write!(output, " {:02} |", hour);
+        }
+        output.push_str("\n|---|");
+        output.push_str(&"---|".repeat(24));
+        output.push('\n');
+    } else {
+        let _ = write!(output, "{:<10}", "me");
+        for hour in 0..24 {
+            let _ = write!(output, " {:02} ", hour);
+        }
+        output.truncate(output.trim_end().len());
+        output.push('\n');
+    }
+
+    for day in days {
+        let label = day.format("%a %d").to_string();
+        if markdown {
+            let _ = write!(output, "| {} |", label);
+        } else {
+            let _ = write!(output, "{:<10}", label);
+        }
+        for hour in 0..24 {
+            // Hours skipped by a DST change have no cell of their own
+            let Some(start) = day
+                .and_hms_opt(hour, 0, 0)
+                .and_then(|t| t.and_local_timezone(Local).earliest())
+            else {
+                output.push_str(if markdown { " -- |" } else { " -- " });
+                continue;
+            };
+            let time = start.with_timezone(&tz);
+            let utc_offset_secs = time.offset().fix().local_minus_utc();
+            let theirs = CityClock {
+                city,
+                time,
+                utc_offset_secs,
+                diff_secs: utc_offset_secs - start.offset().fix().local_minus_utc(),
+                preset: None,
+                format: None,
+                merged: Vec::new(),
+                second: clock
+                    .second
+                    .map(|second| start.with_timezone(&second.timezone())),
+            };
+            if time.minute() != 0 {
+                off_the_hour = Some(time.minute());
+            }
+            let working = theirs.is_working_hours() && !theirs.is_ooo();
+            let _ = match (markdown, working) {
+                (true, true) => write!(output, " **{:02}** |", time.hour()),
+                (true, false) => write!(output, " {:02} |", time.hour()),
+                (false, true) => write!(output, "[{:02}]", time.hour()),
+                (false, false) => write!(output, " {:02} ", time.hour()),
+            };
+        }
+        output.truncate(output.trim_end().len());
+        output.push('\n');
+    }
+
+    let shading = if markdown { "Bold" } else { "Bracketed" };
+    let _ = writeln!(
+        output,
+        "\n{} hours are {}'s working hours ({}).",
+        shading,
+        city.name,
+        tz.name()
+    );
+    if let Some(minute) = off_the_hour {
+        let _ = writeln!(
+            output,
+            "{}'s hours start at :{:02} past each of mine.",
+            city.name, minute
+        );
+    }
+    Ok(output)
+}
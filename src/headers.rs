@@ -0,0 +1,137 @@
+//! Header lines shown above the city list, built from templates such as
+//! `"{local:%A %d %B}"` or `"Week {week}"`.
+//!
+//! Placeholders:
+//! - `{rfc2822}` (the default header), `{local}` and `{local:FORMAT}` for local time
+//! - `{utc}` and `{utc:FORMAT}` for UTC
+//! - `{week}` for the ISO week number, `{epoch}` for the Unix timestamp
+//! - `{zone}` for the local timezone summary, e.g. `Europe/Warsaw · CEST · UTC+2`,
+//!   or its parts `{tzname}`, `{tzabbr}` and `{offset}`
+//!
+//! `FORMAT` uses strftime syntax. Use `{{` and `}}` for literal braces.
+//! Unknown placeholders are left as they are.
+
+use crate::render::format_utc_offset;
+use crate::timefmt;
+use chrono::{DateTime, Datelike, Local, Offset, Utc};
+use chrono_tz::Tz;
+use std::env;
+use std::fmt::Write;
+
+pub const DEFAULT_HEADER: &str = "{rfc2822}";
+
+pub fn expand(template: &str, now: DateTime<Local>, output: &mut String) {
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        output.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            output.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        match tail[1..].find('}').filter(|_| tail.starts_with('{')) {
+            Some(end) => {
+                let placeholder = &tail[1..end + 1];
+                if !expand_placeholder(placeholder, now, output) {
+                    output.push_str(&tail[..end + 2]);
+                }
+                rest = &tail[end + 2..];
+            }
+            None => {
+                output.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+}
+
+/// The first invalid `FORMAT` of a `{local:FORMAT}` or `{utc:FORMAT}` in
+/// `template`
+pub fn check(template: &str) -> Result<(), String> {
+    let template = template.replace("{{", "").replace("}}", "");
+    for placeholder in template.split('{').skip(1) {
+        let Some((placeholder, _)) = placeholder.split_once('}') else {
+            continue;
+        };
+        if let Some((name, format)) = placeholder.split_once(':')
+            && matches!(name.trim(), "local" | "utc")
+        {
+            timefmt::check_format(format)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns `false` for unknown placeholders
+fn expand_placeholder(placeholder: &str, now: DateTime<Local>, output: &mut String) -> bool {
+    let (name, format) = match placeholder.split_once(':') {
+        Some((name, format)) => (name.trim(), Some(format)),
+        None => (placeholder.trim(), None),
+    };
+
+    let _ = match (name, format) {
+        ("rfc2822", None) | ("local", None) => write!(output, "{}", now.to_rfc2822()),
+        // An invalid format falls back to the default, as for city times
+        ("local", Some(format)) => match timefmt::check_format(format) {
+            Ok(()) => write!(output, "{}", now.format(format)),
+            Err(err) => {
+                eprintln!("Warning: {} in header", err);
+                write!(output, "{}", now.to_rfc2822())
+            }
+        },
+        ("utc", None) => write!(output, "{}", now.with_timezone(&Utc).to_rfc2822()),
+        ("utc", Some(format)) => {
+            let now = now.with_timezone(&Utc);
+            match timefmt::check_format(format) {
+                Ok(()) => write!(output, "{}", now.format(format)),
+                Err(err) => {
+                    eprintln!("Warning: {} in header", err);
+                    write!(output, "{}", now.to_rfc2822())
+                }
+            }
+        }
+        ("week", None) => write!(output, "{}", now.iso_week().week()),
+        ("epoch", None) => write!(output, "{}", now.timestamp()),
+        ("zone", None) => {
+            let offset = format_utc_offset(now.offset().fix().local_minus_utc());
+            match local_timezone() {
+                Some(tz) => write!(
+                    output,
+                    "{} · {} · {}",
+                    tz.name(),
+                    now.with_timezone(&tz).format("%Z"),
+                    offset
+                ),
+                None => write!(output, "{}", offset),
+            }
+        }
+        ("tzname", None) => match local_timezone() {
+            Some(tz) => write!(output, "{}", tz.name()),
+            None => write!(output, "{}", now.format("%:z")),
+        },
+        ("tzabbr", None) => match local_timezone() {
+            Some(tz) => write!(output, "{}", now.with_timezone(&tz).format("%Z")),
+            None => write!(output, "{}", now.format("%:z")),
+        },
+        ("offset", None) => write!(
+            output,
+            "{}",
+            format_utc_offset(now.offset().fix().local_minus_utc())
+        ),
+        _ => return false,
+    };
+    true
+}
+
+/// The local timezone as a tz database entry, from `TZ` or the system setting
+pub fn local_timezone() -> Option<Tz> {
+    let name = env::var("TZ")
+        .ok()
+        .map(|tz| tz.trim_start_matches(':').to_string())
+        .filter(|tz| !tz.is_empty())
+        .or_else(|| iana_time_zone::get_timezone().ok())?;
+    name.parse().ok()
+}
@@ -0,0 +1,111 @@
+//! Running external commands whose output is folded into the menu.
+//!
+//! Commands run through `sh -c` with a timeout, and their output is cached on
+//! disk so a slow command doesn't run on every refresh. When a command fails or
+//! times out, the last cached output is shown instead. While `fetchd` runs,
+//! stale output is shown right away and the command is rerun in the background.
+
+use crate::{cache, fetchd};
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+const CACHE_NAMESPACE: &str = "exec";
+
+/// Output of `command`, from the cache if it is younger than `ttl`. Fails
+/// only if the command fails and there is no cached output to fall back on.
+pub fn run_cached(command: &str, ttl: Duration, timeout: Duration) -> Result<String, String> {
+    if let Some(output) = cache::get(CACHE_NAMESPACE, command, ttl) {
+        return Ok(output);
+    }
+    if fetchd::is_running()
+        && let Some(output) = cache::get_stale(CACHE_NAMESPACE, command)
+    {
+        return Ok(output);
+    }
+    rerun(command, timeout)
+}
+
+/// Rerun `command` if its cached output is older than `ttl`
+pub fn refresh(command: &str, ttl: Duration, timeout: Duration) {
+    if cache::get(CACHE_NAMESPACE, command, ttl).is_none() {
+        let _ = rerun(command, timeout);
+    }
+}
+
+/// Time since `command` last produced output
+pub fn age(command: &str) -> Option<Duration> {
+    cache::age(CACHE_NAMESPACE, command)
+}
+
+/// Configured TTL in seconds, or the default
+pub fn ttl(secs: Option<u64>) -> Duration {
+    secs.map(Duration::from_secs).unwrap_or(DEFAULT_TTL)
+}
+
+fn rerun(command: &str, timeout: Duration) -> Result<String, String> {
+    match run(command, timeout) {
+        Ok(output) => {
+            cache::put(CACHE_NAMESPACE, command, &output);
+            Ok(output)
+        }
+        Err(err) => {
+            eprintln!("Warning: Command '{}' failed: {}", command, err);
+            cache::get_stale(CACHE_NAMESPACE, command)
+                .ok_or_else(|| format!("command '{}' failed: {}", command, err))
+        }
+    }
+}
+
+/// First non-empty line of the command's output
+pub fn first_line(output: &str) -> Option<&str> {
+    output.lines().map(str::trim).find(|line| !line.is_empty())
+}
+
+fn run(command: &str, timeout: Duration) -> Result<String, String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    // Read while the command runs: one writing more than the pipe holds
+    // would otherwise block until the timeout kills it
+    let (sender, output) = mpsc::channel();
+    if let Some(mut stdout) = child.stdout.take() {
+        thread::spawn(move || {
+            let mut output = String::new();
+            let _ = sender.send(stdout.read_to_string(&mut output).map(|_| output));
+        });
+    }
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if start.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("timed out after {:?}", timeout));
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    if !status.success() {
+        return Err(format!("exited with {}", status));
+    }
+    // A background process the command left behind can keep stdout open
+    let left = timeout.saturating_sub(start.elapsed());
+    match output.recv_timeout(left) {
+        Ok(output) => output.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("output still open after {:?}", timeout)),
+    }
+}
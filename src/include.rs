@@ -0,0 +1,168 @@
+//! `include:` and `profiles:`, for layering a personal city list on top of
+//! shared ones:
+//!
+//! ```yaml
+//! include: [team_eu.yaml, team_us.yaml]
+//! cities:
+//!   - name: Mum
+//!     timezone: Australia/Perth
+//! profiles:
+//!   travel:
+//!     include: [~/trips/current.yaml]
+//! ```
+//!
+//! Included files are paths relative to the config naming them, or URLs.
+//! Only their cities (and their own includes) are used; they come first, in
+//! order, and a later city with the same name replaces an earlier one in
+//! place. The profile selected with `--profile` is layered last.
+
+use crate::{CityConfig, Config, config_format, expand_home, remote};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Deep enough for any sensible layering, and stops include cycles
+pub const MAX_DEPTH: usize = 8;
+
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Layer the profile `name` on every config loaded for the rest of the run
+/// (`--profile`)
+pub fn select_profile(name: String) {
+    let _ = PROFILE.set(name);
+}
+
+pub fn selected_profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+/// Replace the cities of `config`, loaded from `source`, with its includes,
+/// its own cities and the selected profile merged in that order
+pub fn apply(config: &mut Config, source: &str) {
+    let profile = selected_profile().and_then(|name| {
+        let profile = config.profiles.remove(name);
+        if profile.is_none() {
+            eprintln!("Warning: {}: no profile named {}", source, name);
+        }
+        profile
+    });
+    if config.include.is_empty() && profile.is_none() {
+        return;
+    }
+    let own = std::mem::take(&mut config.cities);
+    let mut cities = Vec::new();
+    for include in &config.include {
+        merge(&mut cities, included(source, include, 1));
+    }
+    merge(&mut cities, own);
+    if let Some(profile) = profile {
+        for include in &profile.include {
+            merge(&mut cities, included(source, include, 1));
+        }
+        merge(&mut cities, profile.cities);
+    }
+    config.cities = cities;
+}
+
+/// Add `layer` to `cities`, replacing the cities of the same name
+fn merge(cities: &mut Vec<CityConfig>, layer: Vec<CityConfig>) {
+    for city in layer {
+        match cities.iter_mut().find(|known| known.name == city.name) {
+            Some(known) => *known = city,
+            None => cities.push(city),
+        }
+    }
+}
+
+/// Cities of the file `include` names, with its own includes; none, with a
+/// warning, if it cannot be read
+fn included(source: &str, include: &str, depth: usize) -> Vec<CityConfig> {
+    if depth > MAX_DEPTH {
+        eprintln!(
+            "Warning: {}: includes nested more than {} deep, is there a cycle?",
+            include, MAX_DEPTH
+        );
+        return Vec::new();
+    }
+    let location = resolve(source, include);
+    let content = if remote::is_url(&location) {
+        remote::fetch_cached(&location)
+    } else {
+        match fs::read_to_string(&location) {
+            Ok(content) => Some(content),
+            Err(err) => {
+                eprintln!("Warning: Cannot include {}: {}", location, err);
+                None
+            }
+        }
+    };
+    let Some(content) = content else {
+        return Vec::new();
+    };
+    let config = match config_format::parse(&location, &content) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Warning: Invalid included config {}: {}", location, err);
+            return Vec::new();
+        }
+    };
+    let mut cities = Vec::new();
+    for nested in &config.include {
+        merge(&mut cities, included(&location, nested, depth + 1));
+    }
+    merge(&mut cities, config.cities);
+    cities
+}
+
+/// Fetch every remote include of `config`, loaded from `source`, and of the
+/// selected profile, however deeply nested, for `fetchd`
+pub fn refresh_remote(config: &Config, source: &str) {
+    let profile = selected_profile().and_then(|name| config.profiles.get(name));
+    let includes = config
+        .include
+        .iter()
+        .chain(profile.into_iter().flat_map(|profile| &profile.include));
+    for include in includes {
+        refresh_included(source, include, 1);
+    }
+}
+
+fn refresh_included(source: &str, include: &str, depth: usize) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let location = resolve(source, include);
+    let content = if remote::is_url(&location) {
+        remote::refresh(&location)
+    } else {
+        fs::read_to_string(&location).ok()
+    };
+    let Some(config) = content.and_then(|content| config_format::parse(&location, &content).ok())
+    else {
+        return;
+    };
+    for nested in &config.include {
+        refresh_included(&location, nested, depth + 1);
+    }
+}
+
+/// Where `include` is, relative to the directory of the config `source`
+pub fn resolve(source: &str, include: &str) -> String {
+    if remote::is_url(include) {
+        return include.to_string();
+    }
+    if let Some(home) = expand_home(include) {
+        return home.display().to_string();
+    }
+    if Path::new(include).is_absolute() {
+        return include.to_string();
+    }
+    if remote::is_url(source) {
+        let base = source.split(['?', '#']).next().unwrap_or(source);
+        let dir = &base[..base.rfind('/').map_or(base.len(), |slash| slash + 1)];
+        return format!("{}{}", dir, include);
+    }
+    let source = expand_home(source).unwrap_or_else(|| PathBuf::from(source));
+    let dir = source.parent().unwrap_or(Path::new(""));
+    dir.join(include).display().to_string()
+}
@@ -0,0 +1,111 @@
+//! `${VAR}` and `~/` in config values, resolved when the config is loaded so
+//! that one config can serve several machines or people, e.g.
+//! `timezone: ${WORK_TZ}`.
+//!
+//! Every string value is expanded except shell commands (`exec`,
+//! `focus_command`), where the shell expands variables itself. As in the
+//! shell, `${VAR:-default}` falls back to `default` when `VAR` is unset or
+//! empty, and `${VAR:?message}` fails with `message`. `$${` is a literal `${`.
+//!
+//! Expansion runs on the parsed config, so only string values can use it: a
+//! number or boolean written as `${VAR}` fails to parse in the first place.
+
+use crate::{Config, expand_home};
+use serde_json::Value;
+use std::env;
+
+/// Keys whose values are left to the shell
+const SHELL_KEYS: &[&str] = &["exec", "focus_command"];
+
+/// Whether `text` has anything to expand, to skip the work for most configs
+pub fn is_needed(text: &str) -> bool {
+    text.contains("${") || text.contains("~/")
+}
+
+/// The config with its string values expanded; an unset variable is an
+/// error naming the value it is in
+pub fn config(config: Config) -> Result<Config, String> {
+    let mut value = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    expand_value(&mut value, &mut String::new())?;
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// Expand every string under `value`, with `path` like `cities[1].timezone`
+fn expand_value(value: &mut Value, path: &mut String) -> Result<(), String> {
+    let length = path.len();
+    match value {
+        Value::String(text) => *text = expand(text).map_err(|e| format!("{}: {}", path, e))?,
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                path.push_str(&format!("[{}]", index));
+                expand_value(item, path)?;
+                path.truncate(length);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                if SHELL_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                expand_value(item, path)?;
+                path.truncate(length);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The value of `VAR`, `VAR:-default` or `VAR:?message`
+fn variable(reference: &str) -> Result<String, String> {
+    let Some((name, fallback)) = reference.split_once(':') else {
+        return env::var(reference)
+            .map_err(|_| format!("environment variable {} is not set", reference));
+    };
+    let value = env::var(name).ok().filter(|value| !value.is_empty());
+    if let Some(default) = fallback.strip_prefix('-') {
+        return Ok(value.unwrap_or_else(|| default.to_string()));
+    }
+    if let Some(message) = fallback.strip_prefix('?') {
+        return value.ok_or_else(|| match message {
+            "" => format!("environment variable {} is not set or empty", name),
+            message => format!("{}: {}", name, message),
+        });
+    }
+    Err(format!(
+        "${{{}}} should be ${{{}:-default}} or ${{{}:?message}}",
+        reference, name, name
+    ))
+}
+
+/// `text` with `${VAR}` replaced and a leading `~/` made absolute
+pub fn expand(text: &str) -> Result<String, String> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(after) = after.strip_prefix("${") {
+            expanded.push_str("${");
+            rest = after;
+        } else if let Some(after) = after.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("unclosed ${{ in '{}'", text))?;
+            expanded.push_str(&variable(&after[..end])?);
+            rest = &after[end + 1..];
+        } else {
+            expanded.push('$');
+            rest = after;
+        }
+    }
+    expanded.push_str(rest);
+    if let Some(home) = expand_home(&expanded) {
+        return Ok(home.to_string_lossy().into_owned());
+    }
+    Ok(expanded)
+}
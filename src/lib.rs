@@ -0,0 +1,898 @@
+//! World clocks for the SwiftBar menu bar, with an emoji clock face for the
+//! local time.
+//!
+//! The `swiftbar_clocks` binary is a thin wrapper around [`cli`]. To embed the
+//! clocks in another menu bar tool, load or parse a [`Config`] and render it:
+//!
+//! ```
+//! let config: swiftbar_clocks::Config =
+//!     serde_yaml::from_str("cities:\n  - name: Tokyo\n    timezone: Asia/Tokyo\n").unwrap();
+//! let menu = swiftbar_clocks::render(&config, chrono::Local::now());
+//! assert!(menu.contains("Tokyo"));
+//! ```
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use backup::ManagedFile;
+
+mod airports;
+mod alerts;
+mod archive;
+mod backup;
+mod bigdigits;
+mod bundle;
+mod cache;
+mod calendar;
+mod callback;
+mod citydb;
+pub mod cli;
+mod color;
+mod comfort;
+mod config_diff;
+mod config_edit;
+mod config_format;
+mod convert;
+mod describe;
+mod digest;
+mod dst;
+mod duration;
+mod faces;
+mod fetchd;
+mod flags;
+mod focus;
+mod fuzzy;
+mod gist;
+mod grid;
+mod headers;
+mod hooks;
+mod include;
+mod interpolate;
+mod line;
+mod lint;
+mod macos_clock;
+mod menu;
+mod metrics;
+mod mqtt;
+mod natural;
+mod notify;
+mod overlap;
+mod pick;
+mod remote;
+mod render;
+mod rules;
+mod selftest;
+mod server;
+mod signals;
+mod snapshot;
+mod speech;
+mod state;
+mod sun;
+mod system_clock;
+mod template;
+mod timefmt;
+mod tui;
+mod tzdata;
+mod validate;
+mod watch;
+
+// Clock face constants (1F55B-1F567)
+const CLOCK_1200: &str = "\u{1F55B}"; // twelve o'clock
+const CLOCK_1230: &str = "\u{1F567}"; // twelve-thirty
+const CLOCK_0100: &str = "\u{1F550}"; // one o'clock
+const CLOCK_0130: &str = "\u{1F55C}"; // one-thirty
+const CLOCK_0200: &str = "\u{1F551}"; // two o'clock
+const CLOCK_0230: &str = "\u{1F55D}"; // two-thirty
+const CLOCK_0300: &str = "\u{1F552}"; // three o'clock
+const CLOCK_0330: &str = "\u{1F55E}"; // three-thirty
+const CLOCK_0400: &str = "\u{1F553}"; // four o'clock
+const CLOCK_0430: &str = "\u{1F55F}"; // four-thirty
+const CLOCK_0500: &str = "\u{1F554}"; // five o'clock
+const CLOCK_0530: &str = "\u{1F560}"; // five-thirty
+const CLOCK_0600: &str = "\u{1F555}"; // six o'clock
+const CLOCK_0630: &str = "\u{1F561}"; // six-thirty
+const CLOCK_0700: &str = "\u{1F556}"; // seven o'clock
+const CLOCK_0730: &str = "\u{1F562}"; // seven-thirty
+const CLOCK_0800: &str = "\u{1F557}"; // eight o'clock
+const CLOCK_0830: &str = "\u{1F563}"; // eight-thirty
+const CLOCK_0900: &str = "\u{1F558}"; // nine o'clock
+const CLOCK_0930: &str = "\u{1F564}"; // nine-thirty
+const CLOCK_1000: &str = "\u{1F559}"; // ten o'clock
+const CLOCK_1030: &str = "\u{1F565}"; // ten-thirty
+const CLOCK_1100: &str = "\u{1F55A}"; // eleven o'clock
+const CLOCK_1130: &str = "\u{1F566}"; // eleven-thirty
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Config {
+    pub cities: Vec<CityConfig>,
+    /// Group cities by their role (home, hq, office, customer)
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    group_by_role: bool,
+    /// Merge in the world clocks configured in the macOS Clock app
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    import_macos_clock: bool,
+    /// Override for the Clock app preferences file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    macos_clock_plist: Option<String>,
+    /// URLs of shared configs whose cities are appended to this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    remote: Vec<String>,
+    /// Config files whose cities come before this one's, see the `include` module
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    /// Named sections layered on top with `--profile`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<String, Profile>,
+    /// Templates of the lines above the city list, see the `headers` module
+    #[serde(
+        default = "default_headers",
+        skip_serializing_if = "is_default_headers"
+    )]
+    headers: Vec<String>,
+    /// Tera template producing the whole SwiftBar output instead of the built-in layout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    template: Option<String>,
+    /// Commands whose output is appended to the menu as extra items
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extra_lines: Vec<ExtraLinesConfig>,
+    /// Number of cities shown inline, the rest go into a "More cities" submenu
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_visible: Option<usize>,
+    /// Spell out in words what icons show, for VoiceOver
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    accessibility: bool,
+    /// Order of the cities within their groups
+    #[serde(default, skip_serializing_if = "SortBy::is_config")]
+    sort_by: SortBy,
+    /// Time format of all cities, see the `timefmt` module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    preset: Option<timefmt::Preset>,
+    /// Clock convention for cities without a preset or format, `24h` or `12h`;
+    /// the macOS system setting if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_format: Option<TimeFormat>,
+    /// Short names usable wherever a timezone is expected, e.g. `HQ: Europe/Berlin`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    aliases: BTreeMap<String, String>,
+    /// Show cities that currently have the same time on one line
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    merge_same_offset: bool,
+    /// Add a "Speak times" item to the menu
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    speak: bool,
+    /// Add a "Convert time from clipboard" item to the menu
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    paste_meeting: bool,
+    /// Offsets from now, e.g. `6h`, to show every city's time at
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    projections: Vec<String>,
+    /// Show a month calendar in a submenu
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    calendar: bool,
+    /// Dates marked in the calendar
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    events: Vec<EventConfig>,
+    /// Dim the menu and hold back sounds and alerts while a macOS Focus is on
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    honor_focus: bool,
+    /// Command telling whether a Focus is on, instead of reading the Focus database
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    focus_command: Option<String>,
+    /// Icons replacing the emoji clock faces, see the `faces` module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    clock_faces: Option<ClockFacesConfig>,
+    /// What the menu bar shows: a clock face, or a sun or moon for the home city
+    #[serde(default, skip_serializing_if = "MenuIcon::is_clock")]
+    menu_icon: MenuIcon,
+    /// Show the country flag in front of each city, see the `flags` module
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    flags: bool,
+    /// Icons of `menu_icon: daypart` replacing the default emoji
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    daypart_icons: Option<DaypartIcons>,
+    /// Hold Option to see how long since and until midnight in each city
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    midnight_detail: bool,
+    /// How rows of cities on another date than mine are marked
+    #[serde(default, skip_serializing_if = "DayOffset::is_days")]
+    day_offset: DayOffset,
+    /// Append each city's UTC offset and its offset from the local timezone
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    show_offset: bool,
+    /// Warn about DST changes in the configured timezones this many days ahead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dst_warning_days: Option<u32>,
+    /// Warn when the system's zoneinfo disagrees with the built-in tz database
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    check_tzdata: bool,
+    /// Minutes someone needs to answer a message: each city then shows the
+    /// last time of mine to send one and still get a reply the same day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reply_buffer: Option<u32>,
+    /// Show today's windows in which every city is within its working hours
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    show_overlap: bool,
+    /// Color each city green in its working hours, orange off hours and red
+    /// when it is asleep
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    color_by_hours: bool,
+    /// Window in the home city's time (local without one) in which
+    /// notifications are held back until it ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quiet_hours: Option<QuietHours>,
+    /// Font, size and color of the city rows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    style: Option<Style>,
+    /// Count pending alerts (DST warnings, held-back notifications) on the
+    /// menu bar title
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    alert_badge: bool,
+    /// Where the config was loaded from, for callbacks from the menu
+    #[serde(skip)]
+    source: Option<String>,
+    /// Names of the cities in the config file itself, in its order; only
+    /// these can be moved from the menu
+    #[serde(skip)]
+    file_cities: Vec<String>,
+    /// Contents of the state file, see the `state` module
+    #[serde(skip)]
+    state: state::State,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortBy {
+    /// As listed in the config
+    #[default]
+    Config,
+    /// Most comfortable local time first
+    Comfort,
+}
+
+impl SortBy {
+    fn is_config(&self) -> bool {
+        *self == SortBy::Config
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum TimeFormat {
+    /// `14:05`
+    #[default]
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    /// `2:05 PM`
+    #[serde(rename = "12h")]
+    TwelveHour,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MenuIcon {
+    /// Clock face of the local time
+    #[default]
+    Clock,
+    /// Sun between sunrise and sunset in the home city, moon otherwise
+    Sun,
+    /// Dawn, day, dusk or night in the home city, see `daypart_icons`
+    Daypart,
+}
+
+impl MenuIcon {
+    fn is_clock(&self) -> bool {
+        *self == MenuIcon::Clock
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DayOffset {
+    /// Days ahead or behind, `+1d`
+    #[default]
+    Days,
+    /// The city's weekday, `Tue`
+    Weekday,
+    /// The city's date, `Tue Oct 15`
+    Date,
+    /// No marker
+    Off,
+}
+
+impl DayOffset {
+    fn is_days(&self) -> bool {
+        *self == DayOffset::Days
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ExtraLinesConfig {
+    /// Shell command, every line of its output becomes a menu item
+    exec: String,
+    /// Seconds to reuse the output before running the command again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ttl: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct ClockFacesConfig {
+    /// Directory of template PNGs named after the face, `1200.png`, `1230.png`,
+    /// `0100.png` and so on up to `1130.png`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dir: Option<String>,
+    /// SF Symbol names of the 24 faces, from 12:00 to 11:30
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    symbols: Vec<String>,
+}
+
+/// Each an emoji or text, `sf:` and an SF Symbol name, or the path of a
+/// template PNG
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct DaypartIcons {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dawn: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    day: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dusk: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    night: Option<String>,
+}
+
+impl DaypartIcons {
+    fn icon(&self, part: sun::Daypart) -> Option<&str> {
+        match part {
+            sun::Daypart::Dawn => self.dawn.as_deref(),
+            sun::Daypart::Day => self.day.as_deref(),
+            sun::Daypart::Dusk => self.dusk.as_deref(),
+            sun::Daypart::Night => self.night.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct EventConfig {
+    date: NaiveDate,
+    title: String,
+}
+
+impl Default for Config {
+    /// Configuration used when there is no config file
+    fn default() -> Self {
+        Config {
+            cities: vec![
+                CityConfig {
+                    name: "New York".to_string(),
+                    timezone: "America/New_York".to_string(),
+                    ..Default::default()
+                },
+                CityConfig {
+                    name: "London".to_string(),
+                    timezone: "Europe/London".to_string(),
+                    ..Default::default()
+                },
+                CityConfig {
+                    name: "Tokyo".to_string(),
+                    timezone: "Asia/Tokyo".to_string(),
+                    ..Default::default()
+                },
+            ],
+            group_by_role: true,
+            import_macos_clock: false,
+            macos_clock_plist: None,
+            remote: Vec::new(),
+            include: Vec::new(),
+            profiles: BTreeMap::new(),
+            headers: default_headers(),
+            template: None,
+            extra_lines: Vec::new(),
+            max_visible: None,
+            accessibility: false,
+            sort_by: SortBy::Config,
+            preset: None,
+            time_format: None,
+            aliases: BTreeMap::new(),
+            merge_same_offset: false,
+            speak: false,
+            paste_meeting: false,
+            projections: Vec::new(),
+            calendar: false,
+            events: Vec::new(),
+            honor_focus: false,
+            focus_command: None,
+            clock_faces: None,
+            menu_icon: MenuIcon::Clock,
+            flags: false,
+            daypart_icons: None,
+            midnight_detail: false,
+            day_offset: DayOffset::Days,
+            show_offset: false,
+            dst_warning_days: None,
+            check_tzdata: false,
+            reply_buffer: None,
+            show_overlap: false,
+            color_by_hours: false,
+            quiet_hours: None,
+            style: None,
+            alert_badge: false,
+            source: None,
+            file_cities: Vec::new(),
+            state: state::State::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Clock convention in effect: the configured one, or the system's
+    fn time_format(&self) -> TimeFormat {
+        self.time_format.unwrap_or_else(system_clock::time_format)
+    }
+
+    /// Parse a tz database name, or one of the configured `aliases`
+    fn timezone(&self, name: &str) -> Result<Tz, String> {
+        if name.is_empty() {
+            return Err("no timezone, and no city of that name in the city database".into());
+        }
+        let resolved = self.aliases.get(name).map_or(name, String::as_str);
+        resolved
+            .parse()
+            .map_err(|_| format!("unknown timezone '{}'", name))
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+fn default_headers() -> Vec<String> {
+    vec![headers::DEFAULT_HEADER.to_string()]
+}
+
+fn is_default_headers(value: &[String]) -> bool {
+    value == [headers::DEFAULT_HEADER]
+}
+
+/// A section of `profiles`, selected with `--profile`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cities: Vec<CityConfig>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CityConfig {
+    pub name: String,
+    /// tz database name, e.g. `Europe/Warsaw`, or one of the config's
+    /// `aliases`; looked up in the city database by name when left out
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub timezone: String,
+    /// Flag shown with `flags: true`: an ISO country code, any emoji or
+    /// text, or empty for none; derived from the timezone when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    flag: Option<String>,
+    /// Emoji or text in front of the row, or `sf:` and an SF Symbol name,
+    /// instead of the role's icon; empty for none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<Role>,
+    /// Last day (inclusive, in the city's timezone) the person is out of office
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ooo_until: Option<NaiveDate>,
+    /// Contact links rendered as a submenu under the city row
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    links: Vec<LinkConfig>,
+    /// Shell command whose first output line is appended to the row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exec: Option<String>,
+    /// Seconds to reuse the `exec` output before running the command again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exec_ttl: Option<u64>,
+    /// Rule deciding whether the city is shown, see the `rules` module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    show_if: Option<String>,
+    /// Time format of the city, overriding the global `preset`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    preset: Option<timefmt::Preset>,
+    /// strftime format of the city's time, e.g. `"%H:%M:%S"` or `"%H:%M %a"`,
+    /// overriding any preset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+    /// Language the time is spoken in by `say`, e.g. `de` or `de_DE`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    /// macOS voice used by `say`, instead of one picked for `language`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    voice: Option<String>,
+    /// Show when you last talked to someone here, with a menu item to mark it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    track_contact: bool,
+    /// First day (in the city's timezone) the city is shown, for project-based entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    active_from: Option<NaiveDate>,
+    /// Last day (inclusive, in the city's timezone) the city is shown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    active_until: Option<NaiveDate>,
+    /// Coordinates in degrees (north and east positive), for sunrise and sunset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    longitude: Option<f64>,
+    /// Whether to append the UTC offset to the row, overriding the global `show_offset`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    show_offset: Option<bool>,
+    /// Start of the working day in the city, e.g. `"08:30"`; 09:00 if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    work_start: Option<NaiveTime>,
+    /// End of the working day, the next morning if it is before `work_start`; 17:00 if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    work_end: Option<NaiveTime>,
+    /// Second location of the same person or team, shown on the same line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    second: Option<SecondZoneConfig>,
+    /// Font, size or color of the city's row, overriding the global `style`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    style: Option<Style>,
+    /// Notes shown on the row while they apply, e.g. "avoid calls after 18:00"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    etiquette: Vec<Etiquette>,
+}
+
+impl CityConfig {
+    /// Whether the city is out of office on the given (city-local) date
+    fn is_ooo(&self, today: NaiveDate) -> bool {
+        self.ooo_until.is_some_and(|until| today <= until)
+    }
+
+    /// Whether the given (city-local) date is within `active_from`..=`active_until`
+    fn is_active(&self, today: NaiveDate) -> bool {
+        self.active_from.is_none_or(|from| from <= today)
+            && self.active_until.is_none_or(|until| today <= until)
+    }
+
+    /// Configured coordinates, or those of the city of the same name in `tz`
+    /// from the city database
+    fn coordinates(&self, tz: Tz) -> Option<(f64, f64)> {
+        if let (Some(latitude), Some(longitude)) = (self.latitude, self.longitude) {
+            return Some((latitude, longitude));
+        }
+        citydb::lookup(&self.name)
+            .into_iter()
+            .find(|city| city.timezone == tz.name())
+            .map(|city| (city.latitude, city.longitude))
+    }
+
+    /// Configured working hours, 09:00-17:00 by default
+    fn working_hours(&self) -> (NaiveTime, NaiveTime) {
+        let hour = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap_or_default();
+        (
+            self.work_start.unwrap_or_else(|| hour(9)),
+            self.work_end.unwrap_or_else(|| hour(17)),
+        )
+    }
+
+    /// The working day starting on a (city-local) date, from start to end in
+    /// local time; a night shift ends the next day. `None` on weekends.
+    fn working_day(&self, date: NaiveDate) -> Option<(NaiveDateTime, NaiveDateTime)> {
+        if date.weekday().number_from_monday() > 5 {
+            return None;
+        }
+        let (start, end) = self.working_hours();
+        let end_date = if end <= start { date.succ_opt()? } else { date };
+        Some((date.and_time(start), end_date.and_time(end)))
+    }
+
+    /// Whether a (city-local) time is within a working day
+    fn is_working_at(&self, time: NaiveDateTime) -> bool {
+        let date = time.date();
+        [date.pred_opt(), Some(date)]
+            .into_iter()
+            .flatten()
+            .filter_map(|date| self.working_day(date))
+            .any(|(start, end)| start <= time && time < end)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Role {
+    Home,
+    Hq,
+    Office,
+    Customer,
+}
+
+impl Role {
+    fn icon(self) -> &'static str {
+        match self {
+            Role::Home => "\u{1F3E0}",       // house
+            Role::Hq => "\u{1F3DB}\u{FE0F}", // classical building
+            Role::Office => "\u{1F3E2}",     // office building
+            Role::Customer => "\u{1F91D}",   // handshake
+        }
+    }
+
+    /// Text equivalent of the icon
+    fn label(self) -> &'static str {
+        match self {
+            Role::Home => "home",
+            Role::Hq => "HQ",
+            Role::Office => "office",
+            Role::Customer => "customer",
+        }
+    }
+
+    /// Position of the role's group in the dropdown; cities without a role go last
+    fn group_rank(role: Option<Role>) -> u8 {
+        match role {
+            Some(Role::Home) => 0,
+            Some(Role::Hq) => 1,
+            Some(Role::Office) => 2,
+            Some(Role::Customer) => 3,
+            None => 4,
+        }
+    }
+}
+
+/// SwiftBar text parameters of a menu line, e.g.
+/// `{font: Menlo, size: 12, color: "#88ccff"}`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Style {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    font: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+}
+
+/// `quiet_hours: {start: "22:00", end: "07:00"}`, past midnight if `end` is
+/// before `start`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+/// The other half of a dual-zone entry, e.g. "Dubai office / Shenzhen factory"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SecondZoneConfig {
+    name: String,
+    timezone: String,
+}
+
+/// A note for a time range in a city, e.g. `{note: "Friday is off", days: [fri]}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Etiquette {
+    note: String,
+    /// Start of the range, the start of the day if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    from: Option<NaiveTime>,
+    /// End of the range (exclusive), the next morning if it is before `from`;
+    /// the end of the day if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    until: Option<NaiveTime>,
+    /// Days the note applies on, every day if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    days: Vec<Weekday>,
+}
+
+impl Etiquette {
+    /// Whether the note applies at the given (city-local) time. A range past
+    /// midnight counts by the day it starts on, so Friday 22:00–06:00 includes
+    /// early Saturday.
+    fn applies(&self, at: NaiveDateTime) -> bool {
+        let time = at.time();
+        let (day, in_range) = match (self.from, self.until) {
+            (Some(from), Some(until)) if until < from => {
+                if time >= from {
+                    (at.date(), true)
+                } else {
+                    (at.date() - chrono::Days::new(1), time < until)
+                }
+            }
+            (from, until) => (
+                at.date(),
+                from.is_none_or(|from| from <= time) && until.is_none_or(|until| time < until),
+            ),
+        };
+        in_range && (self.days.is_empty() || self.days.contains(&day.weekday()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct LinkConfig {
+    title: String,
+    href: String,
+}
+
+fn get_accurate_clock_icon(hour: u32, minute: u32) -> &'static str {
+    // Round to nearest 30 minutes for clock face selection
+    let rounded_minute = if minute < 15 {
+        0
+    } else if minute < 45 {
+        30
+    } else {
+        0
+    };
+
+    let display_hour = if minute >= 45 {
+        (hour + 1) % 12
+    } else {
+        hour % 12
+    };
+
+    match (display_hour, rounded_minute) {
+        (12, 0) | (0, 0) => CLOCK_1200,
+        (12, 30) | (0, 30) => CLOCK_1230,
+        (1, 0) => CLOCK_0100,
+        (1, 30) => CLOCK_0130,
+        (2, 0) => CLOCK_0200,
+        (2, 30) => CLOCK_0230,
+        (3, 0) => CLOCK_0300,
+        (3, 30) => CLOCK_0330,
+        (4, 0) => CLOCK_0400,
+        (4, 30) => CLOCK_0430,
+        (5, 0) => CLOCK_0500,
+        (5, 30) => CLOCK_0530,
+        (6, 0) => CLOCK_0600,
+        (6, 30) => CLOCK_0630,
+        (7, 0) => CLOCK_0700,
+        (7, 30) => CLOCK_0730,
+        (8, 0) => CLOCK_0800,
+        (8, 30) => CLOCK_0830,
+        (9, 0) => CLOCK_0900,
+        (9, 30) => CLOCK_0930,
+        (10, 0) => CLOCK_1000,
+        (10, 30) => CLOCK_1030,
+        (11, 0) => CLOCK_1100,
+        (11, 30) => CLOCK_1130,
+        _ => CLOCK_1200,
+    }
+}
+
+/// Emoji clock face closest to a time of day, e.g. 🕜 for 13:30
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockIcon(&'static str);
+
+impl ClockIcon {
+    /// Face for `hour:minute`, rounded to the nearest half hour
+    pub fn at(hour: u32, minute: u32) -> Self {
+        ClockIcon(get_accurate_clock_icon(hour, minute))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Display for ClockIcon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// The SwiftBar menu for `config` at `now`, as the plugin prints it
+pub fn render(config: &Config, now: DateTime<Local>) -> String {
+    render::render_swiftbar(config, now)
+}
+
+/// Expand a leading `~/` to the home directory, `None` if there is nothing to expand
+fn expand_home(path: &str) -> Option<PathBuf> {
+    let rest = path.strip_prefix("~/")?;
+    let mut expanded_path = PathBuf::from(env::var_os("HOME")?);
+    expanded_path.push(rest);
+    Some(expanded_path)
+}
+
+/// Local path of the config file, `~/` expanded
+fn resolve_config_path(path: &str) -> Result<PathBuf, String> {
+    if remote::is_url(path) {
+        return Err(format!(
+            "{} is a remote config, edit it at the source",
+            path
+        ));
+    }
+    Ok(expand_home(path).unwrap_or_else(|| PathBuf::from(path)))
+}
+
+/// Files covered by backups and bundles, with the name they are stored under
+fn managed_files(config_path: &Path) -> Vec<ManagedFile> {
+    vec![
+        ManagedFile {
+            name: config_format::Format::of_path(config_path).entry_name(),
+            path: config_path.to_path_buf(),
+        },
+        ManagedFile {
+            name: "state.yaml",
+            path: config_path.with_extension("state.yaml"),
+        },
+    ]
+}
+
+/// Read and parse a local config file as written, without any fallback or
+/// `${VAR}` expansion, so that it can be edited and written back
+fn read_config_file(path: &Path) -> Result<Config, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    config_format::Format::detect(&path.to_string_lossy(), &content)
+        .parse_as_written(&content)
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Write the config back in its format, taking a backup of the previous
+/// version first
+fn write_config_file(path: &Path, config: &Config) -> Result<(), String> {
+    let content = config_format::Format::of_path(path).serialize(config)?;
+    backup::backup(path, &managed_files(path)).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// The config at `path` (a file or an http(s) URL) with its state and
+/// remote cities merged in, the defaults if it cannot be read
+pub fn load_config(path: String) -> Config {
+    let mut config = load_config_file(path.clone());
+    config.file_cities = config.cities.iter().map(|city| city.name.clone()).collect();
+    include::apply(&mut config, &path);
+    config.state = state::load(&path);
+    config.source = Some(path);
+    if config.import_macos_clock {
+        macos_clock::merge_world_clocks(&mut config);
+    }
+
+    for url in config.remote.clone() {
+        match remote::fetch_cached(&url).map(|content| config_format::parse(&url, &content)) {
+            Some(Ok(included)) => config.cities.extend(included.cities),
+            Some(Err(err)) => eprintln!("Warning: Invalid remote config {}: {}", url, err),
+            None => {}
+        }
+    }
+    resolve_timezones(&mut config.cities);
+
+    config
+}
+
+/// Fill in the timezone of cities without one from the city database, the
+/// most populous city of the name
+fn resolve_timezones(cities: &mut [CityConfig]) {
+    for city in cities {
+        if city.timezone.is_empty()
+            && let Some(found) = citydb::resolve(&city.name).first()
+        {
+            city.timezone = found.timezone.to_string();
+        }
+    }
+}
+
+fn load_config_file(path: String) -> Config {
+    if remote::is_url(&path)
+        && let Some(content) = remote::fetch_cached(&path)
+        && let Ok(config) = config_format::parse(&path, &content)
+    {
+        return config;
+    }
+
+    // Try provided path first, then with ~/ expanded
+    let content = fs::read_to_string(&path)
+        .ok()
+        .or_else(|| fs::read_to_string(expand_home(&path)?).ok());
+    if let Some(content) = content {
+        match config_format::parse(&path, &content) {
+            Ok(config) => return config,
+            // Unset variables most of all, which are easy to miss
+            Err(err) => eprintln!("Warning: {}: {}, using the defaults", path, err),
+        }
+    }
+
+    Config::default()
+}
@@ -0,0 +1,49 @@
+//! Parameters of a SwiftBar menu line, the ` | key=value …` after its text.
+
+use crate::Style;
+
+#[derive(Debug, Default)]
+pub struct Params(Vec<(&'static str, String)>);
+
+impl Params {
+    /// Set `key`, replacing an earlier value
+    pub fn set(&mut self, key: &'static str, value: impl Into<String>) -> &mut Self {
+        let value = value.into();
+        match self.0.iter_mut().find(|(known, _)| *known == key) {
+            Some(entry) => entry.1 = value,
+            None => self.0.push((key, value)),
+        }
+        self
+    }
+
+    /// Font, size and color of a `style` section, where set
+    pub fn style(&mut self, style: &Style) -> &mut Self {
+        if let Some(font) = &style.font {
+            self.set("font", font.as_str());
+        }
+        if let Some(size) = style.size {
+            self.set("size", size.to_string());
+        }
+        if let Some(color) = &style.color {
+            self.set("color", color.as_str());
+        }
+        self
+    }
+
+    /// ` | key=value …`, or nothing without parameters. Values with spaces,
+    /// like font names, are quoted.
+    pub fn write(&self, output: &mut String) {
+        for (index, (key, value)) in self.0.iter().enumerate() {
+            output.push_str(if index == 0 { " | " } else { " " });
+            output.push_str(key);
+            output.push('=');
+            if value.contains(' ') {
+                output.push('"');
+                output.push_str(value);
+                output.push('"');
+            } else {
+                output.push_str(value);
+            }
+        }
+    }
+}
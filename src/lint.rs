@@ -0,0 +1,69 @@
+//! Mistakes in the config that rendering would only skip with a warning, and
+//! entries that have outlived their use.
+
+use crate::{CityConfig, Config, headers, rules, timefmt};
+use chrono::{DateTime, Utc};
+
+/// One line per problem, e.g. `Berlin: unknown timezone 'Europe/Berln'`.
+/// Cities whose `active_until` has passed are only reported with `expired`.
+pub fn check(config: &Config, now: DateTime<Utc>, expired: bool) -> Vec<String> {
+    let mut problems = Vec::new();
+    for header in &config.headers {
+        if let Err(err) = headers::check(header) {
+            problems.push(format!("header '{}': {}", header, err));
+        }
+    }
+    for city in &config.cities {
+        let tz = match config.timezone(&city.timezone) {
+            Ok(tz) => tz,
+            Err(err) => {
+                problems.push(format!("{}: {}", city.name, err));
+                continue;
+            }
+        };
+        if let Some(second) = &city.second
+            && let Err(err) = config.timezone(&second.timezone)
+        {
+            problems.push(format!("{} / {}: {}", city.name, second.name, err));
+        }
+        for (_, problem) in field_problems(city) {
+            problems.push(problem);
+        }
+        if let (Some(from), Some(until)) = (city.active_from, city.active_until)
+            && from > until
+        {
+            problems.push(format!(
+                "{}: active_from {} is after active_until {}, the city is never shown",
+                city.name, from, until
+            ));
+        }
+        let today = now.with_timezone(&tz).date_naive();
+        if expired && let Some(until) = city.active_until.filter(|until| *until < today) {
+            problems.push(format!(
+                "{}: active_until {} has passed, the entry can be removed",
+                city.name, until
+            ));
+        }
+    }
+    problems
+}
+
+/// Problems with the city's `show_if` rule and `format`, each with the key it
+/// is under; `config validate` reports the same
+pub fn field_problems(city: &CityConfig) -> Vec<(&'static str, String)> {
+    let mut problems = Vec::new();
+    if let Some(rule) = &city.show_if
+        && let Err(err) = rules::parse(rule)
+    {
+        problems.push((
+            "show_if",
+            format!("{}: invalid show_if: {}", city.name, err),
+        ));
+    }
+    if let Some(format) = &city.format
+        && let Err(err) = timefmt::check_format(format)
+    {
+        problems.push(("format", format!("{}: {}", city.name, err)));
+    }
+    problems
+}
@@ -0,0 +1,93 @@
+//! Importer for the world clocks configured in the macOS Clock app.
+//!
+//! The Clock app keeps its cities in a binary plist. Rather than parsing the
+//! format ourselves we let `plutil` convert it to JSON and pick out every
+//! object that carries a timezone identifier, which keeps the importer working
+//! across the small layout differences between macOS releases.
+
+use crate::{CityConfig, Config, expand_home};
+use chrono_tz::Tz;
+use serde_json::Value;
+use std::process::Command;
+
+const DEFAULT_CLOCK_PLIST: &str =
+    "~/Library/Containers/com.apple.clock/Data/Library/Preferences/com.apple.clock.plist";
+
+const TIMEZONE_KEYS: &[&str] = &["timeZone", "timezone", "TimeZone", "timeZoneName"];
+const NAME_KEYS: &[&str] = &["name", "cityName", "displayName", "title"];
+
+/// Append Clock app cities whose timezone is not configured yet
+pub fn merge_world_clocks(config: &mut Config) {
+    let path = config
+        .macos_clock_plist
+        .as_deref()
+        .unwrap_or(DEFAULT_CLOCK_PLIST);
+
+    let cities = match read_world_clocks(path) {
+        Ok(cities) => cities,
+        Err(err) => {
+            eprintln!("Warning: Could not import macOS Clock cities: {}", err);
+            return;
+        }
+    };
+
+    for city in cities {
+        if !config.cities.iter().any(|c| c.timezone == city.timezone) {
+            config.cities.push(city);
+        }
+    }
+}
+
+fn read_world_clocks(path: &str) -> Result<Vec<CityConfig>, String> {
+    let path = expand_home(path).unwrap_or_else(|| path.into());
+    let output = Command::new("plutil")
+        .args(["-convert", "json", "-o", "-"])
+        .arg(&path)
+        .output()
+        .map_err(|err| format!("failed to run plutil: {}", err))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "plutil failed for {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let root: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|err| format!("unexpected plutil output: {}", err))?;
+
+    let mut cities = Vec::new();
+    collect_cities(&root, &mut cities);
+    Ok(cities)
+}
+
+fn collect_cities(value: &Value, cities: &mut Vec<CityConfig>) {
+    match value {
+        Value::Object(map) => {
+            let timezone = TIMEZONE_KEYS
+                .iter()
+                .find_map(|key| map.get(*key).and_then(Value::as_str))
+                .filter(|tz| tz.parse::<Tz>().is_ok());
+
+            if let Some(timezone) = timezone {
+                let name = NAME_KEYS
+                    .iter()
+                    .find_map(|key| map.get(*key).and_then(Value::as_str))
+                    .unwrap_or_else(|| timezone.rsplit('/').next().unwrap_or(timezone))
+                    .replace('_', " ");
+
+                cities.push(CityConfig {
+                    name,
+                    timezone: timezone.to_string(),
+                    ..Default::default()
+                });
+                return;
+            }
+
+            map.values().for_each(|v| collect_cities(v, cities));
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_cities(v, cities)),
+        _ => {}
+    }
+}
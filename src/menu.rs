@@ -0,0 +1,158 @@
+//! The menu as data: lines of text with their submenu depth and parameters,
+//! read back from the SwiftBar output.
+//!
+//! Argos (GNOME) and Kargos (KDE) read almost the same format, so their
+//! backend translates the parameters of the one rendered menu rather than
+//! rendering it a second time.
+
+pub enum Line {
+    /// `---`, `-----` in a submenu
+    Separator {
+        depth: usize,
+    },
+    Item(Item),
+}
+
+pub struct Item {
+    pub depth: usize,
+    pub text: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl Item {
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(known, _)| known == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Lines of a SwiftBar menu
+pub fn parse(menu: &str) -> Vec<Line> {
+    menu.lines().map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Line {
+    if line.len() >= 3 && line.len() % 2 == 1 && line.bytes().all(|b| b == b'-') {
+        return Line::Separator {
+            depth: (line.len() - 3) / 2,
+        };
+    }
+    let mut text = line;
+    let mut depth = 0;
+    while let Some(rest) = text.strip_prefix("--") {
+        text = rest;
+        depth += 1;
+    }
+    let (text, params) = match text.split_once(" | ") {
+        Some((text, params)) => (text, parse_params(params)),
+        None => (text, Vec::new()),
+    };
+    Line::Item(Item {
+        depth,
+        text: text.to_string(),
+        params,
+    })
+}
+
+/// `key=value key="quoted \"value\""`
+fn parse_params(params: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut chars = params.chars().peekable();
+    loop {
+        while chars.next_if_eq(&' ').is_some() {}
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if key.is_empty() {
+            return parsed;
+        }
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ' ') {
+                value.push(c);
+            }
+        }
+        parsed.push((key, value));
+    }
+}
+
+/// The menu in Argos' format. Commands become a single shell line, since
+/// Argos has no `paramN`; SF Symbols and checkmarks have no equivalent, so
+/// symbols are dropped and checked items get a `✓` in front.
+pub fn argos(lines: &[Line]) -> String {
+    let mut output = String::new();
+    for line in lines {
+        match line {
+            Line::Separator { depth } => {
+                output.push_str(&"--".repeat(*depth));
+                output.push_str("---\n");
+            }
+            Line::Item(item) => write_argos_item(&mut output, item),
+        }
+    }
+    output
+}
+
+fn write_argos_item(output: &mut String, item: &Item) {
+    output.push_str(&"--".repeat(item.depth));
+    if item.param("checked") == Some("true") {
+        output.push_str("✓ ");
+    }
+    output.push_str(&item.text);
+
+    let mut params: Vec<(&str, String)> = Vec::new();
+    if let Some(command) = item.param("bash") {
+        let mut line = shell_quote(command);
+        for index in 1.. {
+            let Some(arg) = item.param(&format!("param{}", index)) else {
+                break;
+            };
+            line.push(' ');
+            line.push_str(&shell_quote(arg));
+        }
+        params.push(("bash", line));
+    }
+    for (key, value) in &item.params {
+        let key = match key.as_str() {
+            "templateImage" => "image",
+            "bash" | "checked" | "sfimage" => continue,
+            key if key.starts_with("param") => continue,
+            key => key,
+        };
+        params.push((key, value.clone()));
+    }
+    for (index, (key, value)) in params.iter().enumerate() {
+        output.push_str(if index == 0 { " | " } else { " " });
+        output.push_str(key);
+        output.push('=');
+        if value.contains([' ', '\'', '"']) {
+            output.push('"');
+            output.push_str(&value.replace('"', "\\\""));
+            output.push('"');
+        } else {
+            output.push_str(value);
+        }
+    }
+    output.push('\n');
+}
+
+/// `value`, single-quoted for the shell if it needs to be
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@%+,".contains(c))
+    {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
@@ -0,0 +1,137 @@
+//! `--metrics`: how expensive a refresh is, for keeping an eye on large or
+//! remote configs. Printed as a Prometheus textfile (for node_exporter's
+//! textfile collector) or as JSON.
+
+use crate::{Config, cache, remote, resolve_config_path};
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+use std::fmt::Write;
+use std::fs;
+use std::time::Duration;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    Prometheus,
+    Json,
+}
+
+pub struct Metrics {
+    pub load: Duration,
+    pub render: Duration,
+    /// Size of the config file, or of the cached copy of a remote one
+    pub config_bytes: Option<u64>,
+    pub cities: usize,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    /// Time since each remote config was last fetched, `None` if never
+    pub fetch_ages: Vec<(String, Option<Duration>)>,
+}
+
+impl Metrics {
+    /// Metrics of a run that took `load` to load `config` (from `config_arg`)
+    /// and `render` to render it
+    pub fn collect(config_arg: &str, config: &Config, load: Duration, render: Duration) -> Self {
+        // Before the lookups below, which would count
+        let (cache_hits, cache_misses) = cache::stats();
+        let config_bytes = if remote::is_url(config_arg) {
+            cache::get_stale("remote", config_arg).map(|body| body.len() as u64)
+        } else {
+            resolve_config_path(config_arg)
+                .ok()
+                .and_then(|path| fs::metadata(path).ok())
+                .map(|metadata| metadata.len())
+        };
+        let urls = remote::is_url(config_arg)
+            .then_some(config_arg)
+            .into_iter()
+            .chain(config.remote.iter().map(String::as_str));
+        Metrics {
+            load,
+            render,
+            config_bytes,
+            cities: config.cities.len(),
+            cache_hits,
+            cache_misses,
+            fetch_ages: urls
+                .map(|url| (url.to_string(), remote::age(url)))
+                .collect(),
+        }
+    }
+
+    pub fn format(&self, format: Format) -> String {
+        match format {
+            Format::Prometheus => self.prometheus(),
+            Format::Json => format!("{}\n", self.json()),
+        }
+    }
+
+    fn prometheus(&self) -> String {
+        let mut output = String::new();
+        let mut gauge = |name: &str, help: &str, samples: &[(String, f64)]| {
+            let _ = writeln!(output, "# HELP swiftbar_clocks_{} {}", name, help);
+            let _ = writeln!(output, "# TYPE swiftbar_clocks_{} gauge", name);
+            for (labels, value) in samples {
+                let _ = writeln!(output, "swiftbar_clocks_{}{} {}", name, labels, value);
+            }
+        };
+        let plain = |value: f64| [(String::new(), value)];
+        gauge(
+            "config_load_seconds",
+            "Time to load the config",
+            &plain(self.load.as_secs_f64()),
+        );
+        gauge(
+            "render_seconds",
+            "Time to render the menu",
+            &plain(self.render.as_secs_f64()),
+        );
+        if let Some(bytes) = self.config_bytes {
+            gauge("config_bytes", "Size of the config", &plain(bytes as f64));
+        }
+        gauge("cities", "Configured cities", &plain(self.cities as f64));
+        gauge(
+            "cache_hits",
+            "Cache lookups answered from disk",
+            &plain(self.cache_hits as f64),
+        );
+        gauge(
+            "cache_misses",
+            "Cache lookups that found nothing usable",
+            &plain(self.cache_misses as f64),
+        );
+        let ages: Vec<(String, f64)> = self
+            .fetch_ages
+            .iter()
+            .filter_map(|(url, age)| {
+                // Label values escape backslashes and quotes
+                let url = url.replace('\\', "\\\\").replace('"', "\\\"");
+                Some((format!("{{url=\"{}\"}}", url), age.as_ref()?.as_secs_f64()))
+            })
+            .collect();
+        if !ages.is_empty() {
+            gauge(
+                "fetch_age_seconds",
+                "Time since a remote config was last fetched",
+                &ages,
+            );
+        }
+        output
+    }
+
+    fn json(&self) -> Value {
+        let ages: Map<String, Value> = self
+            .fetch_ages
+            .iter()
+            .map(|(url, age)| (url.clone(), json!(age.map(|age| age.as_secs()))))
+            .collect();
+        json!({
+            "config_load_seconds": self.load.as_secs_f64(),
+            "render_seconds": self.render.as_secs_f64(),
+            "config_bytes": self.config_bytes,
+            "cities": self.cities,
+            "cache_hits": self.cache_hits,
+            "cache_misses": self.cache_misses,
+            "fetch_age_seconds": ages,
+        })
+    }
+}
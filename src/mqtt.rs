@@ -0,0 +1,217 @@
+//! Periodic publishing of the clocks to an MQTT broker.
+//!
+//! Only the handful of MQTT 3.1.1 packets needed to publish at QoS 0 are
+//! implemented, which keeps this mode free of heavy client dependencies.
+
+use crate::{load_config, render};
+use chrono::Local;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_PORT: u16 = 1883;
+const KEEP_ALIVE_SECS: u16 = 120;
+/// How often to ping between publishes, well inside the keep-alive
+const PING_EVERY: Duration = Duration::from_secs(KEEP_ALIVE_SECS as u64 / 2);
+/// How long to wait for CONNACK and PINGRESP
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct BrokerUrl {
+    host: String,
+    port: u16,
+    credentials: Option<(String, String)>,
+}
+
+fn parse_url(url: &str) -> Result<BrokerUrl, String> {
+    let rest = url
+        .strip_prefix("tcp://")
+        .or_else(|| url.strip_prefix("mqtt://"))
+        .ok_or_else(|| format!("unsupported broker URL '{}', expected tcp://host:port", url))?;
+
+    let (credentials, address) = match rest.rsplit_once('@') {
+        Some((auth, address)) => {
+            let (user, password) = auth.split_once(':').unwrap_or((auth, ""));
+            (Some((user.to_string(), password.to_string())), address)
+        }
+        None => (None, rest),
+    };
+    let address = address.trim_end_matches('/');
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| format!("invalid port in '{}'", url))?,
+        ),
+        None => (address, DEFAULT_PORT),
+    };
+
+    Ok(BrokerUrl {
+        host: host.to_string(),
+        port,
+        credentials,
+    })
+}
+
+fn encode_remaining_length(mut len: usize, packet: &mut Vec<u8>) {
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+}
+
+fn encode_string(value: &str, body: &mut Vec<u8>) {
+    body.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    body.extend_from_slice(value.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    encode_remaining_length(body.len(), &mut packet);
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn connect(url: &BrokerUrl) -> Result<TcpStream, String> {
+    let mut stream = TcpStream::connect((url.host.as_str(), url.port))
+        .map_err(|e| format!("{}:{}: {}", url.host, url.port, e))?;
+    stream
+        .set_read_timeout(Some(REPLY_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+
+    let mut body = Vec::new();
+    encode_string("MQTT", &mut body);
+    body.push(4); // protocol level 3.1.1
+    let mut flags = 0x02; // clean session
+    if url.credentials.is_some() {
+        flags |= 0xC0;
+    }
+    body.push(flags);
+    body.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+    encode_string(
+        &format!("swiftbar_clocks-{}", std::process::id()),
+        &mut body,
+    );
+    if let Some((user, password)) = &url.credentials {
+        encode_string(user, &mut body);
+        encode_string(password, &mut body);
+    }
+
+    stream
+        .write_all(&packet(0x10, &body))
+        .map_err(|e| e.to_string())?;
+
+    let mut connack = [0u8; 4];
+    stream
+        .read_exact(&mut connack)
+        .map_err(|e| format!("no CONNACK from broker: {}", e))?;
+    if connack[0] != 0x20 || connack[3] != 0 {
+        return Err(format!("broker refused connection (code {})", connack[3]));
+    }
+    Ok(stream)
+}
+
+fn publish(stream: &mut TcpStream, topic: &str, payload: &str) -> std::io::Result<()> {
+    let mut body = Vec::new();
+    encode_string(topic, &mut body);
+    body.extend_from_slice(payload.as_bytes());
+    // QoS 0 with the retain flag, so dashboards get a value right after subscribing
+    stream.write_all(&packet(0x31, &body))
+}
+
+/// PINGREQ, which keeps the connection alive, and its PINGRESP, which tells
+/// a connection the broker closed from a live one
+fn ping(stream: &mut TcpStream) -> std::io::Result<()> {
+    stream.write_all(&packet(0xC0, &[]))?;
+    let mut pingresp = [0u8; 2];
+    stream.read_exact(&mut pingresp)?;
+    if pingresp != [0xD0, 0] {
+        return Err(std::io::Error::other("unexpected reply to PINGREQ"));
+    }
+    Ok(())
+}
+
+/// A connection that answers a ping: `stream` or, if it does not, a new one;
+/// `None`, with a warning, while the broker cannot be reached
+fn live(stream: Option<TcpStream>, url: &BrokerUrl) -> Option<TcpStream> {
+    if let Some(mut stream) = stream {
+        match ping(&mut stream) {
+            Ok(()) => return Some(stream),
+            Err(err) => eprintln!(
+                "Warning: connection to the broker lost ({}), reconnecting",
+                err
+            ),
+        }
+    }
+    match connect(url) {
+        Ok(stream) => Some(stream),
+        Err(err) => {
+            eprintln!("Warning: cannot reconnect: {}", err);
+            None
+        }
+    }
+}
+
+fn publish_clocks(stream: &mut TcpStream, config_path: &str, prefix: &str) -> std::io::Result<()> {
+    let config = load_config(config_path.to_string());
+    let now = Local::now();
+
+    for clock in render::city_clocks(&config, now) {
+        let topic = format!("{}/{}", prefix, render::slug(&clock.city.name));
+        let day_night = if clock.is_daytime() { "day" } else { "night" };
+        let messages = [
+            ("time", clock.formatted_time()),
+            ("offset", clock.time.format("%:z").to_string()),
+            ("daynight", day_night.to_string()),
+        ];
+
+        for (leaf, payload) in messages {
+            publish(stream, &format!("{}/{}", topic, leaf), &payload)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn run(config_path: &str, url: &str, prefix: &str, interval: Duration) -> Result<(), String> {
+    let url = parse_url(url)?;
+    let mut stream = Some(connect(&url)?);
+    eprintln!("Publishing clocks to {}:{}", url.host, url.port);
+
+    loop {
+        // QoS 0 gets no acknowledgement, so check the connection first: writes
+        // to one the broker has closed can succeed locally and be lost
+        stream = live(stream.take(), &url);
+        if let Some(connected) = stream.as_mut()
+            && let Err(err) = publish_clocks(connected, config_path, prefix)
+        {
+            eprintln!("Warning: publish failed ({}), reconnecting", err);
+            stream = live(None, &url);
+            if let Some(connected) = stream.as_mut()
+                && let Err(err) = publish_clocks(connected, config_path, prefix)
+            {
+                eprintln!("Warning: publish failed again ({})", err);
+                stream = None;
+            }
+        }
+
+        // Ping through long intervals so the broker does not drop us
+        let next = Instant::now() + interval;
+        loop {
+            let left = next.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            thread::sleep(left.min(PING_EVERY));
+            if Instant::now() < next && stream.is_some() {
+                stream = live(stream.take(), &url);
+            }
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! Times as people write them in chat, for `paste-meeting`: `3pm PT`,
+//! `tomorrow 10:30 Berlin`, `Thu 9am CET`, `noon UTC+2`, or a timestamp such
+//! as `2024-03-10T15:04:05+01:00` or `1710079445`.
+//!
+//! The zone can be an abbreviation, a tz database name or alias, a configured
+//! city or a city in the city database; without one the time is local.
+//! Abbreviations stand for the region's wall clock, so `PST` in July is read
+//! as Pacific daylight time, which is what people mean by it. A time without
+//! a day that has passed already is taken to be tomorrow's.
+
+use crate::{Config, citydb};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+
+/// Common abbreviations and the zone whose wall clock they name
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("PT", "America/Los_Angeles"),
+    ("PST", "America/Los_Angeles"),
+    ("PDT", "America/Los_Angeles"),
+    ("MT", "America/Denver"),
+    ("MST", "America/Denver"),
+    ("MDT", "America/Denver"),
+    ("CT", "America/Chicago"),
+    ("CST", "America/Chicago"),
+    ("CDT", "America/Chicago"),
+    ("ET", "America/New_York"),
+    ("EST", "America/New_York"),
+    ("EDT", "America/New_York"),
+    ("UTC", "UTC"),
+    ("GMT", "UTC"),
+    ("Z", "UTC"),
+    ("BST", "Europe/London"),
+    ("UK", "Europe/London"),
+    ("WET", "Europe/Lisbon"),
+    ("WEST", "Europe/Lisbon"),
+    ("CET", "Europe/Paris"),
+    ("CEST", "Europe/Paris"),
+    ("EET", "Europe/Helsinki"),
+    ("EEST", "Europe/Helsinki"),
+    ("IST", "Asia/Kolkata"),
+    ("SGT", "Asia/Singapore"),
+    ("HKT", "Asia/Hong_Kong"),
+    ("JST", "Asia/Tokyo"),
+    ("KST", "Asia/Seoul"),
+    ("AEST", "Australia/Sydney"),
+    ("AEDT", "Australia/Sydney"),
+    ("NZST", "Pacific/Auckland"),
+    ("NZDT", "Pacific/Auckland"),
+];
+
+/// Words that carry no information, as in `on Thu at 3pm`
+const FILLERS: &[&str] = &["at", "on", "in", "@", "-", "from", "starting"];
+
+enum Day {
+    Today,
+    Tomorrow,
+    Weekday(Weekday),
+    Date(NaiveDate),
+}
+
+/// The instant `text` names, as of `now`
+pub fn parse(config: &Config, now: DateTime<Local>, text: &str) -> Result<DateTime<Local>, String> {
+    let text = text.trim();
+    if let Ok(instant) = DateTime::parse_from_rfc3339(text) {
+        return Ok(instant.with_timezone(&Local));
+    }
+    if let Some(instant) = epoch(text) {
+        return Ok(instant);
+    }
+
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            word.trim_matches(|c: char| ",.;!?()".contains(c))
+                .to_string()
+        })
+        .filter(|word| !word.is_empty() && !FILLERS.contains(&word.to_lowercase().as_str()))
+        .collect();
+    let mut day = None;
+    let mut time = None;
+    // The other words, with their position
+    let mut rest: Vec<(usize, &str)> = Vec::new();
+    let mut time_at = 0;
+    let mut index = 0;
+    while index < words.len() {
+        let word = &words[index];
+        let next = words.get(index + 1).map(|next| next.to_lowercase());
+        if day.is_none()
+            && let Some(parsed) = parse_day(word)
+        {
+            day = Some(parsed);
+        } else if time.is_none()
+            && let Some(parsed) = parse_time(word, next.as_deref())
+        {
+            time = Some(parsed.0);
+            time_at = index;
+            index += parsed.1;
+        } else {
+            rest.push((index, word.as_str()));
+        }
+        index += 1;
+    }
+    let time = time.ok_or_else(|| format!("no time in '{}', expected e.g. 3pm PT", text))?;
+    if rest.is_empty() {
+        return instant_in(&Local, now, day, time);
+    }
+    // Chat has other words around the zone: try runs of up to three adjacent
+    // words, longer ones first and nearer the time among equals
+    let mut runs: Vec<&[(usize, &str)]> = Vec::new();
+    for length in (1..=3).rev() {
+        let mut windows: Vec<_> = rest
+            .windows(length)
+            .filter(|run| run.windows(2).all(|pair| pair[0].0 + 1 == pair[1].0))
+            .collect();
+        windows.sort_by_key(|run| run[0].0.abs_diff(time_at + 1));
+        runs.extend(windows);
+    }
+    let tz = runs
+        .iter()
+        .find_map(|run| {
+            let zone: Vec<&str> = run.iter().map(|(_, word)| *word).collect();
+            parse_zone(config, &zone.join(" "))
+        })
+        .ok_or_else(|| format!("no timezone or city in '{}', expected e.g. 3pm PT", text))?;
+    instant_in(&tz, now, day, time)
+}
+
+/// Seconds or milliseconds since 1970
+fn epoch(text: &str) -> Option<DateTime<Local>> {
+    if !(9..=13).contains(&text.len()) || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let number: i64 = text.parse().ok()?;
+    let instant = if text.len() > 11 {
+        DateTime::from_timestamp_millis(number)?
+    } else {
+        DateTime::from_timestamp(number, 0)?
+    };
+    Some(instant.with_timezone(&Local))
+}
+
+fn parse_day(word: &str) -> Option<Day> {
+    let lower = word.to_lowercase();
+    match lower.as_str() {
+        "today" | "tonight" => return Some(Day::Today),
+        "tomorrow" | "tmrw" => return Some(Day::Tomorrow),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+        return Some(Day::Date(date));
+    }
+    // `Thu`, `Thurs`, `Thursday`; chrono takes the first three letters
+    let weekday: Weekday = lower.get(..3)?.parse().ok()?;
+    const NAMES: [&str; 7] = [
+        "monday",
+        "tuesday",
+        "wednesday",
+        "thursday",
+        "friday",
+        "saturday",
+        "sunday",
+    ];
+    NAMES[weekday.num_days_from_monday() as usize]
+        .starts_with(&lower)
+        .then_some(Day::Weekday(weekday))
+}
+
+/// The time in `word`, maybe with `am`/`pm` in the `next` word, and how
+/// many words after `word` it used
+fn parse_time(word: &str, next: Option<&str>) -> Option<(NaiveTime, usize)> {
+    let lower = word.to_lowercase();
+    match lower.as_str() {
+        "noon" | "midday" => return Some((NaiveTime::from_hms_opt(12, 0, 0)?, 0)),
+        "midnight" => return Some((NaiveTime::MIN, 0)),
+        _ => {}
+    }
+    let (clock, meridiem, used) = if let Some(clock) = lower.strip_suffix("am") {
+        (clock, Some(false), 0)
+    } else if let Some(clock) = lower.strip_suffix("pm") {
+        (clock, Some(true), 0)
+    } else {
+        match next {
+            Some("am" | "a.m" | "a.m.") => (lower.as_str(), Some(false), 1),
+            Some("pm" | "p.m" | "p.m.") => (lower.as_str(), Some(true), 1),
+            _ => (lower.as_str(), None, 0),
+        }
+    };
+    let (hour, minute) = match clock.split_once([':', '.']) {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse().ok()?, minute.parse().ok()?),
+        // A bare number is only a time with am/pm, `3pm`
+        None if meridiem.is_some() => (clock.parse().ok()?, 0),
+        _ => return None,
+    };
+    let hour: u32 = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(pm) => hour % 12 + if pm { 12 } else { 0 },
+        None => hour,
+    };
+    Some((NaiveTime::from_hms_opt(hour, minute, 0)?, used))
+}
+
+fn parse_zone(config: &Config, zone: &str) -> Option<Tz> {
+    let upper = zone.to_uppercase();
+    if let Some((_, name)) = ABBREVIATIONS
+        .iter()
+        .find(|(abbreviation, _)| *abbreviation == upper)
+    {
+        return name.parse().ok();
+    }
+    // `UTC+2`, `GMT-5`; the Etc zones count the other way
+    for prefix in ["UTC", "GMT"] {
+        if let Some(offset) = upper.strip_prefix(prefix)
+            && let Ok(hours) = offset.parse::<i32>()
+            && (-12..=14).contains(&hours)
+        {
+            return format!("Etc/GMT{:+}", -hours).parse().ok();
+        }
+    }
+    if let Ok(tz) = config.timezone(zone) {
+        return Some(tz);
+    }
+    if let Some(city) = config
+        .cities
+        .iter()
+        .find(|city| city.name.eq_ignore_ascii_case(zone))
+        && let Ok(tz) = config.timezone(&city.timezone)
+    {
+        return Some(tz);
+    }
+    chrono_tz::TZ_VARIANTS
+        .iter()
+        .find(|tz| tz.name().eq_ignore_ascii_case(zone))
+        .copied()
+        .or_else(|| citydb::resolve(zone).first()?.timezone.parse().ok())
+}
+
+/// `time` on `day` in `tz`; today or, once it has passed, tomorrow without a day
+fn instant_in<T: TimeZone>(
+    tz: &T,
+    now: DateTime<Local>,
+    day: Option<Day>,
+    time: NaiveTime,
+) -> Result<DateTime<Local>, String> {
+    let today = now.with_timezone(tz).date_naive();
+    let date = match &day {
+        None | Some(Day::Today) => today,
+        Some(Day::Tomorrow) => today + Days::new(1),
+        Some(Day::Weekday(weekday)) => {
+            let ahead =
+                (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+            today + Days::new(u64::from(ahead))
+        }
+        Some(Day::Date(date)) => *date,
+    };
+    let at = |date: NaiveDate| {
+        // Ambiguous times (DST fall-back) resolve to the earlier instant
+        tz.from_local_datetime(&date.and_time(time))
+            .earliest()
+            .map(|instant| instant.with_timezone(&Local))
+            .ok_or_else(|| format!("{} {} does not exist there (DST gap)", date, time))
+    };
+    let instant = at(date)?;
+    if day.is_none() && instant < now {
+        return at(date + Days::new(1));
+    }
+    Ok(instant)
+}
@@ -0,0 +1,123 @@
+//! macOS notifications, held back during `quiet_hours` and, with
+//! `honor_focus`, while a Focus is on.
+//!
+//! A notification sent meanwhile goes to a queue in the state file instead,
+//! and the first menu refresh after that delivers it.
+
+use crate::{Config, QuietHours, Role, focus, state};
+use chrono::{DateTime, Local, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// More deferred notifications than this are delivered as a single summary
+const MAX_DELIVERED: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deferred {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    /// When it was sent
+    pub at: DateTime<Utc>,
+}
+
+impl QuietHours {
+    /// Whether `time` is in the window, which may run past midnight
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Time of day in the home city, or locally without one
+fn home_time(config: &Config, now: DateTime<Local>) -> NaiveTime {
+    config
+        .cities
+        .iter()
+        .find(|city| city.role == Some(Role::Home))
+        .and_then(|city| config.timezone(&city.timezone).ok())
+        .map_or_else(|| now.time(), |tz| now.with_timezone(&tz).time())
+}
+
+/// The quiet hours window, if `now` is in it
+pub fn quiet_hours(config: &Config, now: DateTime<Local>) -> Option<&QuietHours> {
+    let quiet = config.quiet_hours.as_ref()?;
+    quiet.contains(home_time(config, now)).then_some(quiet)
+}
+
+/// Whether notifications wait in the queue: during quiet hours, or while a
+/// Focus is on with `honor_focus`
+fn is_held_back(config: &Config, now: DateTime<Local>) -> bool {
+    quiet_hours(config, now).is_some() || focus::is_active(config)
+}
+
+/// Show a notification now, or queue it in the state file while they are
+/// held back
+pub fn send(
+    config: &Config,
+    config_arg: &str,
+    now: DateTime<Local>,
+    title: &str,
+    message: &str,
+) -> Result<(), String> {
+    if !is_held_back(config, now) {
+        return deliver(title, message);
+    }
+    state::update(config_arg, |state| {
+        state.deferred.push(Deferred {
+            title: title.to_string(),
+            message: message.to_string(),
+            at: now.to_utc(),
+        })
+    })
+}
+
+/// Deliver the queued notifications once quiet hours and Focus are over.
+/// Failures are warnings: the menu still has to render.
+pub fn flush(config: &Config, config_arg: &str, now: DateTime<Local>) {
+    let deferred = &config.state.deferred;
+    if deferred.is_empty() || is_held_back(config, now) {
+        return;
+    }
+    let result = if deferred.len() > MAX_DELIVERED {
+        let titles: Vec<&str> = deferred.iter().map(|d| d.title.as_str()).collect();
+        deliver(
+            &format!("{} notifications held back", deferred.len()),
+            &titles.join(", "),
+        )
+    } else {
+        deferred
+            .iter()
+            .try_for_each(|d| deliver(&d.title, &d.message))
+    };
+    // Cleared even if delivery failed, so a broken osascript doesn't pile them up
+    let cleared = state::update(config_arg, |state| state.deferred.clear());
+    if let Err(err) = result.and(cleared) {
+        eprintln!("Warning: Deferred notifications: {}", err);
+    }
+}
+
+/// `display notification` through osascript, with the text passed as
+/// arguments so nothing needs quoting
+pub fn deliver(title: &str, message: &str) -> Result<(), String> {
+    let status = Command::new("osascript")
+        .args([
+            "-e",
+            "on run argv",
+            "-e",
+            "display notification (item 2 of argv) with title (item 1 of argv)",
+            "-e",
+            "end run",
+            title,
+            message,
+        ])
+        .status()
+        .map_err(|e| format!("can't run osascript (macOS only): {}", e))?;
+    if !status.success() {
+        return Err(format!("osascript exited with {}", status));
+    }
+    Ok(())
+}
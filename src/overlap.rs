@@ -0,0 +1,139 @@
+//! The windows of a day in which everyone is at work: each city's working
+//! hours as instants, intersected across all cities.
+//!
+//! Cities out of office don't count, and a dual-zone entry counts with both
+//! of its zones, as in `CityClock::is_working_hours`.
+
+use crate::CityConfig;
+use crate::render::CityClock;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// A stretch of time, `start` inclusive and `end` exclusive
+#[derive(Clone, Copy)]
+pub struct Window {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Window {
+    /// `15:00–17:00` in my local time, or `3:00 PM–5:00 PM`
+    pub fn format(&self, twelve_hour: bool) -> String {
+        let format = if twelve_hour { "%-l:%M %p" } else { "%H:%M" };
+        format!(
+            "{}–{}",
+            self.start.with_timezone(&Local).format(format),
+            self.end.with_timezone(&Local).format(format)
+        )
+    }
+
+    pub fn duration(&self) -> TimeDelta {
+        self.end - self.start
+    }
+}
+
+/// Common working hours on `day`, a date of my local time, earliest first.
+/// Empty if no city has working hours that day.
+pub fn windows(clocks: &[CityClock], day: NaiveDate) -> Vec<Window> {
+    let (Some(from), Some(to)) = (local_midnight(day), day.succ_opt().and_then(local_midnight))
+    else {
+        return Vec::new();
+    };
+    let mut common = vec![Window {
+        start: from,
+        end: to,
+    }];
+    let mut constrained = false;
+    for clock in clocks {
+        let zones = [
+            Some(clock.time.timezone()),
+            clock.second.map(|t| t.timezone()),
+        ];
+        for tz in zones.into_iter().flatten() {
+            let hours = working_windows(clock.city, tz, from, to);
+            // Out of office all day: not in the way of anyone else's meeting
+            if hours.is_empty() && is_ooo_during(clock.city, tz, from, to) {
+                continue;
+            }
+            common = intersect(&common, &hours);
+            constrained = true;
+        }
+    }
+    if constrained { common } else { Vec::new() }
+}
+
+/// Working hours of `city` in `tz` overlapping `from..to`
+fn working_windows(
+    city: &CityConfig,
+    tz: Tz,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Vec<Window> {
+    let first = from.with_timezone(&tz).date_naive().pred_opt();
+    let last = to.with_timezone(&tz).date_naive();
+    let mut windows = Vec::new();
+    let mut date = first;
+    while let Some(day) = date.filter(|day| *day <= last) {
+        if !city.is_ooo(day)
+            && let Some((start, end)) = city.working_day(day)
+            && let (Some(start), Some(end)) = (instant(tz, start), instant(tz, end))
+        {
+            let start = start.max(from);
+            let end = end.min(to);
+            if start < end {
+                windows.push(Window { start, end });
+            }
+        }
+        date = day.succ_opt();
+    }
+    windows
+}
+
+fn is_ooo_during(city: &CityConfig, tz: Tz, from: DateTime<Utc>, to: DateTime<Utc>) -> bool {
+    city.is_ooo(from.with_timezone(&tz).date_naive())
+        || city.is_ooo((to - TimeDelta::seconds(1)).with_timezone(&tz).date_naive())
+}
+
+/// Parts of `a` that are also in `b`. Both are sorted and non-overlapping,
+/// and so is the result.
+fn intersect(a: &[Window], b: &[Window]) -> Vec<Window> {
+    let (mut i, mut j) = (0, 0);
+    let mut common = Vec::new();
+    while i < a.len() && j < b.len() {
+        let start = a[i].start.max(b[j].start);
+        let end = a[i].end.min(b[j].end);
+        if start < end {
+            common.push(Window { start, end });
+        }
+        if a[i].end < b[j].end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    common
+}
+
+/// A local time in `tz`, moved past the gap if a DST change skips it
+fn instant(tz: Tz, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&time)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(time + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn local_midnight(day: NaiveDate) -> Option<DateTime<Utc>> {
+    let midnight = day.and_hms_opt(0, 0, 0)?;
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| {
+            Local
+                .from_local_datetime(&(midnight + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .map(|t| t.with_timezone(&Utc))
+}
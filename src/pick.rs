@@ -0,0 +1,158 @@
+//! `pick`: a fuzzy finder over the city database and every IANA timezone.
+//! The chosen place is appended to the config, which is how the menu's
+//! "Add city…" adds one.
+
+use crate::{citydb, config_edit, fuzzy, resolve_config_path};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+struct Candidate {
+    /// What is shown and matched, e.g. `Kraków, Lesser Poland, PL — Europe/Warsaw`
+    label: String,
+    /// The city name written to the config
+    name: String,
+    timezone: String,
+}
+
+struct Picker {
+    candidates: Vec<Candidate>,
+    query: String,
+    /// Indices into `candidates`, best match first
+    matches: Vec<usize>,
+    list: ListState,
+}
+
+pub fn run(config: &str) -> Result<(), String> {
+    let path = resolve_config_path(config)?;
+    let mut picker = Picker::new(candidates());
+    let mut terminal = ratatui::init();
+    let picked = picker.event_loop(&mut terminal);
+    ratatui::restore();
+    let Some(candidate) = picked?.map(|index| &picker.candidates[index]) else {
+        return Ok(());
+    };
+    config_edit::add_city(&path, &candidate.name, &candidate.timezone)?;
+    println!("Added {} ({})", candidate.name, candidate.timezone);
+    Ok(())
+}
+
+/// Cities first, most populous first, then the timezones themselves named
+/// after their last part (`America/Argentina/Buenos_Aires` is "Buenos Aires")
+fn candidates() -> Vec<Candidate> {
+    let cities = citydb::all().iter().map(|city| {
+        let mut label = city.name.to_string();
+        for part in [city.region, city.country] {
+            if !part.is_empty() {
+                label.push_str(", ");
+                label.push_str(part);
+            }
+        }
+        Candidate {
+            label: format!("{} — {}", label, city.timezone),
+            name: city.name.to_string(),
+            timezone: city.timezone.to_string(),
+        }
+    });
+    let zones = chrono_tz::TZ_VARIANTS.iter().map(|tz| {
+        let timezone = tz.name();
+        let last = timezone.rsplit('/').next().unwrap_or(timezone);
+        Candidate {
+            label: timezone.to_string(),
+            name: last.replace('_', " "),
+            timezone: timezone.to_string(),
+        }
+    });
+    cities.chain(zones).collect()
+}
+
+impl Picker {
+    fn new(candidates: Vec<Candidate>) -> Self {
+        let mut picker = Picker {
+            candidates,
+            query: String::new(),
+            matches: Vec::new(),
+            list: ListState::default(),
+        };
+        picker.filter();
+        picker
+    }
+
+    /// Re-rank for the current query; ties keep the candidates' order
+    fn filter(&mut self) {
+        let mut scored: Vec<(i32, usize)> = self
+            .candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(index, candidate)| {
+                Some((fuzzy::score(&self.query, &candidate.label)?, index))
+            })
+            .collect();
+        scored.sort_by_key(|&(score, index)| (std::cmp::Reverse(score), index));
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+        self.list.select((!self.matches.is_empty()).then_some(0));
+    }
+
+    /// The index of the picked candidate, `None` if cancelled
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<usize>, String> {
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|e| e.to_string())?;
+            let Event::Key(key) = event::read().map_err(|e| e.to_string())? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Enter => {
+                    if let Some(&index) = self.list.selected().and_then(|i| self.matches.get(i)) {
+                        return Ok(Some(index));
+                    }
+                }
+                KeyCode::Down => self.list.select_next(),
+                KeyCode::Up => self.list.select_previous(),
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.filter();
+                }
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.filter();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [input, list] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(frame.area());
+        let title = format!(
+            " Add city · {} matches · Enter to add, Esc to cancel ",
+            self.matches.len()
+        );
+        frame.render_widget(
+            Paragraph::new(format!("> {}", self.query)).block(Block::bordered().title(title)),
+            input,
+        );
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            // Only what fits; ratatui would lay out all of them
+            .take(usize::from(list.height) + self.list.offset() + 1)
+            .map(|&index| ListItem::new(Line::from(self.candidates[index].label.as_str())))
+            .collect();
+        frame.render_stateful_widget(
+            List::new(items).highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            list,
+            &mut self.list,
+        );
+        frame.set_cursor_position((input.x + 3 + self.query.chars().count() as u16, input.y + 1));
+    }
+}
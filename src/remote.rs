@@ -0,0 +1,53 @@
+//! Fetching configs from a URL, with an on-disk copy of the last good response.
+
+use crate::{cache, fetchd};
+use std::time::Duration;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("https://") || path.starts_with("http://")
+}
+
+const CACHE_NAMESPACE: &str = "remote";
+
+/// Content of `url`, from the cache alone while `fetchd` keeps it fresh
+pub fn fetch_cached(url: &str) -> Option<String> {
+    if fetchd::is_running()
+        && let Some(body) = cache::get_stale(CACHE_NAMESPACE, url)
+    {
+        return Some(body);
+    }
+    refresh(url)
+}
+
+/// Fetch `url`, falling back to the cached copy if the network is unavailable
+pub fn refresh(url: &str) -> Option<String> {
+    match fetch(url) {
+        Ok(body) => {
+            cache::put(CACHE_NAMESPACE, url, &body);
+            Some(body)
+        }
+        Err(err) => {
+            eprintln!("Warning: Could not fetch {}: {}", url, err);
+            cache::get_stale(CACHE_NAMESPACE, url)
+        }
+    }
+}
+
+/// Time since `url` was last fetched successfully
+pub fn age(url: &str) -> Option<Duration> {
+    cache::age(CACHE_NAMESPACE, url)
+}
+
+/// HTTP agent with the timeout used for all network access
+pub fn agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(FETCH_TIMEOUT))
+        .build()
+        .into()
+}
+
+fn fetch(url: &str) -> Result<String, ureq::Error> {
+    agent().get(url).call()?.body_mut().read_to_string()
+}
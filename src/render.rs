@@ -0,0 +1,2230 @@
+//! The per-city data model shared by all outputs, and the renderers built on it.
+
+use crate::alerts::{self, Alerts};
+use crate::line::Params;
+use crate::{
+    CityConfig, Config, DayOffset, ExtraLinesConfig, MenuIcon, QuietHours, Role, SortBy,
+    TimeFormat, calendar, callback, color, comfort, convert, dst, duration, faces, flags, focus,
+    get_accurate_clock_icon, headers, hooks, notify, overlap, remote, rules, sun,
+    timefmt::{self, Preset},
+    tzdata,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Local, NaiveDate, Offset, SecondsFormat, TimeDelta, Timelike, Utc};
+use chrono_tz::{OffsetComponents, Tz};
+use serde_json::{Value, json};
+use std::fmt::Write;
+use std::fs;
+
+/// Where someone is in their day, see [`CityClock::availability`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    Working,
+    OffHours,
+    Asleep,
+}
+
+impl Availability {
+    /// Name in JSON output
+    pub fn label(self) -> &'static str {
+        match self {
+            Availability::Working => "working",
+            Availability::OffHours => "off_hours",
+            Availability::Asleep => "asleep",
+        }
+    }
+
+    /// SwiftBar color of a row with `color_by_hours`
+    pub fn color(self) -> &'static str {
+        match self {
+            Availability::Working => "green",
+            Availability::OffHours => "orange",
+            Availability::Asleep => "red",
+        }
+    }
+
+    /// Terminal color of the time in the table
+    fn ansi(self) -> &'static str {
+        match self {
+            Availability::Working => color::GREEN,
+            Availability::OffHours => color::YELLOW,
+            Availability::Asleep => color::RED,
+        }
+    }
+}
+
+/// A configured city resolved to a point in time
+pub struct CityClock<'a> {
+    pub city: &'a CityConfig,
+    pub time: DateTime<Tz>,
+    /// Offset from UTC in seconds
+    pub utc_offset_secs: i32,
+    /// Offset from the base timezone (home city or local) in seconds
+    pub diff_secs: i32,
+    /// Time format of the city, or the global one
+    pub preset: Option<Preset>,
+    /// The city's strftime format, which wins over `preset`
+    pub format: Option<&'a str>,
+    /// Cities shown on the same line, see `merge_same_offset`
+    pub merged: Vec<&'a CityConfig>,
+    /// Time at the city's `second` location, if it has a valid one
+    pub second: Option<DateTime<Tz>>,
+}
+
+impl CityClock<'_> {
+    pub fn is_ooo(&self) -> bool {
+        self.city.is_ooo(self.time.date_naive())
+    }
+
+    /// The city's flag with `flags: true`
+    pub fn flag(&self, config: &Config) -> Option<String> {
+        config
+            .flags
+            .then(|| flags::city_flag(self.city, self.time.timezone()))
+            .flatten()
+    }
+
+    pub fn is_dst(&self) -> bool {
+        !self.time.offset().dst_offset().is_zero()
+    }
+
+    /// Working hours are 09:00-17:00 on weekdays in the city unless it sets
+    /// `work_start`/`work_end`. A dual-zone entry may be in either place, so it
+    /// has to be working hours in both.
+    pub fn is_working_hours(&self) -> bool {
+        let working = |time: &DateTime<Tz>| self.city.is_working_at(time.naive_local());
+        working(&self.time) && self.second.as_ref().is_none_or(working)
+    }
+
+    /// Working hours, or else asleep from 22:00 to 07:00, or else off hours.
+    /// A dual-zone entry is asleep if it is night in either place.
+    pub fn availability(&self) -> Availability {
+        let asleep = |time: &DateTime<Tz>| !(7..22).contains(&time.hour());
+        if self.is_working_hours() {
+            Availability::Working
+        } else if asleep(&self.time) || self.second.as_ref().is_some_and(asleep) {
+            Availability::Asleep
+        } else {
+            Availability::OffHours
+        }
+    }
+
+    /// Daytime is 06:00-18:00 in the city
+    pub fn is_daytime(&self) -> bool {
+        (6..18).contains(&self.time.hour())
+    }
+
+    /// The city's time in its configured format
+    pub fn formatted_time(&self) -> String {
+        let mut output = String::new();
+        self.write_time(&mut output);
+        output
+    }
+
+    /// Like [`formatted_time`](Self::formatted_time), into `output`
+    pub fn write_time(&self, output: &mut String) {
+        self.write_time_of(output, &self.time);
+    }
+
+    /// `time` in the city's format, for its second location
+    fn write_time_of(&self, output: &mut String, time: &DateTime<Tz>) {
+        match self.format {
+            Some(format) => {
+                let _ = write!(output, "{}", time.format(format));
+            }
+            None => timefmt::write_time(output, time, self.preset),
+        }
+    }
+
+    /// Offset from the local timezone in seconds, which differs from
+    /// `diff_secs` when offsets are relative to a home city
+    pub fn local_diff_secs(&self) -> i32 {
+        let local = self
+            .time
+            .with_timezone(&Local)
+            .offset()
+            .fix()
+            .local_minus_utc();
+        self.utc_offset_secs - local
+    }
+
+    /// Offset of the second location from the base timezone in seconds
+    pub fn second_diff_secs(&self) -> Option<i32> {
+        let second = self.second?.offset().fix().local_minus_utc();
+        Some(second - (self.utc_offset_secs - self.diff_secs))
+    }
+
+    /// Whether the time has a configured format instead of the default `15:04`
+    pub fn has_format(&self) -> bool {
+        self.format.is_some() || self.preset.is_some()
+    }
+
+    /// How comfortable the current time is in the city, see the `comfort`
+    /// module; for a dual-zone entry, in the less comfortable of the two
+    pub fn comfort(&self) -> u8 {
+        let score = |time: &DateTime<Tz>| comfort::score(time.hour(), time.minute());
+        let second = self.second.as_ref().map_or(u8::MAX, score);
+        score(&self.time).min(second)
+    }
+
+    /// The city's `etiquette` notes that apply right now
+    pub fn etiquette(&self) -> impl Iterator<Item = &str> {
+        let now = self.time.naive_local();
+        self.city
+            .etiquette
+            .iter()
+            .filter(move |etiquette| etiquette.applies(now))
+            .map(|etiquette| etiquette.note.as_str())
+    }
+
+    /// First line of the city's `exec` command output, if it has one
+    pub fn exec_output(&self) -> Option<String> {
+        let output = hooks::run_cached(
+            self.city.exec.as_deref()?,
+            hooks::ttl(self.city.exec_ttl),
+            hooks::DEFAULT_TIMEOUT,
+        )
+        .ok()?;
+        hooks::first_line(&output).map(str::to_string)
+    }
+
+    /// Age of the exec output, if it is older than its TTL
+    pub fn stale_exec_age(&self) -> Option<std::time::Duration> {
+        let age = hooks::age(self.city.exec.as_deref()?)?;
+        (age > hooks::ttl(self.city.exec_ttl)).then_some(age)
+    }
+
+    /// Time since the city's last midnight and until its next one. Days with a
+    /// DST change are shorter or longer than 24 hours.
+    pub fn midnights(&self) -> (TimeDelta, TimeDelta) {
+        // Whole minutes, like the time shown next to them
+        let time = self
+            .time
+            .with_second(0)
+            .and_then(|time| time.with_nanosecond(0))
+            .unwrap_or(self.time);
+        let tz = time.timezone();
+        let today = time.date_naive();
+        // Midnight may be skipped by a DST change, the day then starts at 01:00
+        let start_of = |date: NaiveDate| {
+            (0..3).find_map(|hour| {
+                date.and_hms_opt(hour, 0, 0)?
+                    .and_local_timezone(tz)
+                    .earliest()
+            })
+        };
+        let since = start_of(today).map_or_else(
+            || TimeDelta::seconds(i64::from(time.num_seconds_from_midnight())),
+            |midnight| time - midnight,
+        );
+        let until = today
+            .succ_opt()
+            .and_then(start_of)
+            .map_or_else(|| TimeDelta::days(1) - since, |midnight| midnight - time);
+        (since, until)
+    }
+
+    /// Days the city's date is ahead of mine (negative if behind), mine being
+    /// the date of the base timezone
+    pub fn day_offset(&self) -> i64 {
+        let mine = self.time.naive_local() - TimeDelta::seconds(i64::from(self.diff_secs));
+        (self.time.date_naive() - mine.date()).num_days()
+    }
+
+    /// End of the city's working day less `buffer`, the last moment a message
+    /// can expect an answer the same day: today's if it is still ahead, the
+    /// next working day's otherwise
+    pub fn reply_deadline(&self, buffer: TimeDelta) -> Option<DateTime<Tz>> {
+        let tz = self.time.timezone();
+        let mut date = self.time.date_naive();
+        for _ in 0..7 {
+            if let Some((_, end)) = self.city.working_day(date)
+                && let Some(end) = end.and_local_timezone(tz).earliest()
+                && end - buffer > self.time
+            {
+                return Some(end - buffer);
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    pub fn group(&self) -> u8 {
+        Role::group_rank(self.city.role)
+    }
+}
+
+/// City relative offsets are computed from: the one picked in the menu, or the home city
+fn base_city(config: &Config) -> Option<&CityConfig> {
+    let relative_to = config.state.relative_to.as_deref();
+    relative_to
+        .and_then(|name| config.cities.iter().find(|city| city.name == name))
+        .or_else(|| {
+            config
+                .cities
+                .iter()
+                .find(|city| city.role == Some(Role::Home))
+        })
+}
+
+/// The city whose sun the menu bar icon follows: home, or the first one
+fn sun_city(config: &Config) -> Option<(&CityConfig, Tz)> {
+    let city = config
+        .cities
+        .iter()
+        .find(|city| city.role == Some(Role::Home))
+        .or(config.cities.first())?;
+    Some((city, config.timezone(&city.timezone).ok()?))
+}
+
+/// Whether the sun is up in the home city (or the first one), from its
+/// coordinates if they are known and 06:00-18:00 otherwise
+fn is_sun_up(config: &Config, now: DateTime<Local>) -> bool {
+    let Some((city, tz)) = sun_city(config) else {
+        return (6..18).contains(&now.hour());
+    };
+    let local = now.with_timezone(&tz);
+    match city.coordinates(tz) {
+        Some((latitude, longitude)) => sun::is_up(
+            now.with_timezone(&Utc),
+            local.date_naive(),
+            latitude,
+            longitude,
+        ),
+        None => (6..18).contains(&local.hour()),
+    }
+}
+
+/// Part of the day in the sun city, from its coordinates or with the sun up
+/// from 06:00 to 18:00 without them
+fn daypart(config: &Config, now: DateTime<Local>) -> sun::Daypart {
+    let local = match sun_city(config) {
+        Some((city, tz)) => {
+            let local = now.with_timezone(&tz);
+            if let Some((latitude, longitude)) = city.coordinates(tz) {
+                let utc = now.with_timezone(&Utc);
+                return sun::daypart(utc, local.date_naive(), latitude, longitude);
+            }
+            local.naive_local()
+        }
+        None => now.naive_local(),
+    };
+    let at = |hour| local.date().and_hms_opt(hour, 0, 0).expect("valid time");
+    sun::daypart_between(local, at(6), at(18))
+}
+
+/// Write the `daypart_icons` icon of `part`, or its default emoji
+fn write_daypart_icon(
+    output: &mut String,
+    params: &mut Params,
+    config: &Config,
+    part: sun::Daypart,
+) {
+    let emoji = match part {
+        sun::Daypart::Dawn => "🌅",
+        sun::Daypart::Day => "☀️",
+        sun::Daypart::Dusk => "🌇",
+        sun::Daypart::Night => "🌙",
+    };
+    let icon = config
+        .daypart_icons
+        .as_ref()
+        .and_then(|icons| icons.icon(part));
+    match icon {
+        Some(symbol) if symbol.starts_with("sf:") => {
+            params.set("sfimage", &symbol[3..]);
+        }
+        Some(path) if path.ends_with(".png") => match fs::read(path) {
+            Ok(png) => {
+                params.set("templateImage", STANDARD.encode(png));
+            }
+            Err(err) => {
+                eprintln!("Warning: Cannot read menu icon {}: {}", path, err);
+                output.push_str(emoji);
+            }
+        },
+        Some(text) => output.push_str(text),
+        None => output.push_str(emoji),
+    }
+}
+
+/// Timezone of the base city, `None` if relative offsets are computed from local time
+pub fn base_timezone(config: &Config) -> Option<Tz> {
+    base_city(config).and_then(|city| config.timezone(&city.timezone).ok())
+}
+
+/// UTC offset relative offsets are computed from: the base city's if there is one, local otherwise
+pub fn base_offset_secs(config: &Config, now: DateTime<Local>) -> i32 {
+    base_timezone(config)
+        .map(|tz| now.with_timezone(&tz).offset().fix().local_minus_utc())
+        .unwrap_or_else(|| now.offset().fix().local_minus_utc())
+}
+
+/// Configured cities with their timezones parsed once, in display order
+///
+/// Streaming modes keep one of these around so each frame only converts the
+/// current instant instead of re-parsing every timezone.
+pub struct ResolvedCities<'a> {
+    config: &'a Config,
+    cities: Vec<ResolvedCity<'a>>,
+    /// Timezone offsets are relative to, see `base_city`
+    base: Option<Tz>,
+    /// Cities that could not be resolved and rules that could not be parsed
+    problems: Vec<String>,
+}
+
+struct ResolvedCity<'a> {
+    city: &'a CityConfig,
+    tz: Tz,
+    show_if: Option<rules::Expr>,
+    /// The city's format if it is valid
+    format: Option<&'a str>,
+    /// Timezone of the city's second location if it is valid
+    second: Option<Tz>,
+}
+
+impl<'a> ResolvedCities<'a> {
+    /// Cities with an invalid timezone are skipped with a warning, and so are
+    /// invalid `show_if` rules
+    pub fn new(config: &'a Config) -> Self {
+        let mut problems = Vec::new();
+        let mut cities: Vec<ResolvedCity> = config
+            .cities
+            .iter()
+            .filter_map(|city| match config.timezone(&city.timezone) {
+                Ok(tz) => Some(ResolvedCity {
+                    city,
+                    tz,
+                    show_if: city.show_if.as_deref().and_then(|rule| {
+                        rules::parse(rule)
+                            .map_err(|err| {
+                                eprintln!(
+                                    "Warning: Invalid show_if rule for {}: {}",
+                                    city.name, err
+                                );
+                                problems.push(format!("{}: invalid show_if: {}", city.name, err));
+                            })
+                            .ok()
+                    }),
+                    format: city.format.as_deref().filter(|format| {
+                        timefmt::check_format(format)
+                            .map_err(|err| {
+                                eprintln!("Warning: {} for {}", err, city.name);
+                                problems.push(format!("{}: {}", city.name, err));
+                            })
+                            .is_ok()
+                    }),
+                    second: city.second.as_ref().and_then(|second| {
+                        config
+                            .timezone(&second.timezone)
+                            .map_err(|err| {
+                                eprintln!(
+                                    "Warning: Invalid timezone '{}' for {} / {}",
+                                    second.timezone, city.name, second.name
+                                );
+                                problems.push(format!("{} / {}: {}", city.name, second.name, err));
+                            })
+                            .ok()
+                    }),
+                }),
+                Err(err) if city.timezone.is_empty() => {
+                    eprintln!("Warning: {}: {}", city.name, err);
+                    problems.push(format!("{}: {}", city.name, err));
+                    None
+                }
+                Err(err) => {
+                    eprintln!(
+                        "Warning: Invalid timezone '{}' for {}",
+                        city.timezone, city.name
+                    );
+                    problems.push(format!("{}: {}", city.name, err));
+                    None
+                }
+            })
+            .collect();
+
+        if config.group_by_role {
+            // Stable sort keeps the configured order within each group
+            cities.sort_by_key(|resolved| Role::group_rank(resolved.city.role));
+        }
+        let base = base_city(config).and_then(|base| {
+            cities
+                .iter()
+                .find(|resolved| std::ptr::eq(resolved.city, base))
+                .map(|resolved| resolved.tz)
+        });
+
+        ResolvedCities {
+            config,
+            cities,
+            base,
+            problems,
+        }
+    }
+
+    pub fn config(&self) -> &'a Config {
+        self.config
+    }
+
+    /// What went wrong resolving the cities, one line per city
+    pub fn problems(&self) -> &[String] {
+        &self.problems
+    }
+
+    pub fn clocks(&self, now: DateTime<Local>) -> Vec<CityClock<'a>> {
+        let mut clocks = Vec::with_capacity(self.cities.len());
+        self.clocks_into(now, &mut clocks);
+        clocks
+    }
+
+    /// Like [`clocks`](Self::clocks), reusing the allocation of `clocks`
+    pub fn clocks_into(&self, now: DateTime<Local>, clocks: &mut Vec<CityClock<'a>>) {
+        let base_offset_secs = match self.base {
+            Some(tz) => now.with_timezone(&tz).offset().fix().local_minus_utc(),
+            None => now.offset().fix().local_minus_utc(),
+        };
+
+        clocks.clear();
+        for resolved in &self.cities {
+            let time = now.with_timezone(&resolved.tz);
+            if !resolved.city.is_active(time.date_naive()) {
+                continue;
+            }
+            let utc_offset_secs = time.offset().fix().local_minus_utc();
+            let clock = CityClock {
+                city: resolved.city,
+                time,
+                utc_offset_secs,
+                diff_secs: utc_offset_secs - base_offset_secs,
+                preset: resolved.city.preset.or(self.config.preset).or(
+                    // 12-hour times are the kitchen preset
+                    match self.config.time_format() {
+                        TimeFormat::TwelveHour => Some(Preset::Kitchen),
+                        TimeFormat::TwentyFourHour => None,
+                    },
+                ),
+                format: resolved.format,
+                merged: Vec::new(),
+                second: resolved.second.map(|tz| now.with_timezone(&tz)),
+            };
+            // Cities whose rule doesn't hold right now are left out
+            if resolved
+                .show_if
+                .as_ref()
+                .is_none_or(|rule| rules::matches(rule, &clock))
+            {
+                clocks.push(clock);
+            }
+        }
+
+        if self.config.sort_by == SortBy::Comfort {
+            // Most comfortable first, within the role groups if cities are grouped
+            let group_by_role = self.config.group_by_role;
+            clocks.sort_by_key(|clock| {
+                let group = if group_by_role { clock.group() } else { 0 };
+                (group, std::cmp::Reverse(clock.comfort()))
+            });
+        }
+    }
+}
+
+/// Cities in display order, cities with an invalid timezone are skipped with a warning
+pub fn city_clocks(config: &Config, now: DateTime<Local>) -> Vec<CityClock<'_>> {
+    ResolvedCities::new(config).clocks(now)
+}
+
+/// Identifier-safe form of a city name, e.g. "New York" becomes "new-york"
+pub fn slug(name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    slug.split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// UTC offset such as `UTC+2`, `UTC-4:30` or `UTC+5:45`
+pub fn format_utc_offset(offset_secs: i32) -> String {
+    let sign = if offset_secs < 0 { '-' } else { '+' };
+    let minutes = offset_secs.unsigned_abs() / 60;
+    match minutes % 60 {
+        0 => format!("UTC{}{}", sign, minutes / 60),
+        rest => format!("UTC{}{}:{:02}", sign, minutes / 60, rest),
+    }
+}
+
+/// Offset suffix such as ` (+2h)` or ` (+5:45)`, empty when there is no difference
+pub fn format_diff(diff_secs: i32) -> String {
+    let mut output = String::new();
+    write_diff(&mut output, diff_secs);
+    output
+}
+
+/// Signed offset such as `+2h` or `-5:30`, see `write_hours`
+pub fn format_hours(diff_secs: i32) -> String {
+    let mut output = String::new();
+    write_hours(&mut output, diff_secs);
+    output
+}
+
+fn write_diff(output: &mut String, diff_secs: i32) {
+    if diff_secs == 0 {
+        return;
+    }
+    output.push_str(" (");
+    write_hours(output, diff_secs);
+    output.push(')');
+}
+
+/// Signed offset such as `+2h`, `-9h` or `+5:45`
+fn write_hours(output: &mut String, diff_secs: i32) {
+    let sign = if diff_secs < 0 { '-' } else { '+' };
+    // Half- and quarter-hour zones (India, Nepal, Chatham) keep their minutes
+    let minutes = diff_secs.unsigned_abs() / 60;
+    let _ = match minutes % 60 {
+        0 => write!(output, "{}{}h", sign, minutes / 60),
+        rest => write!(output, "{}{}:{:02}", sign, minutes / 60, rest),
+    };
+}
+
+/// Whether the city's row ends with its UTC offset, see `show_offset`
+fn shows_offset(config: &Config, clock: &CityClock) -> bool {
+    clock.city.show_offset.unwrap_or(config.show_offset)
+}
+
+/// ` · UTC+9 · +13h from local`, or ` · UTC+2 · same as local`
+fn write_offset_column(output: &mut String, clock: &CityClock) {
+    output.push_str(" · ");
+    output.push_str(&format_utc_offset(clock.utc_offset_secs));
+    output.push_str(" · ");
+    match clock.local_diff_secs() {
+        0 => output.push_str("same as local"),
+        diff_secs => {
+            write_hours(output, diff_secs);
+            output.push_str(" from local");
+        }
+    }
+}
+
+pub fn render_swiftbar(config: &Config, now: DateTime<Local>) -> String {
+    let mut output = String::new();
+    render_swiftbar_into(
+        &mut output,
+        &ResolvedCities::new(config),
+        now,
+        &mut Vec::new(),
+    );
+    output
+}
+
+/// Render the SwiftBar menu into `output`, reusing its and `clocks`' allocations
+pub fn render_swiftbar_into<'a>(
+    output: &mut String,
+    resolved: &ResolvedCities<'a>,
+    now: DateTime<Local>,
+    clocks: &mut Vec<CityClock<'a>>,
+) {
+    let config = resolved.config();
+    let focus = focus::is_active(config);
+    output.clear();
+
+    let mut params = Params::default();
+    if config.accessibility {
+        // The local time spelled out instead of the clock face emoji
+        let _ = write!(output, "{:02}:{:02}", now.hour(), now.minute());
+    } else if config.menu_icon == MenuIcon::Sun {
+        output.push_str(if is_sun_up(config, now) {
+            "☀️"
+        } else {
+            "🌙"
+        });
+    } else if config.menu_icon == MenuIcon::Daypart {
+        write_daypart_icon(output, &mut params, config, daypart(config, now));
+    } else if let Some(image) = config
+        .clock_faces
+        .as_ref()
+        .and_then(|faces| faces::image_param(faces, now.hour(), now.minute()))
+    {
+        params.set(image.0, image.1);
+    } else {
+        // Get clock icon based on current local minutes
+        output.push_str(get_accurate_clock_icon(now.hour(), now.minute()));
+    }
+    let alerts = Alerts::collect(config, now);
+    if config.alert_badge {
+        alerts::write_badge(output, alerts.count());
+    }
+    if focus {
+        // Dimmed while a Focus is on
+        params.set("color", "gray");
+    }
+    params.write(output);
+    output.push_str("\n---\n");
+    resolved.clocks_into(now, clocks);
+
+    let relative_to = config.state.relative_to.as_deref();
+    if let Some(base) = clocks
+        .iter()
+        .find(|clock| Some(&*clock.city.name) == relative_to)
+    {
+        let _ = writeln!(
+            output,
+            "Relative to {}: {} {}",
+            base.city.name,
+            base.formatted_time(),
+            base.time.format("%a")
+        );
+    }
+    for template in &config.headers {
+        headers::expand(template, now, output);
+        output.push('\n');
+    }
+
+    if config.merge_same_offset {
+        merge_same_offset(clocks, config.group_by_role);
+    }
+    output.reserve(clocks.len() * ROW_CAPACITY);
+    // Without max_visible, rosters only get collapsed once they are large
+    let visible = match config.max_visible {
+        Some(max_visible) => max_visible,
+        None if clocks.len() > LARGE_LIST => PAGE_SIZE,
+        None => usize::MAX,
+    }
+    .min(clocks.len());
+    let (inline, more) = clocks.split_at(visible);
+
+    let mut previous_group = None;
+    for clock in inline {
+        let group = clock.group();
+        if config.group_by_role && previous_group.is_some_and(|previous| previous != group) {
+            output.push_str("---\n");
+        }
+        previous_group = Some(group);
+        write_city_row(output, config, clock, 0);
+    }
+
+    if !more.is_empty() {
+        output.push_str("---\nMore cities\n");
+        if more.len() > PAGE_SIZE {
+            write_letter_groups(output, config, more);
+        } else {
+            for clock in more {
+                write_city_row(output, config, clock, 1);
+            }
+        }
+    }
+
+    write_dst_warnings(output, config, now, &alerts.dst);
+    if let Some(quiet) = notify::quiet_hours(config, now) {
+        write_quiet_hours(output, config, quiet);
+    }
+    if config.show_overlap {
+        // Before merging, cities at the same offset may still keep other hours
+        write_overlap(output, config, &resolved.clocks(now), now);
+    }
+
+    // Everything below is optional: a section that fails is left out and
+    // listed under the problems at the end instead
+    let mut problems = resolved.problems().to_vec();
+    if config.check_tzdata {
+        problems.extend(tzdata::mismatches(config));
+    }
+    section(output, &mut problems, |output| {
+        write_projections(output, resolved, now, clocks)
+    });
+    write_relative_menu(output, config, now);
+    if config.calendar {
+        section(output, &mut problems, |output| {
+            calendar::write_submenu(output, config, now)
+        });
+    }
+    // Speaking out loud is suppressed during Focus, like notifications
+    if config.speak && !focus {
+        let _ = writeln!(
+            output,
+            "Speak times{}",
+            callback::command_params(config, &["say"])
+        );
+    }
+    if config.paste_meeting {
+        let _ = writeln!(
+            output,
+            "Convert time from clipboard{}",
+            callback::command_params(config, &["paste-meeting"])
+        );
+    }
+    // The picker edits the file, so only for local configs
+    if let Some(source) = config.source.as_deref()
+        && !remote::is_url(source)
+    {
+        let _ = writeln!(
+            output,
+            "Add city…{}",
+            callback::terminal_params(config, &["pick"])
+        );
+    }
+    write_remote_sources(output, config);
+    let mut separated = false;
+    for (index, extra) in config.extra_lines.iter().enumerate() {
+        let before = output.len();
+        section(output, &mut problems, |output| {
+            write_extra_lines(output, config, index, extra, separated)
+        });
+        separated |= output.len() > before;
+    }
+    write_problems(output, config, &problems);
+}
+
+/// Run an optional section, removing whatever it wrote if it fails
+fn section(
+    output: &mut String,
+    problems: &mut Vec<String>,
+    write: impl FnOnce(&mut String) -> Result<(), String>,
+) {
+    let start = output.len();
+    if let Err(err) = write(output) {
+        output.truncate(start);
+        problems.push(err);
+    }
+}
+
+/// `🌙 Quiet hours until 07:00 · 2 held back`, with the notifications held
+/// back in a submenu
+fn write_quiet_hours(output: &mut String, config: &Config, quiet: &QuietHours) {
+    let deferred = &config.state.deferred;
+    let _ = write!(output, "🌙 Quiet hours until {}", quiet.end.format("%H:%M"));
+    if !deferred.is_empty() {
+        let _ = write!(output, " · {} held back", deferred.len());
+    }
+    output.push_str(" | color=gray\n");
+    for notification in deferred {
+        let _ = writeln!(
+            output,
+            "--{} · {}",
+            notification.title.replace('|', "¦"),
+            notification.at.with_timezone(&Local).format("%H:%M")
+        );
+    }
+}
+
+/// `⚠️ London shifts to BST in 3 days`, with the exact moment in local time
+/// in a submenu
+fn write_dst_warnings(
+    output: &mut String,
+    config: &Config,
+    now: DateTime<Local>,
+    changes: &[dst::Change],
+) {
+    if changes.is_empty() {
+        return;
+    }
+    // Zones changing together to the same abbreviation (London and Dublin)
+    // share a line
+    let mut lines: Vec<(&dst::Change, String, Vec<&str>)> = Vec::new();
+    for change in changes {
+        let abbreviation = change.abbreviation();
+        let names = config
+            .cities
+            .iter()
+            .filter(|city| config.timezone(&city.timezone).ok() == Some(change.tz))
+            .map(|city| city.name.as_str());
+        match lines
+            .iter_mut()
+            .find(|(seen, a, _)| seen.at == change.at && *a == abbreviation)
+        {
+            Some((.., all)) => all.extend(names),
+            None => lines.push((change, abbreviation, names.collect())),
+        }
+    }
+
+    output.push_str("---\n");
+    for (change, abbreviation, names) in lines {
+        let local = change.at.with_timezone(&Local);
+        let when = match (local.date_naive() - now.date_naive()).num_days() {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            days => format!("in {} days", days),
+        };
+        if !config.accessibility {
+            output.push_str("\u{26A0}\u{FE0F} "); // warning sign
+        }
+        let _ = writeln!(
+            output,
+            "{} {} to {} {} | color=orange",
+            names.join(", "),
+            if names.len() == 1 { "shifts" } else { "shift" },
+            abbreviation,
+            when
+        );
+        let direction = if change.change_secs > 0 {
+            "forward"
+        } else {
+            "back"
+        };
+        let format = match config.time_format() {
+            TimeFormat::TwelveHour => "%a %b %-d, %l:%M %p",
+            TimeFormat::TwentyFourHour => "%a %b %-d, %H:%M",
+        };
+        let mut amount = String::new();
+        write_hours(&mut amount, change.change_secs.abs());
+        let _ = writeln!(
+            output,
+            "--Clocks go {} {} on {} your time",
+            direction,
+            amount.trim_start_matches('+'),
+            local.format(format)
+        );
+    }
+}
+
+/// "⚠️ 2 problems" with what went wrong in a submenu
+fn write_problems(output: &mut String, config: &Config, problems: &[String]) {
+    if problems.is_empty() {
+        return;
+    }
+    output.push_str("---\n");
+    if !config.accessibility {
+        output.push_str("\u{26A0}\u{FE0F} "); // warning sign
+    }
+    let plural = if problems.len() == 1 { "" } else { "s" };
+    let _ = writeln!(
+        output,
+        "{} problem{} | color=orange",
+        problems.len(),
+        plural
+    );
+    for problem in problems {
+        // A stray " | " would turn the rest of the line into parameters
+        let problem = problem.replace('|', "¦").replace('\n', " ");
+        let _ = writeln!(output, "--{} | color=red", problem);
+    }
+}
+
+/// Rows such as `+6h → Tokyo 23:00 🌙, SF 07:00`, one per `projections` entry
+fn write_projections<'a>(
+    output: &mut String,
+    resolved: &ResolvedCities<'a>,
+    now: DateTime<Local>,
+    clocks: &mut Vec<CityClock<'a>>,
+) -> Result<(), String> {
+    let config = resolved.config();
+    if config.projections.is_empty() {
+        return Ok(());
+    }
+    output.push_str("---\n");
+    for projection in &config.projections {
+        let delta = duration::parse_duration(projection)
+            .map_err(|err| format!("invalid projection: {}", err))?;
+
+        let projection = projection.trim();
+        if !projection.starts_with(['+', '-']) {
+            output.push('+');
+        }
+        output.push_str(projection);
+        output.push_str(" →");
+        resolved.clocks_into(now + delta, clocks);
+        for (i, clock) in clocks.iter().enumerate() {
+            output.push_str(if i == 0 { " " } else { ", " });
+            output.push_str(&clock.city.name);
+            output.push(' ');
+            clock.write_time(output);
+            if !clock.is_daytime() {
+                output.push_str(if config.accessibility {
+                    " night"
+                } else {
+                    " \u{1F319}" // crescent moon
+                });
+            }
+        }
+        output.push('\n');
+    }
+    Ok(())
+}
+
+/// "What time is it for…" submenu, picking the city all times are shown relative to
+fn write_relative_menu(output: &mut String, config: &Config, now: DateTime<Local>) {
+    // Picking from a whole company roster isn't practical, so rosters go without
+    if config.cities.len() > LARGE_LIST {
+        return;
+    }
+    let relative_to = config.state.relative_to.as_deref();
+    output.push_str("---\nWhat time is it for…\n");
+    // Cities outside their active range are left out, like from the list
+    let active = config.cities.iter().filter(|city| {
+        config
+            .timezone(&city.timezone)
+            .is_ok_and(|tz| city.is_active(now.with_timezone(&tz).date_naive()))
+    });
+    for city in active {
+        let _ = write!(output, "--{}", city.name);
+        output.push_str(&callback::params(config, &["relative-to", &city.name]));
+        if Some(&*city.name) == relative_to {
+            output.push_str(" checked=true");
+        }
+        output.push('\n');
+    }
+    if relative_to.is_some() {
+        let label = match config.cities.iter().any(|c| c.role == Some(Role::Home)) {
+            true => "Back to home",
+            false => "Back to local time",
+        };
+        let _ = writeln!(
+            output,
+            "-----\n--{}{}",
+            label,
+            callback::params(config, &["relative-to"])
+        );
+    }
+}
+
+/// Fold cities that currently show the same time into the first of them.
+/// Cities with their own status (out of office, `exec` output, contact
+/// tracking, a second location) keep their line.
+fn merge_same_offset(clocks: &mut Vec<CityClock>, group_by_role: bool) {
+    let mergeable = |clock: &CityClock| {
+        clock.city.exec.is_none()
+            && !clock.city.track_contact
+            && !clock.is_ooo()
+            && clock.second.is_none()
+    };
+    // Index of the line each (group, offset, preset, format) was first seen on; there
+    // are only a few distinct offsets, so a linear scan is cheap
+    let mut lines: Vec<(_, usize)> = Vec::new();
+    let mut kept = 0;
+    for index in 0..clocks.len() {
+        let clock = &clocks[index];
+        let group = if group_by_role { clock.group() } else { 0 };
+        let key = (group, clock.utc_offset_secs, clock.preset, clock.format);
+        if mergeable(clock)
+            && let Some(&(_, line)) = lines.iter().find(|(seen, _)| *seen == key)
+        {
+            let city = clock.city;
+            clocks[line].merged.push(city);
+            continue;
+        }
+        if mergeable(clock) {
+            lines.push((key, kept));
+        }
+        clocks.swap(kept, index);
+        kept += 1;
+    }
+    clocks.truncate(kept);
+}
+
+/// Hours listed in each city's "Next hours" submenu
+const HOUR_BY_HOUR: u32 = 12;
+const HOUR_BY_HOUR_FONT: &str = " | font=Menlo size=12";
+/// Cities shown inline and per submenu page when the list is long
+const PAGE_SIZE: usize = 25;
+/// Number of cities above which the list is collapsed even without max_visible
+const LARGE_LIST: usize = 100;
+/// Rough length of a city line, to reserve the output up front
+const ROW_CAPACITY: usize = 48;
+/// Letters A-Z, and one bucket for names starting with anything else
+const LETTER_BUCKETS: usize = 27;
+
+fn letter_bucket(name: &str) -> usize {
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => usize::from(c.to_ascii_uppercase() as u8 - b'A'),
+        _ => LETTER_BUCKETS - 1,
+    }
+}
+
+/// Overflow cities in submenus by first letter, split into pages of PAGE_SIZE.
+/// A counting sort keeps it linear and the configured order within a letter.
+fn write_letter_groups(output: &mut String, config: &Config, clocks: &[CityClock]) {
+    let mut starts = [0usize; LETTER_BUCKETS + 1];
+    for clock in clocks {
+        starts[letter_bucket(&clock.city.name) + 1] += 1;
+    }
+    for bucket in 0..LETTER_BUCKETS {
+        starts[bucket + 1] += starts[bucket];
+    }
+    let mut order = vec![0; clocks.len()];
+    let mut next = starts;
+    for (index, clock) in clocks.iter().enumerate() {
+        let bucket = letter_bucket(&clock.city.name);
+        order[next[bucket]] = index;
+        next[bucket] += 1;
+    }
+
+    for bucket in 0..LETTER_BUCKETS {
+        let members = &order[starts[bucket]..starts[bucket + 1]];
+        let label = if bucket < LETTER_BUCKETS - 1 {
+            char::from(b'A' + bucket as u8)
+        } else {
+            '#'
+        };
+        let pages = members.len().div_ceil(PAGE_SIZE);
+        for (page, chunk) in members.chunks(PAGE_SIZE).enumerate() {
+            if pages > 1 {
+                let _ = writeln!(output, "--{} ({}/{})", label, page + 1, pages);
+            } else {
+                let _ = writeln!(output, "--{}", label);
+            }
+            for &index in chunk {
+                write_city_row(output, config, &clocks[index], 2);
+            }
+        }
+    }
+}
+
+/// One city line with its links as a submenu, `depth` levels deep in the menu.
+/// In accessibility mode everything is spelled out in words, see `write_plain_row`.
+fn write_city_row(output: &mut String, config: &Config, clock: &CityClock, depth: usize) {
+    let city = clock.city;
+    let contacted = config
+        .state
+        .contacted
+        .get(&city.name)
+        .filter(|_| city.track_contact);
+    for _ in 0..depth {
+        output.push_str("--");
+    }
+    if config.accessibility {
+        write_plain_row(output, config, clock);
+        if let Some(contacted) = contacted {
+            let ago = clock.time.with_timezone(&Utc) - *contacted;
+            let _ = write!(
+                output,
+                ", last contacted {}",
+                duration::format_ago_words(ago)
+            );
+        }
+        for note in clock.etiquette() {
+            let _ = write!(output, ", {}", note);
+        }
+    } else {
+        // SF Symbols are an image parameter, set with the others below
+        match (city.icon.as_deref(), city.role) {
+            (Some(icon), _) if icon.is_empty() || icon.starts_with("sf:") => {}
+            (Some(icon), _) => {
+                output.push_str(icon);
+                output.push(' ');
+            }
+            (None, Some(role)) => {
+                output.push_str(role.icon());
+                output.push(' ');
+            }
+            (None, None) => {}
+        }
+        if let Some(flag) = clock.flag(config) {
+            output.push_str(&flag);
+            output.push(' ');
+        }
+        clock.write_time(output);
+        output.push(' ');
+        output.push_str(&city.name);
+        for other in &clock.merged {
+            output.push_str(" / ");
+            output.push_str(&other.name);
+        }
+        write_diff(output, clock.diff_secs);
+        write_day_offset(output, config.day_offset, clock);
+        if let (Some(second), Some(time), Some(diff_secs)) =
+            (&city.second, &clock.second, clock.second_diff_secs())
+        {
+            output.push_str(" / ");
+            clock.write_time_of(output, time);
+            output.push(' ');
+            output.push_str(&second.name);
+            write_diff(output, diff_secs);
+        }
+        if shows_offset(config, clock) {
+            write_offset_column(output, clock);
+        }
+
+        if let Some(until) = city.ooo_until.filter(|_| clock.is_ooo()) {
+            let _ = write!(output, " OOO until {}", until.format("%b %-d"));
+        }
+        if let Some(exec) = clock.exec_output() {
+            output.push_str(" · ");
+            output.push_str(&exec);
+            if let Some(age) = clock.stale_exec_age() {
+                let _ = write!(output, " · updated {}", duration::format_ago(ago(age)));
+            }
+        }
+        if let Some(contacted) = contacted {
+            let ago = clock.time.with_timezone(&Utc) - *contacted;
+            let _ = write!(output, " · last sync {}", duration::format_ago(ago));
+        }
+        for note in clock.etiquette() {
+            let _ = write!(output, " · {}", note);
+        }
+    }
+    let mut params = Params::default();
+    for style in [&config.style, &city.style].into_iter().flatten() {
+        params.style(style);
+    }
+    if let Some(symbol) = city
+        .icon
+        .as_deref()
+        .and_then(|icon| icon.strip_prefix("sf:"))
+        && !config.accessibility
+    {
+        params.set("sfimage", symbol);
+    }
+    // Status colors win over styled ones
+    if clock.is_ooo() {
+        params.set("color", "gray");
+    } else if config.color_by_hours {
+        params.set("color", clock.availability().color());
+    }
+    params.write(output);
+    output.push('\n');
+
+    let links = clock.merged.iter().flat_map(|other| &other.links);
+    for link in city.links.iter().chain(links) {
+        for _ in 0..=depth {
+            output.push_str("--");
+        }
+        let _ = writeln!(output, "{} | href={}", link.title, link.href);
+    }
+    // Like the relative menu, rosters go without: they would triple the menu
+    let copy_formats = [
+        ("Copy ISO time", "iso"),
+        ("Copy RFC 3339", "rfc3339"),
+        ("Copy epoch", "epoch"),
+    ];
+    let copy_formats = match config.cities.len() {
+        ..=LARGE_LIST => &copy_formats[..],
+        _ => &[],
+    };
+    for (label, format) in copy_formats {
+        for _ in 0..=depth {
+            output.push_str("--");
+        }
+        output.push_str(label);
+        output.push_str(&callback::params(
+            config,
+            &["copy", &city.name, "--format", format],
+        ));
+        output.push('\n');
+    }
+    if config.cities.len() <= LARGE_LIST && clock.diff_secs != 0 {
+        write_hour_by_hour(output, config, clock, depth + 1);
+    }
+    if let Some(buffer) = config.reply_buffer
+        && clock.diff_secs != 0
+        && !clock.is_ooo()
+    {
+        for _ in 0..=depth {
+            output.push_str("--");
+        }
+        write_reply_window(output, config, clock, buffer);
+    }
+    if let Some(exec) = &city.exec {
+        for _ in 0..=depth {
+            output.push_str("--");
+        }
+        write_refresh_item(output, config, exec, city.exec_ttl, &["--city", &city.name]);
+    }
+    if city.track_contact {
+        for _ in 0..=depth {
+            output.push_str("--");
+        }
+        let _ = writeln!(
+            output,
+            "Talked to {} today{}",
+            city.name,
+            callback::params(config, &["contacted", &city.name])
+        );
+    }
+    write_edit_menu(output, config, city, depth + 1);
+    if config.midnight_detail {
+        // Shown instead of the row while Option is held
+        for _ in 0..depth {
+            output.push_str("--");
+        }
+        let (since, until) = clock.midnights();
+        if config.accessibility {
+            let _ = writeln!(
+                output,
+                "{}: {} since midnight, {} until midnight | alternate=true",
+                city.name,
+                duration::format_hm_words(since),
+                duration::format_hm_words(until)
+            );
+        } else {
+            clock.write_time(output);
+            let _ = writeln!(
+                output,
+                " {} · {} since midnight · {} to midnight | alternate=true",
+                city.name,
+                duration::format_hm(since),
+                duration::format_hm(until)
+            );
+        }
+    }
+}
+
+/// "Next 12 hours" submenu with lines like `14:00 you → 22:00 Ana`
+fn write_hour_by_hour(output: &mut String, config: &Config, clock: &CityClock, depth: usize) {
+    for _ in 0..depth {
+        output.push_str("--");
+    }
+    let _ = writeln!(output, "Next {} hours", HOUR_BY_HOUR);
+    let now = clock.time.with_timezone(&Local);
+    let tz = clock.time.timezone();
+    for (yours, theirs) in convert::hour_by_hour(config, now, tz, HOUR_BY_HOUR) {
+        for _ in 0..=depth {
+            output.push_str("--");
+        }
+        let format = match config.time_format() {
+            TimeFormat::TwelveHour => "%l:%M %p",
+            TimeFormat::TwentyFourHour => "%H:%M",
+        };
+        let _ = write!(
+            output,
+            "{} you → {} {}",
+            yours.format(format),
+            theirs.format(format),
+            clock.city.name
+        );
+        if theirs.date_naive() != yours.date_naive() {
+            let _ = write!(output, " {}", theirs.format("%a"));
+        }
+        if !(6..18).contains(&theirs.hour()) {
+            output.push_str(if config.accessibility {
+                " night"
+            } else {
+                " \u{1F319}" // crescent moon
+            });
+        }
+        output.push_str(HOUR_BY_HOUR_FONT);
+        output.push('\n');
+    }
+}
+
+/// `Everyone at work: 13:00–15:30 (2h 30m)`, with the same windows in each
+/// city's time in a submenu
+fn write_overlap(output: &mut String, config: &Config, clocks: &[CityClock], now: DateTime<Local>) {
+    let windows = overlap::windows(clocks, now.date_naive());
+    let twelve_hour = config.time_format() == TimeFormat::TwelveHour;
+    output.push_str("---\nEveryone at work: ");
+    if windows.is_empty() {
+        output.push_str("not today\n");
+        return;
+    }
+    let shown: Vec<String> = windows
+        .iter()
+        .map(|window| {
+            format!(
+                "{} ({})",
+                window.format(twelve_hour),
+                duration::format_hm(window.duration())
+            )
+        })
+        .collect();
+    let _ = writeln!(output, "{}", shown.join(", "));
+    let format = if twelve_hour { "%-l:%M %p" } else { "%H:%M" };
+    for clock in clocks {
+        if clock.is_ooo() {
+            let _ = writeln!(output, "--{} out of office | color=gray", clock.city.name);
+            continue;
+        }
+        let tz = clock.time.timezone();
+        let theirs: Vec<String> = windows
+            .iter()
+            .map(|window| {
+                format!(
+                    "{}–{}",
+                    window.start.with_timezone(&tz).format(format),
+                    window.end.with_timezone(&tz).format(format)
+                )
+            })
+            .collect();
+        let _ = writeln!(output, "--{} {}", clock.city.name, theirs.join(", "));
+    }
+}
+
+/// "Edit" submenu reordering the city in the config file. Remote configs
+/// can't be edited from here, and rosters are curated in the file anyway.
+fn write_edit_menu(output: &mut String, config: &Config, city: &CityConfig, depth: usize) {
+    let Some(source) = config.source.as_deref() else {
+        return;
+    };
+    // Moves edit the file, so they count in its cities, not the included,
+    // remote or macOS Clock ones around them
+    let own = &config.file_cities;
+    let Some(index) = own.iter().position(|name| *name == city.name) else {
+        return;
+    };
+    if remote::is_url(source) || own.len() > LARGE_LIST || own.len() < 2 {
+        return;
+    }
+    let indent = "--".repeat(depth);
+    let _ = writeln!(output, "{}Edit", indent);
+    if index > 0 {
+        let _ = writeln!(
+            output,
+            "{}--Move up{}",
+            indent,
+            callback::params(config, &["move-up", &city.name])
+        );
+    }
+    if index + 1 < own.len() {
+        let _ = writeln!(
+            output,
+            "{}--Move down{}",
+            indent,
+            callback::params(config, &["move-down", &city.name])
+        );
+    }
+}
+
+/// `Reply window closes 16:30 your time (in 2h 05m)`, or when the window has
+/// closed for today, when the next one closes
+fn write_reply_window(output: &mut String, config: &Config, clock: &CityClock, buffer: u32) {
+    let Some(deadline) = clock.reply_deadline(TimeDelta::minutes(i64::from(buffer))) else {
+        return;
+    };
+    let mine = deadline.with_timezone(&Local);
+    let mut when = String::new();
+    // Today's deadline is usually today for me too, but not always
+    if mine.date_naive() != clock.time.with_timezone(&Local).date_naive() {
+        let _ = write!(when, "{} ", mine.format("%a"));
+    }
+    let _ = match config.time_format() {
+        TimeFormat::TwelveHour => write!(when, "{}", mine.format("%-l:%M %p")),
+        TimeFormat::TwentyFourHour => write!(when, "{}", mine.format("%H:%M")),
+    };
+    if deadline.date_naive() == clock.time.date_naive() {
+        // Rounded up, so it adds up with the minutes shown on the clocks
+        let left = deadline - clock.time;
+        let _ = writeln!(
+            output,
+            "Reply window closes {} your time (in {})",
+            when,
+            duration::format_hm(left + TimeDelta::seconds(59))
+        );
+    } else {
+        let _ = writeln!(
+            output,
+            "Reply window closed, next closes {} your time",
+            when
+        );
+    }
+}
+
+/// Marker after the offset when the city is on another date than me: ` +1d`,
+/// ` Tue` or ` Tue Oct 15`
+fn write_day_offset(output: &mut String, style: DayOffset, clock: &CityClock) {
+    let days = clock.day_offset();
+    if days == 0 {
+        return;
+    }
+    let _ = match style {
+        DayOffset::Days => write!(output, " {:+}d", days),
+        DayOffset::Weekday => write!(output, " {}", clock.time.format("%a")),
+        DayOffset::Date => write!(output, " {}", clock.time.format("%a %b %-d")),
+        DayOffset::Off => Ok(()),
+    };
+}
+
+/// City line that reads as a sentence under VoiceOver, with every icon
+/// replaced by words: "Gdansk, home: 14:05, 2 hours ahead, night"
+fn write_plain_row(output: &mut String, config: &Config, clock: &CityClock) {
+    let city = clock.city;
+    output.push_str(&city.name);
+    for other in &clock.merged {
+        output.push_str(" and ");
+        output.push_str(&other.name);
+    }
+    if let Some(role) = city.role {
+        output.push_str(", ");
+        output.push_str(role.label());
+    }
+    output.push_str(": ");
+    clock.write_time(output);
+    output.push_str(", ");
+    write_diff_words(output, clock.diff_secs);
+    if let (Some(second), Some(time), Some(diff_secs)) =
+        (&city.second, &clock.second, clock.second_diff_secs())
+    {
+        let _ = write!(output, "; {}: ", second.name);
+        clock.write_time_of(output, time);
+        output.push_str(", ");
+        write_diff_words(output, diff_secs);
+    }
+    if shows_offset(config, clock) {
+        let _ = write!(output, ", {}, ", format_utc_offset(clock.utc_offset_secs));
+        match clock.local_diff_secs() {
+            0 => output.push_str("same time as local"),
+            diff_secs => {
+                write_diff_words(output, diff_secs);
+                output.push_str(" of local time");
+            }
+        }
+    }
+    match clock.day_offset() {
+        _ if config.day_offset == DayOffset::Off => {}
+        0 => {}
+        1 => output.push_str(", tomorrow"),
+        -1 => output.push_str(", yesterday"),
+        _ => {
+            let _ = write!(output, ", on {}", clock.time.format("%A, %B %-d"));
+        }
+    }
+
+    if !clock.is_daytime() {
+        output.push_str(", night");
+    }
+    if clock.is_dst() {
+        output.push_str(", daylight saving time");
+    }
+    if let Some(until) = city.ooo_until.filter(|_| clock.is_ooo()) {
+        let _ = write!(output, ", out of office until {}", until.format("%B %-d"));
+    }
+    if let Some(exec) = clock.exec_output() {
+        output.push_str(", ");
+        output.push_str(&exec);
+        if let Some(age) = clock.stale_exec_age() {
+            let _ = write!(output, ", updated {}", duration::format_ago_words(ago(age)));
+        }
+    }
+}
+
+/// Offset spelled out, like "same time" or "5 hours 30 minutes ahead"
+fn write_diff_words(output: &mut String, diff_secs: i32) {
+    if diff_secs == 0 {
+        output.push_str("same time");
+        return;
+    }
+    let minutes = diff_secs.unsigned_abs() / 60;
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+    let plural = |n: u32| if n == 1 { "" } else { "s" };
+    if hours > 0 {
+        let _ = write!(output, "{} hour{}", hours, plural(hours));
+    }
+    if minutes > 0 {
+        if hours > 0 {
+            output.push(' ');
+        }
+        let _ = write!(output, "{} minute{}", minutes, plural(minutes));
+    }
+    output.push_str(if diff_secs > 0 { " ahead" } else { " behind" });
+}
+
+/// "Refresh now · updated 12m ago, next in 3m", rerunning one hook when clicked
+fn write_refresh_item(
+    output: &mut String,
+    config: &Config,
+    command: &str,
+    ttl: Option<u64>,
+    section: &[&str],
+) {
+    output.push_str("Refresh now");
+    if let Some(age) = hooks::age(command) {
+        let _ = write!(output, " · updated {}", duration::format_ago(ago(age)));
+        if let Some(left) = hooks::ttl(ttl)
+            .checked_sub(age)
+            .filter(|left| !left.is_zero())
+        {
+            let _ = write!(output, ", next in {}m", left.as_millis().div_ceil(60_000));
+        }
+    }
+    let mut args = vec!["refresh"];
+    args.extend(section);
+    output.push_str(&callback::params(config, &args));
+    output.push('\n');
+}
+
+/// Remote configs with the time each was last fetched, refreshed one by one
+fn write_remote_sources(output: &mut String, config: &Config) {
+    let source = config
+        .source
+        .as_deref()
+        .filter(|source| remote::is_url(source));
+    let urls: Vec<&str> = source
+        .into_iter()
+        .chain(config.remote.iter().map(String::as_str))
+        .collect();
+    if urls.is_empty() {
+        return;
+    }
+    output.push_str("---\nRemote cities\n");
+    for url in urls {
+        output.push_str("--");
+        output.push_str(url);
+        match remote::age(url) {
+            Some(age) => {
+                let _ = write!(output, " · updated {}", duration::format_ago(ago(age)));
+            }
+            None => output.push_str(" · never fetched"),
+        }
+        output.push_str(&callback::params(config, &["refresh", "--remote", url]));
+        output.push('\n');
+    }
+}
+
+/// Cache ages are measured on the wall clock, which is never before the write
+fn ago(age: std::time::Duration) -> chrono::TimeDelta {
+    chrono::TimeDelta::from_std(age).unwrap_or_default()
+}
+
+/// Output of one `extra_lines` entry, `separated` if a previous one already
+/// wrote the separator
+fn write_extra_lines(
+    output: &mut String,
+    config: &Config,
+    index: usize,
+    extra: &ExtraLinesConfig,
+    separated: bool,
+) -> Result<(), String> {
+    let lines = hooks::run_cached(&extra.exec, hooks::ttl(extra.ttl), hooks::DEFAULT_TIMEOUT)?;
+    let mut lines = lines
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .peekable();
+    if lines.peek().is_none() {
+        return Ok(());
+    }
+    if !separated {
+        output.push_str("---\n");
+    }
+    for line in lines {
+        output.push_str(line);
+        output.push('\n');
+    }
+    // Quiet footer under each section saying how old its output is
+    match hooks::age(&extra.exec) {
+        Some(age) => {
+            let _ = writeln!(
+                output,
+                "updated {} | size=11 color=gray",
+                duration::format_ago(ago(age))
+            );
+        }
+        None => output.push_str("updated now | size=11 color=gray\n"),
+    }
+    output.push_str("--");
+    let index = index.to_string();
+    write_refresh_item(output, config, &extra.exec, extra.ttl, &["--line", &index]);
+    Ok(())
+}
+
+/// Everything known about one city, as exposed to JSON output and user templates
+pub fn city_model(config: &Config, clock: &CityClock) -> Value {
+    json!({
+        "name": clock.city.name,
+        "timezone": clock.city.timezone,
+        "time": clock.time.to_rfc3339_opts(SecondsFormat::Secs, false),
+        "hhmm": format!("{:02}:{:02}", clock.time.hour(), clock.time.minute()),
+        "formatted": clock.formatted_time(),
+        "utc_offset": clock.time.format("%:z").to_string(),
+        "utc_offset_secs": clock.utc_offset_secs,
+        "diff_secs": clock.diff_secs,
+        "day_diff": clock.day_offset(),
+        "diff": format_diff(clock.diff_secs).trim().trim_matches(['(', ')']),
+        "role": clock.city.role,
+        "role_icon": clock.city.role.map(Role::icon),
+        "icon": clock.city.icon,
+        "flag": clock.flag(config),
+        "ooo": clock.is_ooo(),
+        "ooo_until": clock.city.ooo_until,
+        "is_dst": clock.is_dst(),
+        "is_working_hours": clock.is_working_hours(),
+        "availability": clock.availability().label(),
+        "is_daytime": clock.is_daytime(),
+        "comfort": clock.comfort(),
+        "links": clock.city.links,
+        "exec": clock.exec_output(),
+        "etiquette": clock.etiquette().collect::<Vec<_>>(),
+        "second": clock.city.second.as_ref().zip(clock.second).map(|(second, time)| json!({
+            "name": second.name,
+            "timezone": second.timezone,
+            "time": time.to_rfc3339_opts(SecondsFormat::Secs, false),
+        })),
+    })
+}
+
+/// The whole menu as data: local time, menu bar icon, header lines and cities
+pub fn render_json(config: &Config, now: DateTime<Local>) -> Value {
+    let cities: Vec<Value> = city_clocks(config, now)
+        .iter()
+        .map(|clock| city_model(config, clock))
+        .collect();
+    let headers: Vec<String> = config
+        .headers
+        .iter()
+        .map(|template| {
+            let mut line = String::new();
+            headers::expand(template, now, &mut line);
+            line
+        })
+        .collect();
+
+    json!({
+        "local": now.to_rfc3339_opts(SecondsFormat::Secs, false),
+        "icon": get_accurate_clock_icon(now.hour(), now.minute()),
+        "headers": headers,
+        "focus": focus::is_active(config),
+        "cities": cities,
+    })
+}
+
+/// Home Assistant sensor payloads keyed by city slug
+///
+/// Meant for a `command_line` sensor, e.g. `value_template: "{{ value_json.tokyo.state }}"`
+/// and `json_attributes_path: "$.tokyo.attributes"`.
+pub fn render_hass(config: &Config, now: DateTime<Local>) -> Value {
+    let mut sensors = serde_json::Map::new();
+    for clock in city_clocks(config, now) {
+        sensors.insert(
+            slug(&clock.city.name),
+            json!({
+                "state": clock.formatted_time(),
+                "attributes": {
+                    "friendly_name": clock.city.name,
+                    "timezone": clock.city.timezone,
+                    "time": clock.time.to_rfc3339_opts(SecondsFormat::Secs, false),
+                    "utc_offset": clock.time.format("%:z").to_string(),
+                    "offset_hours": clock.diff_secs as f64 / 3600.0,
+                    "is_dst": clock.is_dst(),
+                    "is_working_hours": clock.is_working_hours(),
+                },
+            }),
+        );
+    }
+    Value::Object(sensors)
+}
+
+/// Cities matching `name` (case-insensitive), or all of them if `name` is `None`
+pub fn select_clocks<'a>(
+    config: &'a Config,
+    now: DateTime<Local>,
+    name: Option<&str>,
+) -> Result<Vec<CityClock<'a>>, String> {
+    let clocks = city_clocks(config, now);
+    match name {
+        None => Ok(clocks),
+        Some(name) => {
+            let selected: Vec<CityClock> = clocks
+                .into_iter()
+                .filter(|clock| clock.city.name.eq_ignore_ascii_case(name))
+                .collect();
+            if selected.is_empty() {
+                Err(format!("no configured city named '{}'", name))
+            } else {
+                Ok(selected)
+            }
+        }
+    }
+}
+
+/// Plain lines such as `21:46 in Tokyo`, suitable for Shortcuts to speak or display
+pub fn render_shortcuts(
+    config: &Config,
+    now: DateTime<Local>,
+    city: Option<&str>,
+) -> Result<String, String> {
+    let mut output = String::new();
+    for clock in select_clocks(config, now, city)? {
+        output.push_str(&format!(
+            "{} in {}\n",
+            clock.formatted_time(),
+            clock.city.name
+        ));
+    }
+    Ok(output)
+}
+
+/// The one-line JSON object of a Waybar custom module: the clock icon and
+/// local time (or `city`'s time) as the text, every city in the tooltip
+pub fn render_waybar(
+    config: &Config,
+    now: DateTime<Local>,
+    city: Option<&str>,
+) -> Result<Value, String> {
+    let mut tooltip = String::new();
+    for clock in city_clocks(config, now) {
+        if !tooltip.is_empty() {
+            tooltip.push('\n');
+        }
+        clock.write_time(&mut tooltip);
+        tooltip.push(' ');
+        tooltip.push_str(&clock.city.name);
+        write_diff(&mut tooltip, clock.diff_secs);
+    }
+    let icon = get_accurate_clock_icon(now.hour(), now.minute());
+    Ok(match city {
+        Some(city) => {
+            let clock = select_clocks(config, now, Some(city))?.remove(0);
+            json!({
+                "text": format!("{} {} {}", icon, clock.formatted_time(), clock.city.name),
+                "tooltip": tooltip,
+                // For styling the module by the city's hours
+                "class": clock.availability().label(),
+            })
+        }
+        None => json!({
+            "text": format!("{} {:02}:{:02}", icon, now.hour(), now.minute()),
+            "tooltip": tooltip,
+        }),
+    })
+}
+
+/// One dictionary per city keyed by city name, for the Shortcuts "Get Dictionary" action
+pub fn render_shortcuts_dict(
+    config: &Config,
+    now: DateTime<Local>,
+    city: Option<&str>,
+) -> Result<Value, String> {
+    let dict = |clock: &CityClock| {
+        json!({
+            "name": clock.city.name,
+            "timezone": clock.city.timezone,
+            "time": clock.formatted_time(),
+            "date": clock.time.format("%Y-%m-%d").to_string(),
+            "weekday": clock.time.format("%A").to_string(),
+            "iso": clock.time.to_rfc3339_opts(SecondsFormat::Secs, false),
+            "utc_offset": clock.time.format("%:z").to_string(),
+            "offset_hours": clock.diff_secs as f64 / 3600.0,
+        })
+    };
+
+    let clocks = select_clocks(config, now, city)?;
+    if city.is_some() {
+        return Ok(dict(&clocks[0]));
+    }
+    let mut all = serde_json::Map::new();
+    for clock in &clocks {
+        all.insert(clock.city.name.clone(), dict(clock));
+    }
+    Ok(Value::Object(all))
+}
+
+/// Aligned plain-text table of all cities for terminals, rendered into `output`.
+/// With `color`, the date is bold, times are green, yellow or red by working hours and cities
+/// out of office are dimmed.
+pub fn render_table_into<'a>(
+    output: &mut String,
+    resolved: &ResolvedCities<'a>,
+    now: DateTime<Local>,
+    clocks: &mut Vec<CityClock<'a>>,
+    color: bool,
+) {
+    output.clear();
+    resolved.clocks_into(now, clocks);
+    let name_width = clocks
+        .iter()
+        .map(|clock| clock.city.name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let (bold, reset) = if color {
+        (color::BOLD, color::RESET)
+    } else {
+        ("", "")
+    };
+    let _ = write!(
+        output,
+        "{}{}{}\n\n",
+        bold,
+        now.format("%a %Y-%m-%d %H:%M:%S %Z"),
+        reset
+    );
+    for clock in clocks.iter() {
+        let ooo = color && clock.is_ooo();
+        if ooo {
+            output.push_str(color::DIM);
+        }
+        let _ = write!(output, "{:<width$}  ", clock.city.name, width = name_width);
+        if color && !ooo {
+            output.push_str(clock.availability().ansi());
+        }
+        // The terminal clock shows seconds unless a format is configured
+        if clock.has_format() {
+            clock.write_time(output);
+        } else {
+            let _ = write!(output, "{}", clock.time.format("%H:%M:%S"));
+        }
+        if color && !ooo {
+            output.push_str(color::RESET);
+        }
+        let _ = write!(output, "  {}", clock.time.format("%a"));
+        if clock.diff_secs != 0 {
+            output.push(' ');
+            write_diff(output, clock.diff_secs);
+        }
+        if ooo {
+            output.push_str(color::RESET);
+        }
+        output.push('\n');
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Standalone page for a wall-mounted dashboard, reloading itself every 30 seconds
+pub fn render_html(config: &Config, now: DateTime<Local>) -> String {
+    let mut rows = String::new();
+    for clock in city_clocks(config, now) {
+        let class = if clock.is_ooo() { " class=\"ooo\"" } else { "" };
+        rows.push_str(&format!(
+            "<tr{}><td class=\"time\">{}</td><td>{}</td><td>{}</td></tr>\n",
+            class,
+            clock.formatted_time(),
+            escape_html(&clock.city.name),
+            escape_html(format_diff(clock.diff_secs).trim()),
+        ));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta http-equiv="refresh" content="30">
+<title>Clocks</title>
+<style>
+body {{ font-family: -apple-system, sans-serif; background: #111; color: #eee; }}
+td {{ padding: 0.2em 0.8em; font-size: 2em; }}
+.time {{ font-family: Menlo, monospace; }}
+.ooo {{ color: #777; }}
+</style>
+</head>
+<body>
+<p>{}</p>
+<table>
+{}</table>
+</body>
+</html>
+"#,
+        escape_html(&now.to_rfc2822()),
+        rows
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use chrono::Utc;
+
+    /// A company-wide roster with `n` people spread over all timezones
+    fn roster(n: usize) -> Config {
+        let names = ["Ada", "bob", "Chen", "Émile", "Zoë", "42ops", "Mira"];
+        Config {
+            cities: (0..n)
+                .map(|i| CityConfig {
+                    name: format!("{} {}", names[i % names.len()], i),
+                    timezone: chrono_tz::TZ_VARIANTS[i % chrono_tz::TZ_VARIANTS.len()]
+                        .name()
+                        .to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            group_by_role: false,
+            ..Default::default()
+        }
+    }
+
+    /// City lines of a menu with their submenu depth
+    fn city_rows(output: &str) -> Vec<(usize, &str)> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let row = line.trim_start_matches('-');
+                let depth = (line.len() - row.len()) / 2;
+                let b = row.as_bytes();
+                // Hour-by-hour lines start with a time too
+                let is_time =
+                    b.len() > 6 && b[2] == b':' && b[5] == b' ' && !row.contains(" you → ");
+                (is_time && b[..2].iter().chain(&b[3..5]).all(u8::is_ascii_digit))
+                    .then_some((depth, row))
+            })
+            .collect()
+    }
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).unwrap()
+    }
+
+    fn city(name: &str, timezone: &str) -> CityConfig {
+        CityConfig {
+            name: name.to_string(),
+            timezone: timezone.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Cities in zones with 30 and 45 minute offsets, relative to a UTC home
+    fn fractional_zones() -> Config {
+        let mut home = city("Home", "UTC");
+        home.role = Some(Role::Home);
+        Config {
+            cities: vec![
+                home,
+                city("Mumbai", "Asia/Kolkata"),
+                city("Kathmandu", "Asia/Kathmandu"),
+                city("Chatham", "Pacific/Chatham"),
+                city("St. John's", "America/St_Johns"),
+                city("Marquesas", "Pacific/Marquesas"),
+                city("Eucla", "Australia/Eucla"),
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// 2024-01-15 12:00 UTC: southern summer, northern winter
+    fn winter() -> DateTime<Local> {
+        Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Local)
+    }
+
+    #[test]
+    fn diff_keeps_minutes_of_fractional_offsets() {
+        assert_eq!(format_diff(0), "");
+        assert_eq!(format_diff(2 * 3600), " (+2h)");
+        assert_eq!(format_diff(-7 * 3600), " (-7h)");
+        assert_eq!(format_diff(5 * 3600 + 30 * 60), " (+5:30)");
+        assert_eq!(format_diff(5 * 3600 + 45 * 60), " (+5:45)");
+        assert_eq!(format_diff(-(3 * 3600 + 30 * 60)), " (-3:30)");
+        assert_eq!(format_diff(-(9 * 3600 + 30 * 60)), " (-9:30)");
+        assert_eq!(format_diff(30 * 60), " (+0:30)");
+        assert_eq!(format_diff(-45 * 60), " (-0:45)");
+    }
+
+    #[test]
+    fn utc_offset_keeps_minutes_of_fractional_offsets() {
+        assert_eq!(format_utc_offset(0), "UTC+0");
+        assert_eq!(format_utc_offset(5 * 3600 + 45 * 60), "UTC+5:45");
+        assert_eq!(format_utc_offset(13 * 3600 + 45 * 60), "UTC+13:45");
+        assert_eq!(format_utc_offset(-(9 * 3600 + 30 * 60)), "UTC-9:30");
+        assert_eq!(format_utc_offset(-30 * 60), "UTC-0:30");
+    }
+
+    #[test]
+    fn diff_words_keep_minutes_of_fractional_offsets() {
+        let words = |secs| {
+            let mut output = String::new();
+            write_diff_words(&mut output, secs);
+            output
+        };
+        assert_eq!(words(5 * 3600 + 45 * 60), "5 hours 45 minutes ahead");
+        assert_eq!(words(-30 * 60), "30 minutes behind");
+        assert_eq!(words(3600), "1 hour ahead");
+    }
+
+    #[test]
+    fn fractional_zones_relative_to_home() {
+        let config = fractional_zones();
+        let clocks = city_clocks(&config, winter());
+        let diffs: Vec<(&str, String, String)> = clocks
+            .iter()
+            .map(|clock| {
+                (
+                    clock.city.name.as_str(),
+                    clock.formatted_time(),
+                    format_diff(clock.diff_secs),
+                )
+            })
+            .collect();
+        assert_eq!(
+            diffs,
+            [
+                ("Home", "12:00".to_string(), "".to_string()),
+                ("Mumbai", "17:30".to_string(), " (+5:30)".to_string()),
+                ("Kathmandu", "17:45".to_string(), " (+5:45)".to_string()),
+                // Chatham observes DST in January, +13:45
+                ("Chatham", "01:45".to_string(), " (+13:45)".to_string()),
+                ("St. John's", "08:30".to_string(), " (-3:30)".to_string()),
+                ("Marquesas", "02:30".to_string(), " (-9:30)".to_string()),
+                ("Eucla", "20:45".to_string(), " (+8:45)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn fractional_zones_relative_to_each_other() {
+        let mut config = fractional_zones();
+        config.state.relative_to = Some("Kathmandu".to_string());
+        let clocks = city_clocks(&config, winter());
+        let diff = |name: &str| {
+            let clock = clocks.iter().find(|c| c.city.name == name).unwrap();
+            format_diff(clock.diff_secs)
+        };
+        assert_eq!(diff("Kathmandu"), "");
+        assert_eq!(diff("Mumbai"), " (-0:15)");
+        assert_eq!(diff("Home"), " (-5:45)");
+        assert_eq!(diff("Chatham"), " (+8h)");
+        assert_eq!(diff("Eucla"), " (+3h)");
+    }
+
+    #[test]
+    fn fractional_zones_in_menu_and_rules() {
+        let mut config = fractional_zones();
+        config.cities[2].show_if = Some("offset_hours > 5.5 and offset_hours < 6".to_string());
+        config.cities[1].show_if = Some("offset_hours > 5.5".to_string());
+        let output = render_swiftbar(&config, winter());
+        assert!(output.contains("\n17:45 Kathmandu (+5:45)\n"));
+        assert!(!output.contains("17:30 Mumbai"));
+        assert!(output.contains("\n08:30 St. John's (-3:30)\n"));
+    }
+
+    #[test]
+    fn fractional_zones_merge_only_with_equal_offsets() {
+        let mut config = fractional_zones();
+        config.cities.push(city("Delhi", "Asia/Kolkata"));
+        config.cities.push(city("Pokhara", "Asia/Kathmandu"));
+        config.merge_same_offset = true;
+        let output = render_swiftbar(&config, winter());
+        assert!(output.contains("\n17:30 Mumbai / Delhi (+5:30)\n"));
+        assert!(output.contains("\n17:45 Kathmandu / Pokhara (+5:45)\n"));
+    }
+
+    #[test]
+    fn fractional_local_time_picks_nearest_clock_face() {
+        // 17:45 rounds up to six o'clock, 17:30 is half past five
+        assert_eq!(get_accurate_clock_icon(17, 45), crate::CLOCK_0600);
+        assert_eq!(get_accurate_clock_icon(17, 30), crate::CLOCK_0530);
+        assert_eq!(get_accurate_clock_icon(8, 14), crate::CLOCK_0800);
+    }
+
+    #[test]
+    fn failing_section_leaves_nothing_behind() {
+        let mut output = String::from("kept\n");
+        let mut problems = Vec::new();
+        section(&mut output, &mut problems, |output| {
+            output.push_str("half-written\n");
+            Err("broken".to_string())
+        });
+        section(&mut output, &mut problems, |output| {
+            output.push_str("written\n");
+            Ok(())
+        });
+        assert_eq!(output, "kept\nwritten\n");
+        assert_eq!(problems, ["broken"]);
+    }
+
+    #[test]
+    fn healthy_menu_has_no_problems() {
+        let output = render_swiftbar(&fractional_zones(), winter());
+        assert!(!output.contains("problem"));
+    }
+
+    #[test]
+    fn one_bad_city_does_not_hide_the_others() {
+        let mut config = fractional_zones();
+        config
+            .cities
+            .insert(2, city("Atlantis", "Atlantic/Atlantis"));
+        config.cities[1].show_if = Some("hour >".to_string());
+        let output = render_swiftbar(&config, winter());
+
+        // The invalid rule is ignored, the invalid timezone skips only its city
+        // Rows other than the home one, which starts with its role icon
+        assert_eq!(city_rows(&output).len(), 6);
+        assert!(output.contains("\n17:30 Mumbai (+5:30)\n"));
+        assert!(output.contains("\n17:45 Kathmandu (+5:45)\n"));
+        assert!(output.contains("\n\u{26A0}\u{FE0F} 2 problems | color=orange\n"));
+        assert!(output.contains("\n--Mumbai: invalid show_if: "));
+        assert!(
+            output.contains("\n--Atlantis: unknown timezone 'Atlantic/Atlantis' | color=red\n")
+        );
+    }
+
+    #[test]
+    fn failing_optional_sections_are_reported() {
+        let mut config = fractional_zones();
+        config.projections = vec!["+2h".to_string(), "soonish".to_string()];
+        config.calendar = true;
+        let output = render_swiftbar(&config, winter());
+
+        assert_eq!(city_rows(&output).len(), 6);
+        assert!(
+            !output.contains("\n+2h →"),
+            "partial projections left behind"
+        );
+        assert!(output.contains("\nJanuary 2024\n"));
+        assert!(output.contains("\nWhat time is it for…\n"));
+        assert!(output.contains(" 1 problem | color=orange\n--invalid projection: "));
+    }
+
+    #[test]
+    fn failing_extra_lines_keep_the_others() {
+        let mut config = fractional_zones();
+        config.extra_lines = vec![
+            ExtraLinesConfig {
+                exec: "echo 'half written'; exit 3".to_string(),
+                ttl: None,
+            },
+            ExtraLinesConfig {
+                exec: "echo 'Battery 80%'".to_string(),
+                ttl: None,
+            },
+        ];
+        let output = render_swiftbar(&config, winter());
+
+        assert!(!output.contains("\nhalf written\n"));
+        assert!(output.contains("---\nBattery 80%\n"));
+        assert_eq!(output.matches("---\nBattery").count(), 1);
+        assert!(output.contains("--command 'echo 'half written'; exit 3' failed: exited with"));
+    }
+
+    #[test]
+    fn small_lists_stay_inline() {
+        let output = render_swiftbar(&roster(LARGE_LIST), now());
+        assert!(!output.contains("More cities"));
+        assert!(city_rows(&output).iter().all(|&(depth, _)| depth == 0));
+        assert_eq!(city_rows(&output).len(), LARGE_LIST);
+    }
+
+    #[test]
+    fn large_roster_is_paged_by_letter() {
+        let n = 5000;
+        let output = render_swiftbar(&roster(n), now());
+
+        // Every person is listed exactly once
+        let rows = city_rows(&output);
+        assert_eq!(rows.len(), n);
+        for i in [0, 1, 3, 4, 5, n - 1] {
+            let suffix = format!(" {}", i);
+            let count = rows
+                .iter()
+                .filter(|(_, row)| row.split(" (").next().unwrap().ends_with(&suffix))
+                .count();
+            assert_eq!(count, 1, "city {} listed {} times", i, count);
+        }
+
+        // No menu level grows beyond one page
+        let inline = rows.iter().filter(|&&(depth, _)| depth == 0).count();
+        assert_eq!(inline, PAGE_SIZE);
+        let mut page = 0;
+        for line in output.lines() {
+            if line.starts_with("----") {
+                page += 1;
+                assert!(page <= PAGE_SIZE, "page too long before {:?}", line);
+            } else {
+                page = 0;
+            }
+        }
+
+        assert!(output.contains("\n--A (1/"));
+        assert!(output.contains("\n--B (1/"));
+        assert!(output.contains("\n--# (1/"));
+        assert!(!output.contains("\n--Y"));
+    }
+
+    #[test]
+    fn max_visible_overrides_the_automatic_limit() {
+        let mut config = roster(200);
+        config.max_visible = Some(150);
+        let output = render_swiftbar(&config, now());
+        let rows = city_rows(&output);
+        assert_eq!(rows.iter().filter(|&&(depth, _)| depth == 0).count(), 150);
+        assert!(output.contains("\n--A\n"));
+    }
+}
@@ -0,0 +1,260 @@
+//! The `show_if` rule syntax deciding whether a city is shown.
+//!
+//! A rule is a boolean expression over the city's current state:
+//!
+//! - numbers: `offset_hours` (relative to the base timezone), `utc_offset_hours`,
+//!   `hour`, `minute`, `weekday` (1 = Monday ... 7 = Sunday), `comfort` (0-100)
+//! - flags: `is_working_hours`, `is_daytime`, `is_dst`, `is_ooo`
+//! - comparisons `<`, `<=`, `>`, `>=`, `==`, `!=`, combined with
+//!   `and`/`&&`, `or`/`||`, `not`/`!` and parentheses
+//!
+//! For example `show_if: "is_working_hours or offset_hours > 3"`.
+
+use crate::render::CityClock;
+use chrono::{Datelike, Timelike};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Bool(bool),
+    Var(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Number(f64),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+const OPERATORS: &[&str] = &["<=", ">=", "==", "!=", "&&", "||", "<", ">", "!"];
+
+fn tokenize(rule: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = rule.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        if c == '(' || c == ')' {
+            tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            rest = &rest[1..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else if c.is_ascii_digit() || c == '.' || c == '-' {
+            let len = rest[1..]
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .map_or(rest.len(), |i| i + 1);
+            let number = rest[..len]
+                .parse()
+                .map_err(|_| format!("invalid number '{}'", &rest[..len]))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[len..];
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(match &rest[..len] {
+                "and" => Token::Op("&&"),
+                "or" => Token::Op("||"),
+                "not" => Token::Op("!"),
+                ident => Token::Ident(ident.to_string()),
+            });
+            rest = &rest[len..];
+        } else {
+            return Err(format!("unexpected '{}'", c));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self, op: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Op(o)) if *o == op)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.peek_op("||") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.peek_op("&&") {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.peek_op("!") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.compare()
+    }
+
+    fn compare(&mut self) -> Result<Expr, String> {
+        let left = self.atom()?;
+        let op = match self.tokens.get(self.pos) {
+            Some(Token::Op("<")) => CompareOp::Lt,
+            Some(Token::Op("<=")) => CompareOp::Le,
+            Some(Token::Op(">")) => CompareOp::Gt,
+            Some(Token::Op(">=")) => CompareOp::Ge,
+            Some(Token::Op("==")) => CompareOp::Eq,
+            Some(Token::Op("!=")) => CompareOp::Ne,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        Ok(Expr::Compare(Box::new(left), op, Box::new(self.atom()?)))
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Number(number)) => Ok(Expr::Number(number)),
+            Some(Token::Ident(ident)) => Ok(match ident.as_str() {
+                "true" => Expr::Bool(true),
+                "false" => Expr::Bool(false),
+                _ => Expr::Var(ident),
+            }),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.tokens.get(self.pos) {
+                    Some(Token::Close) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    _ => Err("missing ')'".to_string()),
+                }
+            }
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of rule".to_string()),
+        }
+    }
+}
+
+pub fn parse(rule: &str) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(rule)?,
+        pos: 0,
+    };
+    let expr = parser.or()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(format!("unexpected {:?}", parser.tokens[parser.pos]));
+    }
+    validate(&expr)?;
+    Ok(expr)
+}
+
+fn validate(expr: &Expr) -> Result<(), String> {
+    match expr {
+        Expr::Number(_) | Expr::Bool(_) => Ok(()),
+        Expr::Var(name) => variable_kind(name).map(|_| ()),
+        Expr::Not(inner) => validate(inner),
+        Expr::And(left, right) | Expr::Or(left, right) | Expr::Compare(left, _, right) => {
+            validate(left).and(validate(right))
+        }
+    }
+}
+
+/// `true` for flags, `false` for numbers
+fn variable_kind(name: &str) -> Result<bool, String> {
+    match name {
+        "offset_hours" | "utc_offset_hours" | "hour" | "minute" | "weekday" | "comfort" => {
+            Ok(false)
+        }
+        "is_working_hours" | "is_daytime" | "is_dst" | "is_ooo" => Ok(true),
+        _ => Err(format!("unknown variable '{}'", name)),
+    }
+}
+
+fn variable(name: &str, clock: &CityClock) -> Value {
+    match name {
+        "offset_hours" => Value::Number(f64::from(clock.diff_secs) / 3600.0),
+        "utc_offset_hours" => Value::Number(f64::from(clock.utc_offset_secs) / 3600.0),
+        "hour" => Value::Number(f64::from(clock.time.hour())),
+        "minute" => Value::Number(f64::from(clock.time.minute())),
+        "weekday" => Value::Number(f64::from(clock.time.weekday().number_from_monday())),
+        "comfort" => Value::Number(f64::from(clock.comfort())),
+        "is_working_hours" => Value::Bool(clock.is_working_hours()),
+        "is_daytime" => Value::Bool(clock.is_daytime()),
+        "is_dst" => Value::Bool(clock.is_dst()),
+        "is_ooo" => Value::Bool(clock.is_ooo()),
+        _ => Value::Bool(false),
+    }
+}
+
+fn eval(expr: &Expr, clock: &CityClock) -> Value {
+    let truthy = |expr: &Expr| match eval(expr, clock) {
+        Value::Bool(b) => b,
+        Value::Number(n) => n != 0.0,
+    };
+
+    match expr {
+        Expr::Number(n) => Value::Number(*n),
+        Expr::Bool(b) => Value::Bool(*b),
+        Expr::Var(name) => variable(name, clock),
+        Expr::Not(inner) => Value::Bool(!truthy(inner)),
+        Expr::And(left, right) => Value::Bool(truthy(left) && truthy(right)),
+        Expr::Or(left, right) => Value::Bool(truthy(left) || truthy(right)),
+        Expr::Compare(left, op, right) => {
+            let result = match (eval(left, clock), eval(right, clock)) {
+                (Value::Number(l), Value::Number(r)) => match op {
+                    CompareOp::Lt => l < r,
+                    CompareOp::Le => l <= r,
+                    CompareOp::Gt => l > r,
+                    CompareOp::Ge => l >= r,
+                    CompareOp::Eq => l == r,
+                    CompareOp::Ne => l != r,
+                },
+                (l, r) => match op {
+                    CompareOp::Eq => l == r,
+                    CompareOp::Ne => l != r,
+                    _ => false,
+                },
+            };
+            Value::Bool(result)
+        }
+    }
+}
+
+pub fn matches(expr: &Expr, clock: &CityClock) -> bool {
+    match eval(expr, clock) {
+        Value::Bool(b) => b,
+        Value::Number(n) => n != 0.0,
+    }
+}
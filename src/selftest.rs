@@ -0,0 +1,130 @@
+//! `--self-test`: a menu that is valid no matter what, listing what the plugin
+//! sees, for when the real menu comes up blank.
+//!
+//! Nothing here may exit early: every check reports its failure as a red line
+//! and the next check still runs.
+
+use crate::render::render_swiftbar;
+use crate::{Config, config_format, expand_home, remote, resolve_timezones, rules, state};
+use chrono::Local;
+use std::fmt::Write;
+use std::{env, fs, panic};
+
+const OK: &str = "font=Menlo size=12";
+const FAILED: &str = "font=Menlo size=12 color=red";
+
+pub fn render(config_arg: &str) -> String {
+    let mut output = String::from("🕐 self-test\n---\n");
+    let mut line = |text: &str, ok: bool| {
+        // A stray " | " would turn the rest of the line into parameters
+        let _ = writeln!(
+            output,
+            "{} | {}",
+            text.replace('|', "¦").replace('\n', " "),
+            if ok { OK } else { FAILED }
+        );
+    };
+
+    line(&format!("Version: {}", env!("CARGO_PKG_VERSION")), true);
+    match env::current_exe() {
+        Ok(exe) => line(&format!("Binary: {}", exe.display()), true),
+        Err(err) => line(&format!("Binary: unknown ({})", err), false),
+    }
+    match env::var("SWIFTBAR_VERSION") {
+        Ok(version) => line(&format!("SwiftBar: {}", version), true),
+        Err(_) => line("SwiftBar: not detected (run outside SwiftBar?)", true),
+    }
+    match iana_time_zone::get_timezone() {
+        Ok(tz) => line(&format!("Local timezone: {}", tz), true),
+        Err(err) => line(&format!("Local timezone: unknown ({})", err), false),
+    }
+    line(&format!("Config: {}", config_arg), true);
+
+    let Some(mut config) = parse_config(config_arg, &mut line) else {
+        return output;
+    };
+    resolve_timezones(&mut config.cities);
+    config.state = state::load(config_arg);
+    config.source = Some(config_arg.to_string());
+
+    let invalid: Vec<&str> = config
+        .cities
+        .iter()
+        .filter(|city| config.timezone(&city.timezone).is_err())
+        .map(|city| city.name.as_str())
+        .collect();
+    if !invalid.is_empty() {
+        line(&format!("Invalid timezone: {}", invalid.join(", ")), false);
+    }
+    for city in &config.cities {
+        if let Some(rule) = &city.show_if
+            && let Err(err) = rules::parse(rule)
+        {
+            line(
+                &format!("Invalid show_if for {}: {}", city.name, err),
+                false,
+            );
+        }
+    }
+
+    // The real menu is rendered but not shown, only whether it got that far
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let rendered = panic::catch_unwind(|| render_swiftbar(&config, Local::now()));
+    panic::set_hook(default_hook);
+    match rendered {
+        Ok(menu) => line(
+            &format!("Menu renders: {} lines", menu.lines().count()),
+            true,
+        ),
+        Err(err) => {
+            let message = err
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| err.downcast_ref::<&str>().copied())
+                .unwrap_or("unknown error");
+            line(&format!("Menu render panicked: {}", message), false)
+        }
+    }
+    output
+}
+
+/// Parse the config the way `load_config` would, reporting each step instead
+/// of quietly falling back to the defaults
+fn parse_config(config_arg: &str, line: &mut impl FnMut(&str, bool)) -> Option<Config> {
+    let content = if remote::is_url(config_arg) {
+        match remote::refresh(config_arg) {
+            Some(content) => content,
+            None => {
+                line("Config: could not be fetched and is not cached", false);
+                return None;
+            }
+        }
+    } else {
+        let path = expand_home(config_arg).unwrap_or_else(|| config_arg.into());
+        line(&format!("Config path: {}", path.display()), true);
+        match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                line(
+                    &format!("Config: cannot be read ({}), the defaults are used", err),
+                    false,
+                );
+                return None;
+            }
+        }
+    };
+    match config_format::parse(config_arg, &content) {
+        Ok(config) => {
+            line(&format!("Config: OK, {} cities", config.cities.len()), true);
+            Some(config)
+        }
+        Err(err) => {
+            line(
+                &format!("Config: parse error ({}), the defaults are used", err),
+                false,
+            );
+            None
+        }
+    }
+}
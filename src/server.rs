@@ -0,0 +1,157 @@
+//! Minimal HTTP server exposing the clocks rendered from the same config as
+//! the menu bar plugin.
+//!
+//! Requests are handled one at a time, which is plenty for a dashboard
+//! polling every few seconds and keeps us free of an async runtime.
+
+use crate::{convert, load_config, render};
+use chrono::Local;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// How long a client may stall before it is dropped, so that one that
+/// connects and sends nothing cannot hold up everyone else
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn serve(config_path: &str, host: &str, port: u16) -> Result<(), String> {
+    let listener =
+        TcpListener::bind((host, port)).map_err(|e| format!("{}:{}: {}", host, port, e))?;
+    eprintln!("Serving clocks on http://{}:{}/", host, port);
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(err) = handle(stream, config_path) {
+                    eprintln!("Warning: {}", err);
+                }
+            }
+            Err(err) => eprintln!("Warning: {}", err),
+        }
+    }
+    Ok(())
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Response {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: &'static str) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{}\n", status),
+        }
+    }
+}
+
+fn handle(mut stream: TcpStream, config_path: &str) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Drain the headers, we have no use for them
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => route(target, config_path),
+        (Some(_), Some(_)) => Response::error("405 Method Not Allowed"),
+        _ => Response::error("400 Bad Request"),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )
+}
+
+fn route(target: &str, config_path: &str) -> Response {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    // Reload on every request so config edits show up without a restart
+    let config = load_config(config_path.to_string());
+    let now = Local::now();
+
+    match path {
+        "/json" => Response::ok(
+            "application/json",
+            render::render_json(&config, now).to_string(),
+        ),
+        "/" | "/html" => Response::ok(
+            "text/html; charset=utf-8",
+            render::render_html(&config, now),
+        ),
+        "/convert" => {
+            let param = |name: &str| query_param(query, name);
+            let Some(time) = param("time") else {
+                return Response::error("400 Bad Request");
+            };
+            match convert::resolve(&config, now, &time, param("tz").as_deref()) {
+                Ok(instant) => Response::ok(
+                    "application/json",
+                    convert::convert_json(&config, instant).to_string(),
+                ),
+                Err(err) => Response {
+                    status: "400 Bad Request",
+                    content_type: "application/json",
+                    body: serde_json::json!({ "error": err }).to_string(),
+                },
+            }
+        }
+        _ => Response::error("404 Not Found"),
+    }
+}
+
+/// The decoded value of `name` in a query string like `time=15:00&tz=UTC`
+pub fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(value))
+}
+
+/// Decode `%XX` escapes and `+` in a query string value
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
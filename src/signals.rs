@@ -0,0 +1,44 @@
+//! Signals poking the redrawing modes from outside: SIGUSR1 redraws right
+//! away, SIGHUP reloads the config.
+
+use signal_hook::consts::{SIGHUP, SIGUSR1};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+pub enum Event {
+    /// The interval passed, or SIGUSR1 asked for a redraw
+    Redraw,
+    /// SIGHUP asked to reload the config
+    Reload,
+}
+
+pub struct Signals {
+    received: Receiver<i32>,
+}
+
+impl Signals {
+    pub fn register() -> Result<Self, String> {
+        let mut signals = signal_hook::iterator::Signals::new([SIGUSR1, SIGHUP])
+            .map_err(|e| format!("failed to register signal handlers: {}", e))?;
+        let (sender, received) = mpsc::channel();
+        thread::spawn(move || {
+            for signal in signals.forever() {
+                if sender.send(signal).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Signals { received })
+    }
+
+    /// Wait for `interval`, or less if a signal arrives
+    pub fn wait(&self, interval: Duration) -> Event {
+        match self.received.recv_timeout(interval) {
+            Ok(SIGHUP) => Event::Reload,
+            Ok(_) | Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                Event::Redraw
+            }
+        }
+    }
+}
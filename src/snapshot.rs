@@ -0,0 +1,123 @@
+//! Pinned snapshots of every city's UTC offset, for checking what changed
+//! since (DST transitions, tz database updates, edited timezones).
+
+use crate::Config;
+use crate::render::format_utc_offset;
+use chrono::{DateTime, Offset, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    taken: DateTime<Utc>,
+    cities: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    name: String,
+    timezone: String,
+    utc_offset_secs: i32,
+    abbreviation: String,
+}
+
+pub fn save(config: &Config, path: &Path) -> Result<(), String> {
+    let taken = Utc::now();
+    let cities = config
+        .cities
+        .iter()
+        .filter_map(|city| {
+            let tz = config.timezone(&city.timezone).ok()?;
+            let time = taken.with_timezone(&tz);
+            Some(Entry {
+                name: city.name.clone(),
+                timezone: city.timezone.clone(),
+                utc_offset_secs: time.offset().fix().local_minus_utc(),
+                abbreviation: time.format("%Z").to_string(),
+            })
+        })
+        .collect();
+
+    let content = serde_yaml::to_string(&Snapshot { taken, cities }).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    println!("Saved snapshot to {}", path.display());
+    Ok(())
+}
+
+/// Print every offset that differs from the snapshot; an error if any does
+pub fn diff(config: &Config, path: &Path) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let snapshot: Snapshot =
+        serde_yaml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let now = Utc::now();
+    let mut changes = 0;
+    let mut report = |line: String| {
+        println!("{}", line);
+        changes += 1;
+    };
+
+    for entry in &snapshot.cities {
+        let Some(city) = config.cities.iter().find(|city| city.name == entry.name) else {
+            report(format!("{}: removed", entry.name));
+            continue;
+        };
+        let tz = config.timezone(&city.timezone)?;
+        let offset_at = |instant: DateTime<Utc>| {
+            let time = tz.from_utc_datetime(&instant.naive_utc());
+            (
+                time.offset().fix().local_minus_utc(),
+                time.format("%Z").to_string(),
+            )
+        };
+
+        // The same instant giving a different offset means the timezone or its rules changed
+        let (then_offset, then_abbreviation) = offset_at(snapshot.taken);
+        if then_offset != entry.utc_offset_secs {
+            let cause = if city.timezone == entry.timezone {
+                "tz database rules changed"
+            } else {
+                "timezone changed"
+            };
+            report(format!(
+                "{}: {} ({} {} is now {} {} on {})",
+                city.name,
+                cause,
+                entry.abbreviation,
+                format_utc_offset(entry.utc_offset_secs),
+                then_abbreviation,
+                format_utc_offset(then_offset),
+                snapshot.taken.format("%Y-%m-%d")
+            ));
+        }
+
+        let (now_offset, now_abbreviation) = offset_at(now);
+        if now_offset != then_offset {
+            report(format!(
+                "{}: {} {} -> {} {} since the snapshot",
+                city.name,
+                then_abbreviation,
+                format_utc_offset(then_offset),
+                now_abbreviation,
+                format_utc_offset(now_offset)
+            ));
+        }
+    }
+
+    for city in &config.cities {
+        if !snapshot.cities.iter().any(|entry| entry.name == city.name) {
+            report(format!("{}: added", city.name));
+        }
+    }
+
+    match changes {
+        0 => {
+            println!(
+                "No offsets changed since {}",
+                snapshot.taken.format("%Y-%m-%d %H:%M UTC")
+            );
+            Ok(())
+        }
+        changes => Err(format!("{} change(s) since the snapshot", changes)),
+    }
+}
@@ -0,0 +1,99 @@
+//! Reading the cities' times aloud with the macOS `say` command.
+//!
+//! Each city can be spoken in its own language: the phrase is translated for
+//! a few common languages and spoken by the first installed voice for it,
+//! unless the city names a `voice` explicitly.
+
+use crate::Config;
+use crate::render::{CityClock, select_clocks};
+use chrono::Local;
+use std::process::Command;
+
+/// "It is {time} in {city}" for the languages with a translation
+const PHRASES: &[(&str, &str)] = &[
+    ("en", "It is {time} in {city}"),
+    ("de", "In {city} ist es {time}"),
+    ("fr", "Il est {time} à {city}"),
+    ("es", "En {city} son las {time}"),
+    ("it", "A {city} sono le {time}"),
+    ("pt", "Em {city} são {time}"),
+    ("pl", "W {city} jest {time}"),
+    ("nl", "In {city} is het {time}"),
+];
+
+/// Speak the time of the `cities` named, or of all cities if there are none
+pub fn say(config: &Config, cities: &[String]) -> Result<(), String> {
+    let now = Local::now();
+    let mut clocks = Vec::new();
+    if cities.is_empty() {
+        clocks = select_clocks(config, now, None)?;
+    } else {
+        for name in cities {
+            clocks.extend(select_clocks(config, now, Some(name))?);
+        }
+    }
+
+    let voices = installed_voices();
+    for clock in &clocks {
+        let mut command = Command::new("say");
+        if let Some(voice) = voice(clock, &voices) {
+            command.arg("-v").arg(voice);
+        }
+        let status = command
+            .arg(phrase(clock))
+            .status()
+            .map_err(|e| format!("can't run say (macOS only): {}", e))?;
+        if !status.success() {
+            return Err(format!("say exited with {}", status));
+        }
+    }
+    Ok(())
+}
+
+fn phrase(clock: &CityClock) -> String {
+    // "de_DE" and "de-DE" use the "de" phrase
+    let language = clock.city.language.as_deref().unwrap_or("en");
+    let short = language.split(['_', '-']).next().unwrap_or(language);
+    let template = PHRASES
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(short))
+        .map_or(PHRASES[0].1, |(_, phrase)| phrase);
+    template
+        .replace("{time}", &clock.formatted_time())
+        .replace("{city}", &clock.city.name)
+}
+
+/// The city's `voice`, or the first installed voice matching its `language`
+fn voice<'a>(clock: &'a CityClock, voices: &'a [(String, String)]) -> Option<&'a str> {
+    if let Some(voice) = &clock.city.voice {
+        return Some(voice);
+    }
+    let language = clock.city.language.as_deref()?.replace('-', "_");
+    voices
+        .iter()
+        .find(|(_, locale)| locale.eq_ignore_ascii_case(&language))
+        .or_else(|| {
+            voices.iter().find(|(_, locale)| {
+                let prefix = locale.split('_').next().unwrap_or(locale);
+                prefix.eq_ignore_ascii_case(&language)
+            })
+        })
+        .map(|(name, _)| name.as_str())
+}
+
+/// Installed voices with their locale, from lines like
+/// `Anna                de_DE    # Hallo! Ich heiße Anna.`
+fn installed_voices() -> Vec<(String, String)> {
+    let Ok(output) = Command::new("say").args(["-v", "?"]).output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (voice, _) = line.split_once('#')?;
+            let mut words: Vec<&str> = voice.split_whitespace().collect();
+            let locale = words.pop()?;
+            Some((words.join(" "), locale.to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,66 @@
+//! Settings changed from the menu rather than by editing the config, kept in
+//! a state file next to it (`swiftbar_clock_config.state.yaml`).
+
+use crate::{backup, cache, managed_files, notify, remote, resolve_config_path};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    /// City the menu is shown relative to, instead of the home city
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative_to: Option<String>,
+    /// When each tracked city was last marked as talked to
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub contacted: BTreeMap<String, DateTime<Utc>>,
+    /// Notifications held back during quiet hours, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deferred: Vec<notify::Deferred>,
+}
+
+/// State file of the config at `config`. Remote configs keep it in the cache directory.
+pub fn path(config: &str) -> Option<PathBuf> {
+    if remote::is_url(config) {
+        return Some(cache::dir()?.join("state.yaml"));
+    }
+    let config_path = resolve_config_path(config).ok()?;
+    Some(config_path.with_extension("state.yaml"))
+}
+
+/// State of the config at `config`, empty if there is none yet
+pub fn load(config: &str) -> State {
+    let Some(content) = path(config).and_then(|path| fs::read_to_string(path).ok()) else {
+        return State::default();
+    };
+    serde_yaml::from_str(&content).unwrap_or_else(|err| {
+        eprintln!("Warning: Ignoring invalid state file: {}", err);
+        State::default()
+    })
+}
+
+/// Load the state, apply `change` and write it back, taking a backup first
+/// like edits of the config do
+pub fn update(config: &str, change: impl FnOnce(&mut State)) -> Result<(), String> {
+    let path = path(config).ok_or("can't locate the state file")?;
+    let old = load(config);
+    let mut state = old.clone();
+    change(&mut state);
+    if state == old {
+        return Ok(());
+    }
+
+    // Remote configs have no backups, their state is only in the cache
+    if !remote::is_url(config) {
+        let config_path = resolve_config_path(config)?;
+        backup::backup(&config_path, &managed_files(&config_path)).map_err(|e| e.to_string())?;
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    let content = serde_yaml::to_string(&state).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
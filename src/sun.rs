@@ -0,0 +1,104 @@
+//! Sunrise and sunset from a city's coordinates, using the sunrise equation
+//! (accurate to a minute or two away from the poles).
+
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use std::ops::Sub;
+
+/// Julian date of 2000-01-01 12:00 UTC
+const J2000: f64 = 2_451_545.0;
+
+pub enum Daylight {
+    Between {
+        sunrise: DateTime<Utc>,
+        sunset: DateTime<Utc>,
+    },
+    /// The sun doesn't set
+    PolarDay,
+    /// The sun doesn't rise
+    PolarNight,
+}
+
+/// Sunrise and sunset on `date` at `latitude`, `longitude` (degrees, north and
+/// east positive)
+pub fn daylight(date: NaiveDate, latitude: f64, longitude: f64) -> Daylight {
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1).expect("valid date");
+    let days = (date - epoch).num_days() as f64;
+
+    let mean_noon = days - longitude / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * mean_noon)
+        .rem_euclid(360.0)
+        .to_radians();
+    let center =
+        1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = J2000 + mean_noon + 0.0053 * anomaly.sin() - 0.0069 * (2.0 * ecliptic).sin();
+
+    let declination = (ecliptic.sin() * 23.4397_f64.to_radians().sin()).asin();
+    let latitude = latitude.to_radians();
+    // -0.833° accounts for refraction and the size of the sun's disc
+    let cos_hour_angle = ((-0.833_f64).to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if cos_hour_angle < -1.0 {
+        return Daylight::PolarDay;
+    }
+    if cos_hour_angle > 1.0 {
+        return Daylight::PolarNight;
+    }
+    let half_day = cos_hour_angle.acos().to_degrees() / 360.0;
+    Daylight::Between {
+        sunrise: from_julian(transit - half_day),
+        sunset: from_julian(transit + half_day),
+    }
+}
+
+/// Whether the sun is up at `now`, for the day `date` it falls on locally
+pub fn is_up(now: DateTime<Utc>, date: NaiveDate, latitude: f64, longitude: f64) -> bool {
+    match daylight(date, latitude, longitude) {
+        Daylight::Between { sunrise, sunset } => sunrise <= now && now < sunset,
+        Daylight::PolarDay => true,
+        Daylight::PolarNight => false,
+    }
+}
+
+/// Part of the day, for `menu_icon: daypart`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Daypart {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+/// The part of the day at `now`, for the day `date` it falls on locally. Dawn
+/// and dusk are the half hour either side of sunrise and sunset.
+pub fn daypart(now: DateTime<Utc>, date: NaiveDate, latitude: f64, longitude: f64) -> Daypart {
+    match daylight(date, latitude, longitude) {
+        Daylight::Between { sunrise, sunset } => daypart_between(now, sunrise, sunset),
+        Daylight::PolarDay => Daypart::Day,
+        Daylight::PolarNight => Daypart::Night,
+    }
+}
+
+/// Like [`daypart`], with the sun rising at `sunrise` and setting at `sunset`
+pub fn daypart_between<T>(now: T, sunrise: T, sunset: T) -> Daypart
+where
+    T: Sub<Output = TimeDelta> + PartialOrd + Copy,
+{
+    let twilight = TimeDelta::minutes(30);
+    if (now - sunrise).abs() < twilight {
+        Daypart::Dawn
+    } else if (now - sunset).abs() < twilight {
+        Daypart::Dusk
+    } else if sunrise < now && now < sunset {
+        Daypart::Day
+    } else {
+        Daypart::Night
+    }
+}
+
+fn from_julian(julian: f64) -> DateTime<Utc> {
+    let epoch = DateTime::from_timestamp(946_728_000, 0).expect("valid timestamp");
+    epoch + TimeDelta::milliseconds(((julian - J2000) * 86_400_000.0) as i64)
+}
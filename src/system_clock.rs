@@ -0,0 +1,59 @@
+//! The 12/24-hour preference of the macOS system, used when the config has no
+//! `time_format`, so the menu agrees with the menu bar clock.
+//!
+//! An explicit "24-Hour Time" setting wins; otherwise the region of the
+//! system locale decides, as it does for the system clock.
+
+use crate::{TimeFormat, hooks};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Preferences change rarely, reading them costs a process per key
+const TTL: Duration = Duration::from_secs(3600);
+
+/// Regions whose locale defaults to a 12-hour clock
+const TWELVE_HOUR_REGIONS: &[&str] = &[
+    "US", "CA", "AU", "NZ", "IN", "PH", "PK", "BD", "EG", "SA", "KR", "TW", "MY", "CO",
+];
+
+/// The system's clock convention, 24-hour if it can't be read
+pub fn time_format() -> TimeFormat {
+    static FORMAT: OnceLock<TimeFormat> = OnceLock::new();
+    *FORMAT.get_or_init(|| detect().unwrap_or_default())
+}
+
+fn detect() -> Option<TimeFormat> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    if read_global("AppleICUForce24HourTime").as_deref() == Some("1") {
+        return Some(TimeFormat::TwentyFourHour);
+    }
+    if read_global("AppleICUForce12HourTime").as_deref() == Some("1") {
+        return Some(TimeFormat::TwelveHour);
+    }
+    read_global("AppleLocale").map(|locale| locale_default(&locale))
+}
+
+/// Clock convention of a locale such as `en_US` or `de_DE@calendar=gregorian`
+fn locale_default(locale: &str) -> TimeFormat {
+    let locale = locale.split('@').next().unwrap_or(locale);
+    let region = locale.rsplit(['_', '-']).next().unwrap_or_default();
+    // Canada is split: French speakers use the 24-hour clock
+    if region == "CA" && locale.starts_with("fr") {
+        return TimeFormat::TwentyFourHour;
+    }
+    if TWELVE_HOUR_REGIONS.contains(&region) {
+        TimeFormat::TwelveHour
+    } else {
+        TimeFormat::TwentyFourHour
+    }
+}
+
+/// A key of the global domain, `None` if it is not set
+fn read_global(key: &str) -> Option<String> {
+    // `defaults` fails for unset keys, which is an answer and not an error
+    let command = format!("defaults read -g {} 2>/dev/null || true", key);
+    let output = hooks::run_cached(&command, TTL, hooks::DEFAULT_TIMEOUT).ok()?;
+    hooks::first_line(&output).map(str::to_string)
+}
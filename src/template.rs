@@ -0,0 +1,23 @@
+//! User-provided Tera templates producing the whole plugin output.
+//!
+//! The template gets the same data model as the JSON output (see
+//! [`render::render_json`]): `local`, `icon`, `headers` and `cities`, where
+//! each city has `name`, `hhmm`, `diff`, `role_icon`, `ooo`, `links` and so on.
+
+use crate::render::render_json;
+use crate::{Config, expand_home};
+use chrono::{DateTime, Local};
+use std::fs;
+use tera::{Context, Tera};
+
+pub fn render(
+    config: &Config,
+    template_path: &str,
+    now: DateTime<Local>,
+) -> Result<String, String> {
+    let path = expand_home(template_path).unwrap_or_else(|| template_path.into());
+    let template = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let context = Context::from_serialize(&render_json(config, now)).map_err(|e| e.to_string())?;
+    Tera::one_off(&template, &context, false).map_err(|e| format!("{}: {}", path.display(), e))
+}
@@ -0,0 +1,47 @@
+//! Named time formats shared by all outputs, set with `preset:` globally or
+//! per city. Without a preset times are shown as `15:04`. A city's own
+//! strftime `format:` takes precedence over both.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, SecondsFormat, Timelike};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// `2024-03-10T15:04`
+    Iso,
+    /// `2024-03-10T15:04:05+01:00`
+    Rfc3339,
+    /// `3:04 PM`
+    Kitchen,
+    /// `1504`
+    Military,
+}
+
+pub fn write_time(output: &mut String, time: &DateTime<Tz>, preset: Option<Preset>) {
+    let _ = match preset {
+        None => write!(output, "{:02}:{:02}", time.hour(), time.minute()),
+        Some(Preset::Iso) => write!(output, "{}", time.format("%Y-%m-%dT%H:%M")),
+        Some(Preset::Rfc3339) => {
+            output.push_str(&time.to_rfc3339_opts(SecondsFormat::Secs, false));
+            Ok(())
+        }
+        Some(Preset::Kitchen) => {
+            let (pm, hour) = time.hour12();
+            let suffix = if pm { "PM" } else { "AM" };
+            write!(output, "{}:{:02} {}", hour, time.minute(), suffix)
+        }
+        Some(Preset::Military) => write!(output, "{:02}{:02}", time.hour(), time.minute()),
+    };
+}
+
+/// Check a strftime `format` up front, chrono only reports errors while writing
+pub fn check_format(format: &str) -> Result<(), String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("invalid time format '{}'", format));
+    }
+    Ok(())
+}
@@ -0,0 +1,337 @@
+//! Full-screen terminal world clock with large digits and in-place editing of
+//! the city list. Edits are written back to the YAML config.
+//!
+//! A table view fits every city on one screen, with offsets and whether it is
+//! day or night there.
+
+use crate::{
+    CityConfig, Config, bigdigits, citydb, read_config_file, render, resolve_config_path,
+    write_config_file,
+};
+use chrono::{Local, Offset, Timelike};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const PANEL_HEIGHT: u16 = 7;
+const NIGHT_BACKGROUND: Color = Color::Indexed(17);
+
+enum Mode {
+    Normal,
+    AddName(String),
+    AddTimezone { name: String, input: String },
+}
+
+struct App {
+    config_path: PathBuf,
+    config: Config,
+    selected: usize,
+    dirty: bool,
+    mode: Mode,
+    message: Option<String>,
+    table: bool,
+    /// Without colors, night is dimmed and the selection only bold
+    color: bool,
+}
+
+pub fn run(config: &str, table: bool, color: bool) -> Result<(), String> {
+    let config_path = resolve_config_path(config)?;
+    let config = if config_path.exists() {
+        read_config_file(&config_path)?
+    } else {
+        Config::default()
+    };
+
+    let mut app = App {
+        config_path,
+        config,
+        selected: 0,
+        dirty: false,
+        mode: Mode::Normal,
+        message: None,
+        table,
+        color,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<(), String> {
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|e| e.to_string())?;
+
+            // Wake up at the next full second to keep the clocks current
+            let millis = 1000 - u64::from(Local::now().timestamp_subsec_millis().min(999));
+            if !event::poll(Duration::from_millis(millis)).map_err(|e| e.to_string())? {
+                continue;
+            }
+            if let Event::Key(key) = event::read().map_err(|e| e.to_string())?
+                && key.kind == KeyEventKind::Press
+                && !self.handle_key(key.code)?
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns `false` when the app should quit
+    fn handle_key(&mut self, code: KeyCode) -> Result<bool, String> {
+        let count = self.config.cities.len();
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => match code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    self.save()?;
+                    return Ok(false);
+                }
+                KeyCode::Down | KeyCode::Char('j') if self.selected + 1 < count => {
+                    self.selected += 1
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Char('J') if self.selected + 1 < count => {
+                    self.config.cities.swap(self.selected, self.selected + 1);
+                    self.selected += 1;
+                    self.dirty = true;
+                }
+                KeyCode::Char('K') if self.selected > 0 => {
+                    self.config.cities.swap(self.selected, self.selected - 1);
+                    self.selected -= 1;
+                    self.dirty = true;
+                }
+                KeyCode::Char('d') | KeyCode::Delete if count > 0 => {
+                    let removed = self.config.cities.remove(self.selected);
+                    self.selected = self.selected.min(count.saturating_sub(2));
+                    self.message = Some(format!("Removed {}", removed.name));
+                    self.dirty = true;
+                }
+                KeyCode::Char('a') => self.mode = Mode::AddName(String::new()),
+                KeyCode::Char('s') => self.save()?,
+                KeyCode::Char('t') => self.table = !self.table,
+                _ => {}
+            },
+            Mode::AddName(mut input) => match code {
+                KeyCode::Esc => {}
+                KeyCode::Enter if !input.trim().is_empty() => {
+                    // Suggest the timezone of the biggest city of that name
+                    let suggestion = citydb::lookup(input.trim())
+                        .first()
+                        .map_or_else(String::new, |city| city.timezone.to_string());
+                    self.mode = Mode::AddTimezone {
+                        name: input.trim().to_string(),
+                        input: suggestion,
+                    }
+                }
+                code => {
+                    edit_input(&mut input, code);
+                    self.mode = Mode::AddName(input);
+                }
+            },
+            Mode::AddTimezone { name, mut input } => match code {
+                KeyCode::Esc => {}
+                KeyCode::Enter => match self.config.timezone(input.trim()) {
+                    Ok(tz) => {
+                        self.config.cities.push(CityConfig {
+                            name,
+                            timezone: tz.name().to_string(),
+                            ..Default::default()
+                        });
+                        self.selected = self.config.cities.len() - 1;
+                        self.dirty = true;
+                    }
+                    Err(_) => {
+                        self.message = Some(format!("Unknown timezone '{}'", input.trim()));
+                        self.mode = Mode::AddTimezone { name, input };
+                    }
+                },
+                code => {
+                    edit_input(&mut input, code);
+                    self.mode = Mode::AddTimezone { name, input };
+                }
+            },
+        }
+        Ok(true)
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        if self.dirty {
+            write_config_file(&self.config_path, &self.config)?;
+            self.dirty = false;
+            self.message = Some(format!("Saved {}", self.config_path.display()));
+        }
+        Ok(())
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [cities_area, status_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+        if self.table {
+            self.draw_table(frame, cities_area);
+        } else {
+            self.draw_panels(frame, cities_area);
+        }
+
+        let status = match &self.mode {
+            Mode::Normal => self.message.clone().unwrap_or_else(|| {
+                "↑↓ select  J/K move  a add  d delete  t table  s save  q quit".to_string()
+            }),
+            Mode::AddName(input) => format!("Name: {}█", input),
+            Mode::AddTimezone { name, input } => {
+                format!("Timezone for {} (e.g. Europe/Berlin): {}█", name, input)
+            }
+        };
+        frame.render_widget(
+            Paragraph::new(status).style(Style::default().add_modifier(Modifier::REVERSED)),
+            status_area,
+        );
+    }
+
+    /// One row per city: time, date, UTC offset, offset from home and day or night
+    fn draw_table(&self, frame: &mut Frame, area: Rect) {
+        let now = Local::now();
+        let base_offset_secs = render::base_offset_secs(&self.config, now);
+        let rows = self.config.cities.iter().map(|city| {
+            let Ok(tz) = self.config.timezone(&city.timezone) else {
+                return Row::new([
+                    Cell::from(city.name.as_str()),
+                    Cell::from(format!("Invalid timezone '{}'", city.timezone)),
+                ]);
+            };
+            let time = now.with_timezone(&tz);
+            let utc_offset_secs = time.offset().fix().local_minus_utc();
+            let night = !(6..18).contains(&time.hour());
+            let row = Row::new([
+                city.name.clone(),
+                time.format("%H:%M:%S").to_string(),
+                time.format("%a %d %b").to_string(),
+                render::format_utc_offset(utc_offset_secs),
+                render::format_diff(utc_offset_secs - base_offset_secs)
+                    .trim()
+                    .to_string(),
+                if night { "☾ night" } else { "☀ day" }.to_string(),
+            ]);
+            if night {
+                row.style(self.night_style())
+            } else {
+                row
+            }
+        });
+        let widths = [
+            Constraint::Fill(1),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(7),
+        ];
+        let header = Row::new(["City", "Time", "Date", "UTC", "From home", ""])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::bordered().title(format!(" {} ", now.format("%a %d %b %H:%M:%S"))))
+            .row_highlight_style(self.selected_style(Style::default()));
+        let mut state = TableState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(table, area, &mut state);
+    }
+
+    fn night_style(&self) -> Style {
+        if self.color {
+            Style::default().bg(NIGHT_BACKGROUND).fg(Color::Gray)
+        } else {
+            Style::default().add_modifier(Modifier::DIM)
+        }
+    }
+
+    fn selected_style(&self, style: Style) -> Style {
+        let style = style.add_modifier(Modifier::BOLD);
+        if self.color {
+            style.fg(Color::Yellow)
+        } else {
+            style
+        }
+    }
+
+    /// A panel with large digits per city, as many as fit
+    fn draw_panels(&self, frame: &mut Frame, cities_area: Rect) {
+        let now = Local::now();
+        let base_offset_secs = render::base_offset_secs(&self.config, now);
+        let visible = usize::from((cities_area.height / PANEL_HEIGHT).max(1));
+        let first = self.selected.saturating_sub(visible - 1);
+
+        for (row, (index, city)) in self
+            .config
+            .cities
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(visible)
+            .enumerate()
+        {
+            let area = Rect {
+                y: cities_area.y + row as u16 * PANEL_HEIGHT,
+                height: PANEL_HEIGHT.min(cities_area.height),
+                ..cities_area
+            };
+
+            let Ok(tz) = self.config.timezone(&city.timezone) else {
+                let block = Block::bordered().title(format!(" {} ", city.name));
+                let text = format!("Invalid timezone '{}'", city.timezone);
+                frame.render_widget(Paragraph::new(text).block(block), area);
+                continue;
+            };
+            let time = now.with_timezone(&tz);
+            let diff_secs = time.offset().fix().local_minus_utc() - base_offset_secs;
+            let night = !(6..18).contains(&time.hour());
+
+            let style = if night {
+                self.night_style()
+            } else {
+                Style::default()
+            };
+            let border_style = if index == self.selected {
+                self.selected_style(style)
+            } else {
+                style
+            };
+
+            let title = format!(
+                " {}{}  {} ",
+                city.name,
+                render::format_diff(diff_secs),
+                time.format("%a %d %b")
+            );
+            let block = Block::bordered()
+                .title(title)
+                .border_style(border_style)
+                .style(style);
+            let digits = bigdigits::render(&time.format("%H:%M:%S").to_string(), '█', 2);
+            let lines: Vec<Line> = digits.into_iter().map(Line::from).collect();
+            frame.render_widget(
+                Paragraph::new(lines)
+                    .alignment(Alignment::Center)
+                    .block(block),
+                area,
+            );
+        }
+    }
+}
+
+fn edit_input(input: &mut String, code: KeyCode) {
+    match code {
+        KeyCode::Char(c) => input.push(c),
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        _ => {}
+    }
+}
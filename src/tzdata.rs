@@ -0,0 +1,88 @@
+//! `check_tzdata`: compare the offsets of the built-in tz database with the
+//! system's zoneinfo, which macOS updates on its own. A mismatch means one of
+//! them predates a change of a country's timezone rules, most likely the
+//! copy compiled into the plugin.
+
+use crate::{Config, hooks};
+use chrono::{DateTime, Offset};
+use chrono_tz::Tz;
+use std::time::Duration;
+
+/// Rules change a few times a year, there's no point asking `date` every minute
+const TTL: Duration = Duration::from_secs(3600);
+const ZONEINFO: &str = "/usr/share/zoneinfo";
+
+/// One line per configured zone whose offset differs, e.g.
+/// `Asia/Almaty: built-in data says UTC+6, the system says UTC+5`
+pub fn mismatches(config: &Config) -> Vec<String> {
+    let mut zones: Vec<Tz> = config
+        .cities
+        .iter()
+        .filter_map(|city| config.timezone(&city.timezone).ok())
+        .collect();
+    zones.sort_by_key(|tz| tz.name());
+    zones.dedup();
+    if zones.is_empty() {
+        return Vec::new();
+    }
+
+    // The first line is when the offsets were read, so a cached answer is
+    // compared with the built-in data at that instant and not at a later DST change
+    let mut command = String::from("date +%s");
+    for tz in &zones {
+        command.push_str(&format!(
+            "; if [ -e {dir}/{zone} ]; then TZ={zone} date +%z; else echo missing; fi",
+            dir = ZONEINFO,
+            zone = tz.name()
+        ));
+    }
+    let output = match hooks::run_cached(&command, TTL, hooks::DEFAULT_TIMEOUT) {
+        Ok(output) => output,
+        Err(err) => return vec![format!("tzdata: could not read the system zones ({})", err)],
+    };
+    let mut lines = output.lines();
+    let Some(at) = lines
+        .next()
+        .and_then(|line| line.trim().parse().ok())
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+    else {
+        return vec!["tzdata: unexpected output of date".to_string()];
+    };
+
+    let mut problems = Vec::new();
+    for (tz, line) in zones.iter().zip(lines) {
+        let line = line.trim();
+        if line == "missing" {
+            problems.push(format!("{}: not in the system's zoneinfo", tz.name()));
+            continue;
+        }
+        let Some(system) = parse_offset(line) else {
+            continue;
+        };
+        let builtin = at.with_timezone(tz).offset().fix().local_minus_utc();
+        if builtin != system {
+            problems.push(format!(
+                "{}: built-in data says {}, the system says {}",
+                tz.name(),
+                crate::render::format_utc_offset(builtin),
+                crate::render::format_utc_offset(system)
+            ));
+        }
+    }
+    problems
+}
+
+/// `+0545` as seconds
+fn parse_offset(offset: &str) -> Option<i32> {
+    let (sign, digits) = match offset.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if digits.len() != 4 {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
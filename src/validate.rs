@@ -0,0 +1,432 @@
+//! `config validate` and `--strict`: everything that makes loading fall back
+//! to the defaults or skip a city, reported as `file:line: problem`.
+//!
+//! Unknown keys are found by comparing each mapping with the fields serde
+//! expects for it, and located by walking the block structure of the file.
+//! Keys inside flow mappings (`{...}`) are reported at the mapping's line,
+//! and problems in TOML and JSON configs without one.
+
+use crate::config_format::{self, Format};
+use crate::{
+    CityConfig, ClockFacesConfig, Config, DaypartIcons, Etiquette, EventConfig, ExtraLinesConfig,
+    LinkConfig, Profile, QuietHours, SecondZoneConfig, Style, headers, include, lint, remote,
+    resolve_config_path, resolve_timezones,
+};
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+use std::fs;
+
+/// A step from a mapping into a key or from a list into an item
+#[derive(Clone)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+struct Path(Vec<Step>);
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, step) in self.0.iter().enumerate() {
+            match step {
+                Step::Key(key) if i == 0 => write!(f, "{}", key)?,
+                Step::Key(key) => write!(f, ".{}", key)?,
+                Step::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Problems with the config at `config_arg`, a path or URL, one line each
+pub fn validate(config_arg: &str) -> Result<(), Vec<String>> {
+    let (file, text) = if remote::is_url(config_arg) {
+        let text = remote::fetch_cached(config_arg)
+            .ok_or_else(|| vec![format!("{}: could not be fetched", config_arg)])?;
+        (config_arg.to_string(), text)
+    } else {
+        let path = resolve_config_path(config_arg).map_err(|err| vec![err])?;
+        let text =
+            fs::read_to_string(&path).map_err(|e| vec![format!("{}: {}", path.display(), e)])?;
+        (path.display().to_string(), text)
+    };
+    // The parsers' errors name the field and the line already
+    let format = Format::detect(config_arg, &text);
+    let error = |e: String| vec![format!("{}: {}", file, e)];
+    let mut config: Config = format.parse(&text).map_err(error)?;
+    resolve_timezones(&mut config.cities);
+    for profile in config.profiles.values_mut() {
+        resolve_timezones(&mut profile.cities);
+    }
+    let document: Value = match format {
+        Format::Yaml => serde_yaml::from_str(&text).map_err(|e| e.to_string()),
+        Format::Toml => toml::from_str(&text).map_err(|e| e.to_string()),
+        Format::Json => serde_json::from_str(&text).map_err(|e| e.to_string()),
+    }
+    .map_err(error)?;
+
+    // Lines are only found in YAML
+    let lines: Vec<&str> = match format {
+        Format::Yaml => text.lines().collect(),
+        _ => Vec::new(),
+    };
+    // Problems with their line, to list them in the order of the file
+    let mut problems: Vec<(Option<usize>, String)> = Vec::new();
+    let mut report = |path: &Path, problem: String| {
+        let line = locate(&lines, &path.0);
+        let at = match line {
+            Some(line) => format!("{}:{}", file, line + 1),
+            None => file.clone(),
+        };
+        problems.push((line, format!("{}: {}", at, problem)));
+    };
+    let mut unknown = Vec::new();
+    config_keys(&document, &mut unknown);
+    for path in unknown {
+        report(&path, format!("unknown key {}", path));
+    }
+    for (index, city) in config.cities.iter().enumerate() {
+        let at = [Step::Key("cities".to_string()), Step::Index(index)];
+        for (steps, problem) in city_problems(&config, city) {
+            report(&Path([&at[..], &steps].concat()), problem);
+        }
+    }
+    for (name, profile) in &config.profiles {
+        for (index, city) in profile.cities.iter().enumerate() {
+            let at = [
+                Step::Key("profiles".to_string()),
+                Step::Key(name.clone()),
+                Step::Key("cities".to_string()),
+                Step::Index(index),
+            ];
+            for (steps, problem) in city_problems(&config, city) {
+                report(
+                    &Path([&at[..], &steps].concat()),
+                    format!("profile {}: {}", name, problem),
+                );
+            }
+        }
+    }
+    for (index, header) in config.headers.iter().enumerate() {
+        if let Err(err) = headers::check(header) {
+            let path = vec![Step::Key("headers".to_string()), Step::Index(index)];
+            report(&Path(path), format!("header '{}': {}", header, err));
+        }
+    }
+    let mut includes: Vec<(Path, &String)> = Vec::new();
+    for (index, include) in config.include.iter().enumerate() {
+        let path = vec![Step::Key("include".to_string()), Step::Index(index)];
+        includes.push((Path(path), include));
+    }
+    for (name, profile) in &config.profiles {
+        for (index, include) in profile.include.iter().enumerate() {
+            let path = vec![
+                Step::Key("profiles".to_string()),
+                Step::Key(name.clone()),
+                Step::Key("include".to_string()),
+                Step::Index(index),
+            ];
+            includes.push((Path(path), include));
+        }
+    }
+    for (path, include) in includes {
+        let location = include::resolve(config_arg, include);
+        let text = if remote::is_url(&location) {
+            remote::fetch_cached(&location).ok_or_else(|| "could not be fetched".to_string())
+        } else {
+            fs::read_to_string(&location).map_err(|e| e.to_string())
+        };
+        match text.and_then(|text| config_format::parse(&location, &text)) {
+            // Its cities join this config, with its aliases
+            Ok(included) => {
+                for problem in included_problems(&config, &location, included, 1) {
+                    report(&path, problem);
+                }
+            }
+            Err(err) => report(&path, format!("cannot include {}: {}", location, err)),
+        }
+    }
+    if let Some(name) = include::selected_profile()
+        && !config.profiles.contains_key(name)
+    {
+        report(&Path(vec![]), format!("no profile named {}", name));
+    }
+    if problems.is_empty() {
+        return Ok(());
+    }
+    problems.sort_by_key(|(line, _)| *line);
+    Err(problems.into_iter().map(|(_, problem)| problem).collect())
+}
+
+/// Problems with the timezones, `show_if` and `format` of `city`, each with
+/// the steps from the city to the key at fault
+fn city_problems(config: &Config, city: &CityConfig) -> Vec<(Vec<Step>, String)> {
+    let timezone = || Step::Key("timezone".to_string());
+    let mut problems = Vec::new();
+    if let Err(err) = config.timezone(&city.timezone) {
+        problems.push((vec![timezone()], format!("{}: {}", city.name, err)));
+    }
+    if let Some(second) = &city.second
+        && let Err(err) = config.timezone(&second.timezone)
+    {
+        let steps = vec![Step::Key("second".to_string()), timezone()];
+        problems.push((steps, format!("{} / {}: {}", city.name, second.name, err)));
+    }
+    for (key, problem) in lint::field_problems(city) {
+        problems.push((vec![Step::Key(key.to_string())], problem));
+    }
+    problems
+}
+
+/// Problems with the cities of the file at `location`, included into
+/// `config`, and of the files it includes, each naming the file
+fn included_problems(
+    config: &Config,
+    location: &str,
+    mut included: Config,
+    depth: usize,
+) -> Vec<String> {
+    // Deeper nesting is reported when the config is loaded
+    if depth > include::MAX_DEPTH {
+        return Vec::new();
+    }
+    resolve_timezones(&mut included.cities);
+    let mut problems: Vec<String> = included
+        .cities
+        .iter()
+        .flat_map(|city| city_problems(config, city))
+        .map(|(_, problem)| format!("in {}: {}", location, problem))
+        .collect();
+    for nested in &included.include {
+        let nested = include::resolve(location, nested);
+        let text = if remote::is_url(&nested) {
+            remote::fetch_cached(&nested).ok_or_else(|| "could not be fetched".to_string())
+        } else {
+            fs::read_to_string(&nested).map_err(|e| e.to_string())
+        };
+        match text.and_then(|text| config_format::parse(&nested, &text)) {
+            Ok(nested_config) => {
+                problems.extend(included_problems(config, &nested, nested_config, depth + 1))
+            }
+            Err(err) => problems.push(format!(
+                "in {}: cannot include {}: {}",
+                location, nested, err
+            )),
+        }
+    }
+    problems
+}
+
+/// Unknown keys anywhere in the config
+fn config_keys(document: &Value, unknown: &mut Vec<Path>) {
+    let Some(config) = check::<Config>(document, &[], unknown) else {
+        return;
+    };
+    for (key, value) in config {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        let path = [Step::Key(key.to_string())];
+        match key {
+            "cities" => {
+                for (index, city) in items(value) {
+                    let path = [Step::Key(key.to_string()), Step::Index(index)];
+                    city_keys(city, &path, unknown);
+                }
+            }
+            "extra_lines" => {
+                for (index, item) in items(value) {
+                    let path = [Step::Key(key.to_string()), Step::Index(index)];
+                    check::<ExtraLinesConfig>(item, &path, unknown);
+                }
+            }
+            "events" => {
+                for (index, item) in items(value) {
+                    let path = [Step::Key(key.to_string()), Step::Index(index)];
+                    check::<EventConfig>(item, &path, unknown);
+                }
+            }
+            "clock_faces" => {
+                check::<ClockFacesConfig>(value, &path, unknown);
+            }
+            "quiet_hours" => {
+                check::<QuietHours>(value, &path, unknown);
+            }
+            "style" => {
+                check::<Style>(value, &path, unknown);
+            }
+            "daypart_icons" => {
+                check::<DaypartIcons>(value, &path, unknown);
+            }
+            "profiles" => {
+                for (name, profile) in value.as_mapping().into_iter().flatten() {
+                    let Some(name) = name.as_str() else {
+                        continue;
+                    };
+                    let path = [Step::Key(key.to_string()), Step::Key(name.to_string())];
+                    let Some(profile) = check::<Profile>(profile, &path, unknown) else {
+                        continue;
+                    };
+                    if let Some(cities) = profile.get("cities") {
+                        for (index, city) in items(cities) {
+                            let mut path = path.to_vec();
+                            path.extend([Step::Key("cities".to_string()), Step::Index(index)]);
+                            city_keys(city, &path, unknown);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn city_keys(city: &Value, path: &[Step], unknown: &mut Vec<Path>) {
+    let Some(city) = check::<CityConfig>(city, path, unknown) else {
+        return;
+    };
+    let nested = |key: &str| {
+        let mut nested = path.to_vec();
+        nested.push(Step::Key(key.to_string()));
+        nested
+    };
+    for (key, value) in city {
+        match key.as_str() {
+            Some("links") => {
+                for (index, item) in items(value) {
+                    let mut path = nested("links");
+                    path.push(Step::Index(index));
+                    check::<LinkConfig>(item, &path, unknown);
+                }
+            }
+            Some("etiquette") => {
+                for (index, item) in items(value) {
+                    let mut path = nested("etiquette");
+                    path.push(Step::Index(index));
+                    check::<Etiquette>(item, &path, unknown);
+                }
+            }
+            Some("second") => {
+                check::<SecondZoneConfig>(value, &nested("second"), unknown);
+            }
+            Some("style") => {
+                check::<Style>(value, &nested("style"), unknown);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn items(value: &Value) -> impl Iterator<Item = (usize, &Value)> {
+    value.as_sequence().into_iter().flatten().enumerate()
+}
+
+/// Record the keys of the mapping `value` that `T` has no field for
+fn check<'a, T: DeserializeOwned>(
+    value: &'a Value,
+    path: &[Step],
+    unknown: &mut Vec<Path>,
+) -> Option<&'a Mapping> {
+    let mapping = value.as_mapping()?;
+    let known = fields::<T>();
+    for key in mapping.keys() {
+        if let Some(key) = key.as_str()
+            && !known.contains(&key)
+        {
+            let mut steps = path.to_vec();
+            steps.push(Step::Key(key.to_string()));
+            unknown.push(Path(steps));
+        }
+    }
+    Some(mapping)
+}
+
+/// The field names serde derived for the struct `T`
+fn fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    // Deserializing stops with an error as soon as the names are known
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+impl<'de> Deserializer<'de> for FieldNames<'_> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = fields;
+        Err(de::Error::custom("done"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// Index of the line where `path` is in a block-style YAML document
+fn locate(lines: &[&str], path: &[Step]) -> Option<usize> {
+    // Content as (line, indent, text), with `- key: value` split into the
+    // item marker and `key: value` two columns further in
+    let mut content: Vec<(usize, usize, &str)> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let text = line.trim_start();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+        let mut indent = line.len() - text.len();
+        let mut text = text;
+        while text == "-" || text.starts_with("- ") {
+            content.push((index, indent, "-"));
+            let rest = text[1..].trim_start();
+            indent += text.len() - rest.len();
+            text = rest;
+        }
+        if !text.is_empty() {
+            content.push((index, indent, text));
+        }
+    }
+
+    let mut block = &content[..];
+    let mut line = None;
+    for step in path {
+        let indent = block.first()?.1;
+        let mut children = block
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.1 == indent);
+        let (start, entry) = match step {
+            Step::Key(key) => children.find(|(_, entry)| {
+                entry
+                    .2
+                    .strip_prefix(key.as_str())
+                    .is_some_and(|rest| rest.trim_start().starts_with(':'))
+            })?,
+            Step::Index(index) => children.filter(|(_, entry)| entry.2 == "-").nth(*index)?,
+        };
+        line = Some(entry.0);
+        let rest = &block[start + 1..];
+        let end = rest
+            .iter()
+            .position(|other| other.1 <= indent)
+            .unwrap_or(rest.len());
+        block = &rest[..end];
+        // The last step may have no block under it, e.g. `timezone: UTC`
+        if block.is_empty() {
+            break;
+        }
+    }
+    line
+}
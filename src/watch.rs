@@ -0,0 +1,110 @@
+//! Terminal world clock, redrawn in place.
+//!
+//! SIGUSR1 forces an immediate redraw and SIGHUP reloads the config, so
+//! scripts can poke a running clock without restarting it.
+
+use crate::render::{self, ResolvedCities};
+use crate::signals::{Event, Signals};
+use crate::{Config, bigdigits};
+use chrono::Local;
+use std::fmt::Write as _;
+use std::io::{self, BufWriter, Write};
+use std::time::Duration;
+
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const CURSOR_HOME: &str = "\x1b[H";
+const CLEAR_LINE: &str = "\x1b[K";
+const CLEAR_BELOW: &str = "\x1b[J";
+
+/// `reload` is called on SIGHUP to get the new config
+pub fn run(
+    mut config: Config,
+    reload: impl Fn() -> Config,
+    interval: Duration,
+    color: bool,
+) -> Result<(), String> {
+    let signals = Signals::register()?;
+    loop {
+        let resolved = ResolvedCities::new(&config);
+        let mut clocks = Vec::new();
+        redraw_loop(interval, &signals, |text| {
+            render::render_table_into(text, &resolved, Local::now(), &mut clocks, color);
+            Ok(())
+        })?;
+        config = reload();
+    }
+}
+
+/// Large digits of a single city's time, for presentation screens and standups
+pub fn run_bigtime(
+    mut config: Config,
+    reload: impl Fn() -> Config,
+    city: Option<&str>,
+    interval: Duration,
+) -> Result<(), String> {
+    let signals = Signals::register()?;
+    loop {
+        redraw_loop(interval, &signals, |text| {
+            render_bigtime(text, &config, city)
+        })?;
+        config = reload();
+    }
+}
+
+fn render_bigtime(text: &mut String, config: &Config, city: Option<&str>) -> Result<(), String> {
+    let now = Local::now();
+    let clocks = render::select_clocks(config, now, city)?;
+    let clock = clocks.first().ok_or("no cities configured")?;
+
+    text.clear();
+    text.push('\n');
+    for line in bigdigits::render(&clock.time.format("%H:%M:%S").to_string(), '#', 2) {
+        let _ = writeln!(text, "  {}", line);
+    }
+    let _ = write!(
+        text,
+        "\n  {}  {}\n",
+        clock.city.name,
+        clock.time.format("%A %d %B")
+    );
+    Ok(())
+}
+
+/// Redraw the text produced by `render_frame` every `interval`, until SIGHUP
+/// asks for the config to be reloaded
+///
+/// Both the text and the frame buffers are reused between redraws, and each
+/// frame is written to the terminal in a single flush.
+fn redraw_loop(
+    interval: Duration,
+    signals: &Signals,
+    mut render_frame: impl FnMut(&mut String) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    let _ = write!(stdout, "{}", CLEAR_SCREEN);
+
+    let mut text = String::new();
+    let mut frame = String::new();
+    loop {
+        render_frame(&mut text)?;
+
+        // Overwrite the previous frame line by line instead of clearing the
+        // screen, so there is no visible flicker between redraws
+        frame.clear();
+        frame.push_str(CURSOR_HOME);
+        for line in text.lines() {
+            frame.push_str(line);
+            frame.push_str(CLEAR_LINE);
+            frame.push('\n');
+        }
+        frame.push_str(CLEAR_BELOW);
+
+        stdout
+            .write_all(frame.as_bytes())
+            .and_then(|_| stdout.flush())
+            .map_err(|e| e.to_string())?;
+        if let Event::Reload = signals.wait(interval) {
+            return Ok(());
+        }
+    }
+}